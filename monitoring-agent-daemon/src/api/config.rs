@@ -0,0 +1,37 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use log::{error, info};
+
+use crate::api::StateApi;
+use crate::common::configuration::MonitoringConfig;
+
+/**
+ * Reload the configuration. The configuration file is read and validated again, and only the jobs of added,
+ * removed or changed monitors are changed.
+ * 
+ * `state`: The state object.
+ * 
+ * Returns ok if the configuration was reloaded or bad request with the error if the reload failed.
+ */
+#[post("/config/reload")]
+pub async fn reload_config(state: web::Data<StateApi>) -> impl Responder {
+    match state.scheduling_service.lock().await.reload_config(&state.config_path, state.config_format).await {
+        Ok(()) => {
+            info!("Configuration reloaded!");
+            HttpResponse::Ok().body("Configuration reloaded")
+        }
+        Err(err) => {
+            error!("Error reloading configuration: {:?}", err);
+            HttpResponse::BadRequest().body(err.get_message())
+        }
+    }
+}
+
+/**
+ * Get the JSON schema of the configuration file.
+ * 
+ * Returns the JSON schema.
+ */
+#[get("/config/schema")]
+pub async fn get_config_schema() -> impl Responder {
+    HttpResponse::Ok().json(MonitoringConfig::get_schema())
+}
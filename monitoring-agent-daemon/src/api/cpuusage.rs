@@ -0,0 +1,20 @@
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::api::StateApi;
+use crate::api::response::CpuUsageResponse;
+
+/**
+ * Get current cpu usage, measured over one second.
+ * 
+ * `state`: The state object.
+ * 
+ * Returns the current cpu usage or an error.
+ */
+#[get("/cpu/usage/current")]
+pub async fn get_current_cpu_usage(state: web::Data<StateApi>) -> impl Responder {
+    let usage = state.monitoring_service.get_current_cpu_usage().await;
+    match usage {
+        Ok(usage) => HttpResponse::Ok().json(CpuUsageResponse::from_cpu_usage(&usage)),
+        Err(err) => HttpResponse::InternalServerError().body(format!("Error occured: {err:?}")),
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt::Write;
+
+use actix_web::{get, web, HttpResponse, Responder};
+use monitoring_agent_lib::proc::{ProcsLoadavg, ProcsMeminfo};
+
+use crate::api::StateApi;
+use crate::common::{MonitorStatus, Status};
+
+/// Content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prefix used for all exported metrics.
+const METRIC_PREFIX: &str = "monitoring_agent";
+
+/// All status labels. One sample is exported per status label for every monitor.
+const STATUS_LABELS: [&str; 6] = ["ok", "unknown", "warning", "error", "flapping", "paused"];
+
+/**
+ * Get all metrics in the Prometheus text exposition format.
+ *
+ * `state`: The state object.
+ *
+ * Returns the monitor statuses and the current system readings.
+ */
+#[get("/metrics")]
+pub async fn get_metrics(state: web::Data<StateApi>) -> impl Responder {
+    let monitor_statuses = state.monitoring_service.get_all_monitorstatuses();
+    let meminfo = state.monitoring_service.get_current_meminfo().ok();
+    let loadavg = state.monitoring_service.get_current_loadavg().ok();
+    let number_of_processes = state.monitoring_service.get_processes().ok().map(|processes| processes.len());
+    let metrics = PrometheusMetrics::new(&state.server_name)
+        .add_monitor_statuses(&monitor_statuses)
+        .add_meminfo(meminfo.as_ref())
+        .add_loadavg(loadavg.as_ref())
+        .add_number_of_processes(number_of_processes)
+        .render();
+    HttpResponse::Ok().content_type(PROMETHEUS_CONTENT_TYPE).body(metrics)
+}
+
+/**
+ * Prometheus metrics builder.
+ *
+ * Collects metric families and renders them in the Prometheus text exposition format.
+ * All samples are labelled with the server name.
+ *
+ * `server_name`: The server name used as label on all samples.
+ * `families`: The metric families added so far.
+ */
+struct PrometheusMetrics {
+    /// The server name.
+    server_name: String,
+    /// The metric families.
+    families: Vec<MetricFamily>,
+}
+
+/**
+ * The type of a metric family.
+ */
+#[derive(Clone, Copy)]
+enum MetricType {
+    /// A value that can go up and down.
+    Gauge,
+    /// A value that only goes up, until the agent is restarted.
+    Counter,
+}
+
+impl MetricType {
+    /**
+     * Get the type name used in the TYPE line.
+     *
+     * Returns the type name.
+     */
+    fn get_name(self) -> &'static str {
+        match self {
+            MetricType::Gauge => "gauge",
+            MetricType::Counter => "counter",
+        }
+    }
+}
+
+/**
+ * A single metric family. Contains the help text and all samples of the metric.
+ */
+struct MetricFamily {
+    /// The metric name without prefix.
+    name: String,
+    /// The help text.
+    help: String,
+    /// The metric type.
+    metric_type: MetricType,
+    /// The samples as label pairs and value.
+    samples: Vec<(Vec<(String, String)>, f64)>,
+}
+
+impl PrometheusMetrics {
+    /**
+     * Create a new metrics builder.
+     *
+     * `server_name`: The server name.
+     *
+     * Returns a new `PrometheusMetrics`.
+     */
+    fn new(server_name: &str) -> PrometheusMetrics {
+        PrometheusMetrics {
+            server_name: server_name.to_string(),
+            families: Vec::new(),
+        }
+    }
+
+    /**
+     * Add the monitor statuses.
+     *
+     * `monitor_statuses`: The monitor statuses.
+     *
+     * Returns the builder.
+     */
+    fn add_monitor_statuses(mut self, monitor_statuses: &[MonitorStatus]) -> PrometheusMetrics {
+        let mut monitor_statuses: Vec<&MonitorStatus> = monitor_statuses.iter().collect();
+        monitor_statuses.sort_by(|first, second| first.name.cmp(&second.name));
+        let mut up = Vec::new();
+        let mut status = Vec::new();
+        let mut last_successful_time = Vec::new();
+        let mut last_error_time = Vec::new();
+        let mut skipped_checks = Vec::new();
+        let mut timed_out_checks = Vec::new();
+        for monitor_status in monitor_statuses {
+            let monitor_label = vec![("monitor".to_string(), monitor_status.name.clone())];
+            up.push((monitor_label.clone(), if monitor_status.status == Status::Ok { 1.0 } else { 0.0 }));
+            let current_status_label = PrometheusMetrics::get_status_label(&monitor_status.status);
+            for status_label in STATUS_LABELS {
+                let mut labels = monitor_label.clone();
+                labels.push(("status".to_string(), status_label.to_string()));
+                status.push((labels, if status_label == current_status_label { 1.0 } else { 0.0 }));
+            }
+            if let Some(time) = monitor_status.last_successful_time {
+                last_successful_time.push((monitor_label.clone(), PrometheusMetrics::get_timestamp(time)));
+            }
+            if let Some(time) = monitor_status.last_error_time {
+                last_error_time.push((monitor_label.clone(), PrometheusMetrics::get_timestamp(time)));
+            }
+            skipped_checks.push((monitor_label.clone(), PrometheusMetrics::get_count(monitor_status.skipped_checks)));
+            timed_out_checks.push((monitor_label.clone(), PrometheusMetrics::get_count(monitor_status.timed_out_checks)));
+        }
+        self.add_family("monitor_up", "Whether the monitor status is ok (1) or not (0).", MetricType::Gauge, up);
+        self.add_family("monitor_status", "The current status of the monitor.", MetricType::Gauge, status);
+        self.add_family("monitor_last_successful_time_seconds", "Unix time of the last successful check.", MetricType::Gauge, last_successful_time);
+        self.add_family("monitor_last_error_time_seconds", "Unix time of the last failed check.", MetricType::Gauge, last_error_time);
+        self.add_family("monitor_skipped_checks_total", "Scheduled checks skipped because the previous check was still running.", MetricType::Counter, skipped_checks);
+        self.add_family("monitor_timed_out_checks_total", "Checks that timed out.", MetricType::Counter, timed_out_checks);
+        self
+    }
+
+    /**
+     * Add the memory information.
+     *
+     * `meminfo`: The memory information. Nothing is added if missing.
+     *
+     * Returns the builder.
+     */
+    fn add_meminfo(mut self, meminfo: Option<&ProcsMeminfo>) -> PrometheusMetrics {
+        let Some(meminfo) = meminfo else { return self };
+        self.add_kilobytes("memory_total_bytes", "Total memory.", meminfo.memtotal);
+        self.add_kilobytes("memory_free_bytes", "Free memory.", meminfo.memfree);
+        self.add_kilobytes("memory_available_bytes", "Available memory.", meminfo.memavailable);
+        self.add_kilobytes("swap_total_bytes", "Total swap.", meminfo.swaptotal);
+        self.add_kilobytes("swap_free_bytes", "Free swap.", meminfo.swapfree);
+        self
+    }
+
+    /**
+     * Add the load average.
+     *
+     * `loadavg`: The load average. Nothing is added if missing.
+     *
+     * Returns the builder.
+     */
+    fn add_loadavg(mut self, loadavg: Option<&ProcsLoadavg>) -> PrometheusMetrics {
+        let Some(loadavg) = loadavg else { return self };
+        self.add_value("loadavg_1min", "Load average for the last minute.", loadavg.loadavg1min.map(f64::from));
+        self.add_value("loadavg_5min", "Load average for the last 5 minutes.", loadavg.loadavg5min.map(f64::from));
+        self.add_value("loadavg_10min", "Load average for the last 10 minutes.", loadavg.loadavg10min.map(f64::from));
+        self.add_value("running_processes", "Number of currently running processes.", loadavg.current_running_processes.map(f64::from));
+        self
+    }
+
+    /**
+     * Add the number of processes.
+     *
+     * `number_of_processes`: The number of processes. Nothing is added if missing.
+     *
+     * Returns the builder.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    fn add_number_of_processes(mut self, number_of_processes: Option<usize>) -> PrometheusMetrics {
+        self.add_value("processes", "Number of processes.", number_of_processes.map(|number| number as f64));
+        self
+    }
+
+    /**
+     * Add a value given in kilobytes as bytes.
+     *
+     * `name`: The metric name.
+     * `help`: The help text.
+     * `value`: The value in kilobytes.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    fn add_kilobytes(&mut self, name: &str, help: &str, value: Option<u64>) {
+        self.add_value(name, help, value.map(|value| (value * 1024) as f64));
+    }
+
+    /**
+     * Add a single unlabelled value.
+     *
+     * `name`: The metric name.
+     * `help`: The help text.
+     * `value`: The value. Nothing is added if missing.
+     */
+    fn add_value(&mut self, name: &str, help: &str, value: Option<f64>) {
+        if let Some(value) = value {
+            self.add_family(name, help, MetricType::Gauge, vec![(Vec::new(), value)]);
+        }
+    }
+
+    /**
+     * Add a metric family.
+     *
+     * `name`: The metric name.
+     * `help`: The help text.
+     * `metric_type`: The metric type.
+     * `samples`: The samples. Nothing is added if empty.
+     */
+    fn add_family(&mut self, name: &str, help: &str, metric_type: MetricType, samples: Vec<(Vec<(String, String)>, f64)>) {
+        if samples.is_empty() {
+            return;
+        }
+        self.families.push(MetricFamily {
+            name: name.to_string(),
+            help: help.to_string(),
+            metric_type,
+            samples,
+        });
+    }
+
+    /**
+     * Render the metrics in the Prometheus text exposition format.
+     *
+     * Returns the rendered metrics.
+     */
+    fn render(&self) -> String {
+        let mut output = String::new();
+        for family in &self.families {
+            let name = format!("{METRIC_PREFIX}_{}", family.name);
+            let _ = writeln!(output, "# HELP {name} {}", family.help);
+            let _ = writeln!(output, "# TYPE {name} {}", family.metric_type.get_name());
+            for (labels, value) in &family.samples {
+                let labels = std::iter::once(("server".to_string(), self.server_name.clone()))
+                    .chain(labels.iter().cloned())
+                    .map(|(key, value)| format!("{key}=\"{}\"", PrometheusMetrics::escape_label_value(&value)))
+                    .collect::<Vec<String>>()
+                    .join(",");
+                let _ = writeln!(output, "{name}{{{labels}}} {value}");
+            }
+        }
+        output
+    }
+
+    /**
+     * Escape a label value. Backslash, double quote and line feed must be escaped.
+     *
+     * `value`: The label value.
+     *
+     * Returns the escaped label value.
+     */
+    fn escape_label_value(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    }
+
+    /**
+     * Get the status label.
+     *
+     * `status`: The status.
+     *
+     * Returns the label value used for the status.
+     */
+    fn get_status_label(status: &Status) -> &'static str {
+        match status {
+            Status::Ok => "ok",
+            Status::Unknown => "unknown",
+            Status::Warning { message: _ } => "warning",
+            Status::Error { message: _ } => "error",
+            Status::Flapping { message: _ } => "flapping",
+            Status::Paused => "paused",
+        }
+    }
+
+    /**
+     * Get a count as a sample value.
+     *
+     * `count`: The count.
+     *
+     * Returns the count.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    fn get_count(count: u64) -> f64 {
+        count as f64
+    }
+
+    /**
+     * Get a unix timestamp in seconds.
+     *
+     * `time`: The time.
+     *
+     * Returns the seconds since epoch.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    fn get_timestamp(time: chrono::DateTime<chrono::Utc>) -> f64 {
+        time.timestamp_millis() as f64 / 1000.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn test_render_monitor_statuses() {
+        let mut monitor_status = MonitorStatus::new("tcp \"local\"".to_string(), Status::Error { message: "error".to_string() });
+        monitor_status.last_error_time = Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap());
+        monitor_status.skipped_checks = 3;
+        let metrics = PrometheusMetrics::new("server1")
+            .add_monitor_statuses(&[monitor_status])
+            .render();
+        assert!(metrics.contains("# TYPE monitoring_agent_monitor_up gauge\n"));
+        assert!(metrics.contains("monitoring_agent_monitor_up{server=\"server1\",monitor=\"tcp \\\"local\\\"\"} 0\n"));
+        assert!(metrics.contains("monitoring_agent_monitor_status{server=\"server1\",monitor=\"tcp \\\"local\\\"\",status=\"error\"} 1\n"));
+        assert!(metrics.contains("monitoring_agent_monitor_status{server=\"server1\",monitor=\"tcp \\\"local\\\"\",status=\"ok\"} 0\n"));
+        assert!(metrics.contains("monitoring_agent_monitor_last_error_time_seconds{server=\"server1\",monitor=\"tcp \\\"local\\\"\"} 1700000000\n"));
+        assert!(!metrics.contains("monitoring_agent_monitor_last_successful_time_seconds"));
+        assert!(metrics.contains("# TYPE monitoring_agent_monitor_skipped_checks_total counter\n"));
+        assert!(metrics.contains("monitoring_agent_monitor_skipped_checks_total{server=\"server1\",monitor=\"tcp \\\"local\\\"\"} 3\n"));
+        assert!(metrics.contains("# TYPE monitoring_agent_monitor_timed_out_checks_total counter\n"));
+        assert!(metrics.contains("monitoring_agent_monitor_timed_out_checks_total{server=\"server1\",monitor=\"tcp \\\"local\\\"\"} 0\n"));
+    }
+
+    #[test]
+    fn test_render_system_readings() {
+        let meminfo = ProcsMeminfo::new(Some(100), Some(50), None, Some(10), Some(5));
+        let loadavg = ProcsLoadavg::new(Some(0.5), Some(1.0), Some(1.5), Some(2), Some(300));
+        let metrics = PrometheusMetrics::new("server1")
+            .add_meminfo(Some(&meminfo))
+            .add_loadavg(Some(&loadavg))
+            .add_number_of_processes(Some(42))
+            .render();
+        assert!(metrics.contains("monitoring_agent_memory_total_bytes{server=\"server1\"} 102400\n"));
+        assert!(metrics.contains("monitoring_agent_swap_free_bytes{server=\"server1\"} 5120\n"));
+        assert!(!metrics.contains("monitoring_agent_memory_available_bytes"));
+        assert!(metrics.contains("monitoring_agent_loadavg_1min{server=\"server1\"} 0.5\n"));
+        assert!(metrics.contains("monitoring_agent_running_processes{server=\"server1\"} 2\n"));
+        assert!(metrics.contains("monitoring_agent_processes{server=\"server1\"} 42\n"));
+    }
+
+    #[test]
+    fn test_render_empty() {
+        let metrics = PrometheusMetrics::new("server1")
+            .add_monitor_statuses(&[])
+            .add_meminfo(None)
+            .add_loadavg(None)
+            .add_number_of_processes(None)
+            .render();
+        assert!(metrics.is_empty());
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(PrometheusMetrics::escape_label_value("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+}
@@ -6,21 +6,31 @@
  * `response`: The response API.
  * `cpuinfo`: The cpu information API.
  * `loadavg`: The load average API.
+ * `cpuusage`: The cpu usage API.
  * `process`: The process API.
+ * `monitor`: The monitor status API.
+ * `metrics`: The Prometheus metrics API.
+ * `config`: The configuration API.
  */
 mod meminfo;
 mod state;
 mod response;
 mod cpuinfo;
 mod loadavg;
+mod cpuusage;
 mod process;
 mod monitor;
+mod metrics;
+mod config;
 
 pub use crate::api::meminfo::get_current_meminfo;
 pub use crate::api::cpuinfo::get_current_cpuinfo;
 pub use crate::api::loadavg::get_current_loadavg;
+pub use crate::api::cpuusage::get_current_cpu_usage;
 pub use crate::api::process::{get_processes, get_process, get_threads};
-pub use crate::api::monitor::get_monitor_status;
+pub use crate::api::monitor::{get_monitor_status, get_monitor_history, run_monitor, pause_monitor, resume_monitor};
+pub use crate::api::metrics::get_metrics;
+pub use crate::api::config::{get_config_schema, reload_config};
 
 #[allow(clippy::module_name_repetitions)]
 pub use crate::api::state::StateApi;
@@ -1,10 +1,111 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 
 use crate::api::StateApi;
-use crate::api::response::MonitorResponse;
+use crate::common::ApplicationError;
+use crate::api::response::{MonitorHistoryResponse, MonitorResponse};
+
+/**
+ * Query parameters for the monitor history.
+ */
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Only return results after this time.
+    since: Option<DateTime<Utc>>,
+    /// The maximum number of results.
+    limit: Option<usize>,
+}
 
 #[get("/monitors/status")]
 pub async fn get_monitor_status(state: web::Data<StateApi>) -> impl Responder {
     let monitor_statuses = state.monitoring_service.get_all_monitorstatuses();
     HttpResponse::Ok().json(MonitorResponse::from_monitor_status_messages(&monitor_statuses))    
-}
\ No newline at end of file
+}
+
+/**
+ * Get the check history of a monitor.
+ * 
+ * `state`: The state object.
+ * `path`: The name of the monitor.
+ * `query`: The since and limit query parameters.
+ * 
+ * Returns the check history or not found if the monitor does not exist.
+ */
+#[get("/monitors/{name}/history")]
+pub async fn get_monitor_history(state: web::Data<StateApi>, path: web::Path<String>, query: web::Query<HistoryQuery>) -> impl Responder {
+    let name = path.into_inner();
+    match state.monitoring_service.get_monitor_history(&name, query.since, query.limit) {
+        Some(history) => HttpResponse::Ok().json(MonitorHistoryResponse::from_history_entries(&history)),
+        None => HttpResponse::NotFound().body(format!("Monitor not found: {name}")),
+    }
+}
+
+/**
+ * Run the check of a monitor outside of its schedule.
+ * 
+ * `state`: The state object.
+ * `path`: The name of the monitor.
+ * 
+ * Returns the status of the monitor after the check, not found if the monitor does not exist or conflict if the monitor is paused or a check of the monitor is in progress.
+ */
+#[post("/monitors/{name}/run")]
+pub async fn run_monitor(state: web::Data<StateApi>, path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    let running_checks = state.scheduling_service.lock().await.get_running_checks();
+    match state.monitoring_service.run_monitor(&name, &running_checks).await {
+        Ok(Some(monitor_status)) => HttpResponse::Ok().json(MonitorResponse::from_monitor_status_message(&monitor_status)),
+        Ok(None) => HttpResponse::NotFound().body(format!("Monitor not found: {name}")),
+        Err(err) => HttpResponse::Conflict().body(err.get_message()),
+    }
+}
+
+/**
+ * Pause a monitor. The monitor is not checked until it is resumed.
+ * 
+ * `state`: The state object.
+ * `path`: The name of the monitor.
+ * 
+ * Returns the status of the monitor or not found if the monitor does not exist.
+ */
+#[post("/monitors/{name}/pause")]
+pub async fn pause_monitor(state: web::Data<StateApi>, path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    let result = state.scheduling_service.lock().await.pause_monitor(&name).await;
+    get_monitor_change_response(&state, &name, result)
+}
+
+/**
+ * Resume a paused monitor.
+ * 
+ * `state`: The state object.
+ * `path`: The name of the monitor.
+ * 
+ * Returns the status of the monitor or not found if the monitor does not exist.
+ */
+#[post("/monitors/{name}/resume")]
+pub async fn resume_monitor(state: web::Data<StateApi>, path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    let result = state.scheduling_service.lock().await.resume_monitor(&name).await;
+    get_monitor_change_response(&state, &name, result)
+}
+
+/**
+ * Get the response after pausing or resuming a monitor.
+ * 
+ * `state`: The state object.
+ * `name`: The name of the monitor.
+ * `result`: The result of pausing or resuming the monitor.
+ * 
+ * Returns the status of the monitor, not found if the monitor does not exist or internal server error if the change failed.
+ */
+fn get_monitor_change_response(state: &StateApi, name: &str, result: Result<bool, ApplicationError>) -> HttpResponse {
+    match result {
+        Ok(true) => match state.monitoring_service.get_monitorstatus(name) {
+            Some(monitor_status) => HttpResponse::Ok().json(MonitorResponse::from_monitor_status_message(&monitor_status)),
+            None => HttpResponse::NotFound().body(format!("Monitor not found: {name}")),
+        },
+        Ok(false) => HttpResponse::NotFound().body(format!("Monitor not found: {name}")),
+        Err(err) => HttpResponse::InternalServerError().body(err.get_message()),
+    }
+}
@@ -1,8 +1,10 @@
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
-use monitoring_agent_lib::proc::{process::ProcessState, ProcsCpuinfo, ProcsLoadavg, ProcsMeminfo, ProcsProcess};
+use monitoring_agent_lib::proc::{process::ProcessState, stat::CpuUsage, ProcsCpuUsage, ProcsCpuinfo, ProcsLoadavg, ProcsMeminfo, ProcsProcess};
 use serde::{Deserialize, Serialize};
 
-use crate::common::{MonitorStatus, Status};
+use crate::common::{MonitorHistoryEntry, MonitorStatus, Status};
 
 /**
  * The `MeminfoResponse` struct represents the response of the meminfo endpoint.
@@ -210,6 +212,77 @@ impl LoadavgResponse {
 
 }
 
+/**
+ * The `CpuUsageResponse` struct represents the response of the cpu usage endpoint.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct CpuUsageResponse {
+    /// The usage of all cpus together.
+    pub total: CpuPercentagesResponse,
+    /// The usage of each core.
+    pub cores: Vec<CpuPercentagesResponse>,
+}
+
+/**
+ * The usage of a single cpu in percent.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct CpuPercentagesResponse {
+    /// The name of the cpu, cpu for all cpus or cpu0, cpu1... for a single core.
+    pub name: String,
+    /// Percentage in user mode, including low priority.
+    pub user: f64,
+    /// Percentage in system mode, including interrupts.
+    pub system: f64,
+    /// Percentage idle waiting for I/O.
+    pub iowait: f64,
+    /// Percentage stolen by the hypervisor.
+    pub steal: f64,
+    /// Percentage idle.
+    pub idle: f64,
+    /// Percentage in use, i.e. neither idle nor waiting for I/O.
+    pub used: f64,
+}
+
+impl CpuUsageResponse {
+    /**
+     * Create a new `CpuUsageResponse` from a `ProcsCpuUsage`.
+     *
+     * `usage`: The `ProcsCpuUsage` object.
+     * 
+     * Returns a new `CpuUsageResponse`.
+     */
+    pub fn from_cpu_usage(usage: &ProcsCpuUsage) -> CpuUsageResponse {
+        CpuUsageResponse {
+            total: CpuPercentagesResponse::from_cpu(&usage.total),
+            cores: usage.cores.iter().map(CpuPercentagesResponse::from_cpu).collect(),
+        }
+    }
+}
+
+impl CpuPercentagesResponse {
+    /**
+     * Create a new `CpuPercentagesResponse` from a `CpuUsage`.
+     *
+     * `cpu`: The `CpuUsage` object.
+     * 
+     * Returns a new `CpuPercentagesResponse`.
+     */
+    pub fn from_cpu(cpu: &CpuUsage) -> CpuPercentagesResponse {
+        CpuPercentagesResponse {
+            name: cpu.name.clone(),
+            user: cpu.user,
+            system: cpu.system,
+            iowait: cpu.iowait,
+            steal: cpu.steal,
+            idle: cpu.idle,
+            used: cpu.get_percent_used(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(clippy::module_name_repetitions)]
 pub struct ProcessResponse {
@@ -375,6 +448,27 @@ pub struct MonitorResponse {
     /// The last time the monitor encountered an error.
     #[serde(skip_serializing_if = "Option::is_none", rename = "lastErrorTime")]
     last_error_time: Option<DateTime<Utc>>,
+    /// The last warning message.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "lastWarning")]
+    last_warning: Option<String>,
+    /// The last time the monitor reported a warning.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "lastWarningTime")]
+    last_warning_time: Option<DateTime<Utc>>,
+    /// The number of consecutive failed checks not yet causing an error status.
+    #[serde(rename = "pendingFailures", default)]
+    pending_failures: u32,
+    /// The number of consecutive successful checks not yet clearing the error status.
+    #[serde(rename = "pendingSuccesses", default)]
+    pending_successes: u32,
+    /// The duration of the last check in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "lastDuration")]
+    last_duration: Option<u64>,
+    /// The number of scheduled checks skipped because the previous check was still in progress.
+    #[serde(rename = "skippedChecks", default)]
+    skipped_checks: u64,
+    /// The number of checks that timed out.
+    #[serde(rename = "timedOutChecks", default)]
+    timed_out_checks: u64,
 }
 
 impl MonitorResponse {
@@ -386,14 +480,29 @@ impl MonitorResponse {
      * `last_successful_time`: The last time the monitor was successful.
      * `last_error`: The last error message.
      * `last_error_time`: The last time the monitor encountered an error.
+     * `last_warning`: The last warning message.
+     * `last_warning_time`: The last time the monitor reported a warning.
+     * `pending_failures`: The number of consecutive failed checks not yet causing an error status.
+     * `pending_successes`: The number of consecutive successful checks not yet clearing the error status.
+     * `last_duration`: The duration of the last check in milliseconds.
+     * `skipped_checks`: The number of scheduled checks skipped because the previous check was still in progress.
+     * `timed_out_checks`: The number of checks that timed out.
      * 
      */
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         status: MonitorStatusResponse,
         last_successful_time: Option<DateTime<Utc>>,
         last_error: Option<String>,
         last_error_time: Option<DateTime<Utc>>,
+        last_warning: Option<String>,
+        last_warning_time: Option<DateTime<Utc>>,
+        pending_failures: u32,
+        pending_successes: u32,
+        last_duration: Option<u64>,
+        skipped_checks: u64,
+        timed_out_checks: u64,
     ) -> MonitorResponse {
         MonitorResponse {
             name,
@@ -401,6 +510,13 @@ impl MonitorResponse {
             last_successful_time,
             last_error,
             last_error_time,
+            last_warning,
+            last_warning_time,
+            pending_failures,
+            pending_successes,
+            last_duration,
+            skipped_checks,
+            timed_out_checks,
         }
     }
 
@@ -419,6 +535,13 @@ impl MonitorResponse {
             monitor_status.last_successful_time,
             monitor_status.last_error.clone(),
             monitor_status.last_error_time,
+            monitor_status.last_warning.clone(),
+            monitor_status.last_warning_time,
+            monitor_status.get_pending_failures(),
+            monitor_status.get_pending_successes(),
+            monitor_status.last_duration.map(get_duration_ms),
+            monitor_status.skipped_checks,
+            monitor_status.timed_out_checks,
         )
     }
 
@@ -435,6 +558,70 @@ impl MonitorResponse {
     }
 }
 
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorHistoryResponse {
+    /// The time of the check.
+    #[serde(rename = "time")]
+    time: DateTime<Utc>,
+    /// The status returned by the check.
+    #[serde(rename = "status")]
+    status: MonitorStatusResponse,
+    /// The message returned by the check.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "message")]
+    message: Option<String>,
+    /// The duration of the check in milliseconds.
+    #[serde(rename = "duration")]
+    duration: u64,
+}
+
+impl MonitorHistoryResponse {
+    /**
+     * Create a new `MonitorHistoryResponse` from a `MonitorHistoryEntry`.
+     * 
+     * `entry`: The `MonitorHistoryEntry` object.
+     * 
+     * Returns a new `MonitorHistoryResponse`.
+     * 
+     */
+    pub fn from_history_entry(entry: &MonitorHistoryEntry) -> MonitorHistoryResponse {
+        let message = match &entry.status {
+            Status::Warning { message } | Status::Error { message } | Status::Flapping { message } => Some(message.clone()),
+            Status::Ok | Status::Unknown | Status::Paused => None,
+        };
+        MonitorHistoryResponse {
+            time: entry.time,
+            status: MonitorStatusResponse::from_status(&entry.status),
+            message,
+            duration: get_duration_ms(entry.duration),
+        }
+    }
+
+    /**
+     * Create a list of `MonitorHistoryResponse` from `MonitorHistoryEntry` objects.
+     * 
+     * `entries`: The `MonitorHistoryEntry` objects.
+     * 
+     * Returns a list of `MonitorHistoryResponse`.
+     * 
+     */
+    pub fn from_history_entries(entries: &[MonitorHistoryEntry]) -> Vec<MonitorHistoryResponse> {
+        entries.iter().map(MonitorHistoryResponse::from_history_entry).collect()
+    }
+}
+
+/**
+ * Get a duration in milliseconds.
+ * 
+ * `duration`: The duration.
+ * 
+ * Returns the duration in milliseconds.
+ * 
+ */
+fn get_duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MonitorStatusResponse {
@@ -442,8 +629,14 @@ pub enum MonitorStatusResponse {
     Ok,
     /// The monitor status is unknown.
     Unknown,
+    /// The monitor has a warning.
+    Warning,
     /// The monitor has an error.
     Error,
+    /// The monitor changes status too often.
+    Flapping,
+    /// The monitor is paused.
+    Paused,
 }
 
 impl MonitorStatusResponse {
@@ -459,13 +652,19 @@ impl MonitorStatusResponse {
         match status {
             Status::Ok => MonitorStatusResponse::Ok,
             Status::Unknown => MonitorStatusResponse::Unknown,
+            Status::Warning { message: _ } => MonitorStatusResponse::Warning,
             Status::Error { message: _ } => MonitorStatusResponse::Error,
+            Status::Flapping { message: _ } => MonitorStatusResponse::Flapping,
+            Status::Paused => MonitorStatusResponse::Paused,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
+    use std::collections::VecDeque;
+
     use super::*;
 
     #[test]
@@ -577,6 +776,7 @@ mod test {
             state: Some(ProcessState::Running),
             threads: Some(3),
             groups: Some(vec!["group1".to_string(), "group2".to_string()]),
+            command_line: None,
         };
         let process_response = ProcessResponse::from_process(&procs_process);
         assert_eq!(process_response.pid, Some(1));
@@ -613,6 +813,7 @@ mod test {
             state: Some(ProcessState::Running),
             threads: Some(3),
             groups: Some(vec!["group1".to_string(), "group2".to_string()]),
+            command_line: None,
         }];
         let process_response = ProcessResponse::from_processes(&procs_process);
         assert_eq!(process_response[0].pid, Some(1));
@@ -632,6 +833,21 @@ mod test {
             last_successful_time: Some(Utc::now()),
             last_error: Some("error".to_string()),
             last_error_time: Some(Utc::now()),
+            last_warning: None,
+            last_warning_time: None,
+            failure_threshold: 3,
+            success_threshold: 1,
+            consecutive_failures: 2,
+            consecutive_successes: 0,
+            check_status: Status::Ok,
+            flap_detection: None,
+            transitions: VecDeque::new(),
+            history: VecDeque::new(),
+            last_duration: Some(Duration::from_millis(120)),
+            skipped_checks: 4,
+            timed_out_checks: 1,
+            timeout: None,
+            check_in_progress: false,
         };
         let monitor_response = MonitorResponse::from_monitor_status_message(&monitor_status);
         assert_eq!(monitor_response.name, "name".to_string());
@@ -639,16 +855,28 @@ mod test {
         assert_eq!(monitor_response.last_successful_time.is_some(), true);
         assert_eq!(monitor_response.last_error, Some("error".to_string()));
         assert_eq!(monitor_response.last_error_time.is_some(), true);
+        assert_eq!(monitor_response.pending_failures, 2);
+        assert_eq!(monitor_response.pending_successes, 0);
+        assert_eq!(monitor_response.last_duration, Some(120));
+        assert_eq!(monitor_response.skipped_checks, 4);
+        assert_eq!(monitor_response.timed_out_checks, 1);
     }
 
     #[test]
     fn test_new_moniorresponse() {
-        let monitor_response = MonitorResponse::new("name".to_string(), MonitorStatusResponse::Ok, Some(Utc::now()), Some("error".to_string()), Some(Utc::now()));
+        let monitor_response = MonitorResponse::new("name".to_string(), MonitorStatusResponse::Ok, Some(Utc::now()), Some("error".to_string()), Some(Utc::now()), Some("warning".to_string()), Some(Utc::now()), 1, 0, Some(10), 2, 3);
         assert_eq!(monitor_response.name, "name".to_string());
         assert_eq!(monitor_response.status, MonitorStatusResponse::Ok);
         assert_eq!(monitor_response.last_successful_time.is_some(), true);
         assert_eq!(monitor_response.last_error, Some("error".to_string()));
         assert_eq!(monitor_response.last_error_time.is_some(), true);        
+        assert_eq!(monitor_response.last_warning, Some("warning".to_string()));
+        assert!(monitor_response.last_warning_time.is_some());
+        assert_eq!(monitor_response.pending_failures, 1);
+        assert_eq!(monitor_response.pending_successes, 0);
+        assert_eq!(monitor_response.last_duration, Some(10));
+        assert_eq!(monitor_response.skipped_checks, 2);
+        assert_eq!(monitor_response.timed_out_checks, 3);
     }
 
     #[test]
@@ -659,6 +887,21 @@ mod test {
             last_successful_time: Some(Utc::now()),
             last_error: Some("error".to_string()),
             last_error_time: Some(Utc::now()),
+            last_warning: None,
+            last_warning_time: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            check_status: Status::Ok,
+            flap_detection: None,
+            transitions: VecDeque::new(),
+            history: VecDeque::new(),
+            last_duration: None,
+            skipped_checks: 0,
+            timed_out_checks: 0,
+            timeout: None,
+            check_in_progress: false,
         }];
         let monitor_response = MonitorResponse::from_monitor_status_messages(&monitor_status);
         assert_eq!(monitor_response[0].name, "name".to_string());
@@ -668,10 +911,29 @@ mod test {
         assert_eq!(monitor_response[0].last_error_time.is_some(), true);        
     }
 
+    #[test]
+    fn test_from_history_entries() {
+        let entries = vec![
+            MonitorHistoryEntry { time: Utc::now(), status: Status::Ok, duration: Duration::from_millis(25) },
+            MonitorHistoryEntry { time: Utc::now(), status: Status::Error { message: "error".to_string() }, duration: Duration::from_secs(5) },
+        ];
+        let history_response = MonitorHistoryResponse::from_history_entries(&entries);
+        assert_eq!(history_response.len(), 2);
+        assert_eq!(history_response[0].status, MonitorStatusResponse::Ok);
+        assert_eq!(history_response[0].message, None);
+        assert_eq!(history_response[1].status, MonitorStatusResponse::Error);
+        assert_eq!(history_response[1].message, Some("error".to_string()));
+        assert_eq!(history_response[0].duration, 25);
+        assert_eq!(history_response[1].duration, 5000);
+    }
+
     #[test]
     fn test_from_status() {
         assert_eq!(MonitorStatusResponse::from_status(&Status::Ok), MonitorStatusResponse::Ok);
         assert_eq!(MonitorStatusResponse::from_status(&Status::Unknown), MonitorStatusResponse::Unknown);
+        assert_eq!(MonitorStatusResponse::from_status(&Status::Warning { message: "warning".to_string() }), MonitorStatusResponse::Warning);
         assert_eq!(MonitorStatusResponse::from_status(&Status::Error { message: "error".to_string() }), MonitorStatusResponse::Error);
+        assert_eq!(MonitorStatusResponse::from_status(&Status::Flapping { message: "flapping".to_string() }), MonitorStatusResponse::Flapping);
+        assert_eq!(MonitorStatusResponse::from_status(&Status::Paused), MonitorStatusResponse::Paused);
     }
 }
\ No newline at end of file
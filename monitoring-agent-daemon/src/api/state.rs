@@ -1,4 +1,7 @@
-use crate::services::MonitoringService;
+use std::sync::Arc;
+
+use crate::common::ConfigFormat;
+use crate::services::{MonitoringService, SchedulingService};
 
 /**
  * State object for the API modules.
@@ -7,6 +10,14 @@ use crate::services::MonitoringService;
 pub struct StateApi {
     /// Monitoring service object.
     pub monitoring_service: MonitoringService,
+    /// Scheduling service object. Used to pause and resume monitors.
+    pub scheduling_service: Arc<tokio::sync::Mutex<SchedulingService>>,
+    /// The server name. Used as label in the exported metrics.
+    pub server_name: String,
+    /// The path to the configuration file. Used when reloading the configuration.
+    pub config_path: String,
+    /// The format of the configuration file. If not given, the format is detected from the file extension.
+    pub config_format: Option<ConfigFormat>,
 }
 
 impl StateApi {
@@ -14,13 +25,21 @@ impl StateApi {
      * Constructor for `MeminfoApi`
      * 
      * @param `monitoring_service` `MonitoringService` The monitoring service object.
+     * @param `scheduling_service` `Arc<tokio::sync::Mutex<SchedulingService>>` The scheduling service object.
+     * @param `server_name` `&str` The server name.
+     * @param `config_path` `&str` The path to the configuration file.
+     * @param `config_format` `Option<ConfigFormat>` The format of the configuration file.
      * 
      * @return `StateApi`
      * 
      */
-    pub fn new(monitoring_service: MonitoringService) -> StateApi {
+    pub fn new(monitoring_service: MonitoringService, scheduling_service: Arc<tokio::sync::Mutex<SchedulingService>>, server_name: &str, config_path: &str, config_format: Option<ConfigFormat>) -> StateApi {
         StateApi {
-            monitoring_service
+            monitoring_service,
+            scheduling_service,
+            server_name: server_name.to_string(),
+            config_path: config_path.to_string(),
+            config_format,
         }
     }
 }
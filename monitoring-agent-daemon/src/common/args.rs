@@ -1,5 +1,7 @@
 use clap::Parser;
 
+use crate::common::ConfigFormat;
+
 /**
  * Application arguments.
  *
@@ -15,6 +17,10 @@ pub struct ApplicationArguments {
     #[arg(short = 'c', long, default_value = "/etc/monitoring-agent-daemon/config.json")]
     pub config: String,
 
+    /// Configuration file format. Detected from the file extension by default.
+    #[arg(short = 'f', long, value_enum)]
+    pub format: Option<ConfigFormat>,
+
     /// log4rs logfile.
     #[arg(short = 'l', long, default_value = "/var/log/monitoring-agent-daemon/monitoring-agent.log")]
     pub logfile: String,
@@ -38,9 +44,14 @@ pub struct ApplicationArguments {
     /// pid file. Only used when daemonizing the application.
     #[arg(short = 'p', long, default_value = "/var/run/monitoring-agent-daemon.pid")]
     pub pidfile: String,
+
+    /// Print the JSON schema of the configuration file and exit.
+    #[arg(long = "print-schema", default_value = "false")]
+    pub print_schema: bool,
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::needless_borrows_for_generic_args)]
 mod test {
 
     use super::*;
@@ -53,8 +64,15 @@ mod test {
         assert_eq!(args.daemon, true);
         assert_eq!(args.test, true);
         assert_eq!(args.pidfile, "/var/run/monitoring-agent-daemon.pid");
+        assert_eq!(args.format, None);
     }
 
+    #[test]
+    fn test_application_format_argument() {
+        let args = ApplicationArguments::try_parse_from(["monitoring-agent-daemon", "-c", "config.conf", "--format", "yaml"]).unwrap();
+        assert_eq!(args.format, Some(ConfigFormat::Yaml));
+        assert!(ApplicationArguments::try_parse_from(["monitoring-agent-daemon", "--format", "xml"]).is_err());
+    }
 
     #[test]
     fn test_application_default_arguments() {
@@ -64,6 +82,13 @@ mod test {
         assert_eq!(args.daemon, false);
         assert_eq!(args.test, false);
         assert_eq!(args.pidfile, "/var/run/monitoring-agent-daemon.pid");
+        assert!(!args.print_schema);
+    }
+
+    #[test]
+    fn test_application_print_schema_argument() {
+        let args = ApplicationArguments::parse_from(["monitoring-agent-daemon", "--print-schema"]);
+        assert!(args.print_schema);
     }
 
 }
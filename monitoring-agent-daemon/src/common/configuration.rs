@@ -1,9 +1,50 @@
 use log::debug;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SubschemaValidation};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::common::ApplicationError;
+use crate::common::{secrets, ApplicationError};
+
+/**
+ * Configuration file format.
+ *
+ * `Json`: The default format.
+ * `Yaml`: Used for files ending with .yaml or .yml.
+ * `Toml`: Used for files ending with .toml.
+ *
+ */
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /**
+     * Get the configuration format from the file extension.
+     *
+     * `path`: The path to the configuration file.
+     *
+     * result: The format. Json if the extension is not yaml, yml or toml.
+     */
+    pub fn from_path(path: &str) -> ConfigFormat {
+        let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
 
 /**
  * Monitor type.
@@ -15,14 +56,21 @@ use crate::common::ApplicationError;
  * `Sql`: Monitor a SQL connection.
  * `Command`: Monitor a command.
  * `LoadAvg`: Monitor the load average of the system. Can only be one.
+ * `Disk`: Monitor the disk space and inode usage of the filesystems.
+ * `Cpu`: Monitor the cpu usage from /proc/stat.
+ * `Certificate`: Monitor the expiry of TLS certificates, from a server or from PEM files.
+ * `Dns`: Monitor the resolution of a name.
+ * `Process`: Monitor the processes matching a name or command line.
  *
  */
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum MonitorType {
     Tcp {
         host: String,
         port: u16,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxResponseTime")]
+        max_response_time: Option<u64>,
     },
     Http {
         url: String,
@@ -42,7 +90,10 @@ pub enum MonitorType {
         #[serde(skip_serializing_if = "Option::is_none", rename = "identity")]
         identity: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none", rename = "identityPassword")]
+        #[schemars(schema_with = "secret_schema", default)]
         identity_password: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxResponseTime")]
+        max_response_time: Option<u64>,
     },
     Command {
         command: String,
@@ -58,6 +109,12 @@ pub enum MonitorType {
         threshold_5min: Option<f32>,
         #[serde(skip_serializing_if = "Option::is_none", rename = "threshold10min")]
         threshold_10min: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningThreshold1min")]
+        warning_threshold_1min: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningThreshold5min")]
+        warning_threshold_5min: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningThreshold10min")]
+        warning_threshold_10min: Option<f32>,
         #[serde(rename = "storeValues", default = "default_as_false")]
         store_values: bool,    
     },  
@@ -66,9 +123,102 @@ pub enum MonitorType {
         max_percentage_mem: Option<f64>,
         #[serde(skip_serializing_if = "Option::is_none", rename = "maxPercentageSwapUsed")]
         max_percentage_swap: Option<f64>,        
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageMemUsed")]
+        warning_percentage_mem: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageSwapUsed")]
+        warning_percentage_swap: Option<f64>,
         #[serde(rename = "storeValues", default = "default_as_false")]
         store_values: bool,    
     },   
+    Disk {
+        /// The mount points to check. If not given, then all real filesystems from /proc/mounts are checked.
+        #[serde(skip_serializing_if = "Option::is_none", rename = "mountPoints")]
+        mount_points: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxPercentageUsed")]
+        max_percentage_used: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxPercentageInodesUsed")]
+        max_percentage_inodes_used: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageUsed")]
+        warning_percentage_used: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageInodesUsed")]
+        warning_percentage_inodes_used: Option<f64>,
+        #[serde(rename = "storeValues", default = "default_as_false")]
+        store_values: bool,
+    },
+    Cpu {
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxPercentageUsed")]
+        max_percentage_used: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageUsed")]
+        warning_percentage_used: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxPercentageIowait")]
+        max_percentage_iowait: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageIowait")]
+        warning_percentage_iowait: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxPercentageSteal")]
+        max_percentage_steal: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "warningPercentageSteal")]
+        warning_percentage_steal: Option<f64>,
+        /// Check the thresholds for each core as well as for all cores together.
+        #[serde(rename = "perCore", default = "default_as_false")]
+        per_core: bool,
+        #[serde(rename = "storeValues", default = "default_as_false")]
+        store_values: bool,
+    },
+    Certificate {
+        /// The host to connect to and read the certificate chain from.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+        #[serde(default = "default_certificate_port")]
+        port: u16,
+        /// The server name sent with SNI and verified against the certificate. The host is used if not given.
+        #[serde(skip_serializing_if = "Option::is_none", rename = "serverName")]
+        server_name: Option<String>,
+        /// PEM files with certificates to check.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        files: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "rootCertificate")]
+        root_certificate: Option<String>,
+        #[serde(default = "default_as_false", rename = "acceptInvalidCerts")]
+        accept_invalid_certs: bool,
+        /// Days before expiry the status becomes warning.
+        #[serde(default = "default_certificate_warning_days", rename = "warningDays")]
+        warning_days: u32,
+        /// Days before expiry the status becomes error.
+        #[serde(default = "default_certificate_error_days", rename = "errorDays")]
+        error_days: u32,
+    },
+    Dns {
+        /// The name to resolve.
+        name: String,
+        #[serde(default = "default_dns_record_type", rename = "recordType")]
+        record_type: DnsRecordType,
+        /// The nameserver to ask, an ip address with an optional port. The system resolver is used if not given.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        nameserver: Option<String>,
+        /// Answers that must be among the answers returned.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expected: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxResponseTime")]
+        max_response_time: Option<u64>,
+    },
+    Process {
+        /// The name of the process, as shown by the processes api. The kernel truncates names to 15 characters.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Regular expression searched for in the command line of the process.
+        #[serde(skip_serializing_if = "Option::is_none", rename = "commandLine")]
+        command_line: Option<String>,
+        #[serde(default = "default_min_processes", rename = "minProcesses")]
+        min_processes: u32,
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxProcesses")]
+        max_processes: Option<u32>,
+        /// Max number of threads of each matching process.
+        #[serde(skip_serializing_if = "Option::is_none", rename = "maxThreads")]
+        max_threads: Option<u32>,
+        /// States no matching process may be in.
+        #[serde(skip_serializing_if = "Option::is_none", rename = "forbiddenStates")]
+        forbidden_states: Option<Vec<ProcessStateType>>,
+    },
     Systemctl {
         #[serde(rename = "active")]
         active: Vec<String>,
@@ -85,7 +235,7 @@ pub enum MonitorType {
 /**
  * HTTP methods.
  */
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Copy)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, Copy)]
 #[serde(rename_all = "camelCase")]
 pub enum HttpMethod {
     Get,
@@ -96,36 +246,350 @@ pub enum HttpMethod {
     Head,
 }
 
+/**
+ * DNS record types.
+ */
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordType {
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Txt,
+}
+
+/**
+ * Process states, named as in the processes api.
+ */
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, Copy)]
+pub enum ProcessStateType {
+    Running,
+    InterruptableSleep,
+    DiskSleep,
+    Stopped,
+    TracingStop,
+    Zombie,
+    Idle,
+    Dead,
+}
+
 /**
  * Monitor struct.
  *
  * This struct represents a monitor configuration.
  *
  * name: Monitor name.
- * schedule: Monitor cron schedule.
+ * schedule: Monitor cron schedule or interval.
+ * jitter: Maximum random delay of each check.
+ * timeout: Maximum duration of each check.
  * monitor: Monitor type.
  *
  */
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct Monitor {
     /// The name of the monitor.
     #[serde(rename = "name")]
     pub name: String,
-    /// The schedule of the monitor.
+    /// The schedule of the monitor. A cron expression, e.g. "0 * * * * *", or an interval, e.g. "30s" or "5m".
     #[serde(rename = "schedule")]
     pub schedule: String,
+    /// The maximum random delay of each check, e.g. "10s". Spreads the checks of monitors with the same schedule.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "jitter")]
+    pub jitter: Option<String>,
+    /// The maximum duration of each check, e.g. "10s". A check running longer is cancelled and counts as an error.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "timeout")]
+    pub timeout: Option<String>,
     /// The details of the monitor.
     #[serde(rename = "details")]
     pub details: MonitorType,
     /// The database store configuration.
     #[serde(rename = "store", default = "default_database_store_level")]
     pub store: DatabaseStoreLevel,
+    /// The email recipients of notifications for this monitor. If not given, then the default recipients are used.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "emailRecipients")]
+    pub email_recipients: Option<Vec<String>>,
+    /// The number of consecutive failed checks before the monitor changes status to error.
+    #[serde(rename = "failureThreshold", default = "default_threshold")]
+    pub failure_threshold: u32,
+    /// The number of consecutive successful checks before the monitor changes status from error.
+    #[serde(rename = "successThreshold", default = "default_threshold")]
+    pub success_threshold: u32,
+    /// The flap detection configuration. If not given, then flap detection is disabled.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "flapDetection")]
+    pub flap_detection: Option<FlapDetectionConfig>,
+}
+
+impl Monitor {
+    /**
+     * Get every problem in the monitor configuration.
+     *
+     * `has_database`: True if the global database configuration is given.
+     *
+     * result: The problems found. Empty if the monitor is valid.
+     */
+    fn get_validation_errors(&self, has_database: bool) -> Vec<String> {
+        let mut validation_errors = Vec::new();
+        let interval = parse_duration(&self.schedule);
+        if interval.is_none() {
+            if let Err(err) = cron::Schedule::from_str(&self.schedule) {
+                validation_errors.push(format!("invalid schedule \"{}\": {err}", self.schedule));
+            }
+        }
+        if let Some(jitter) = &self.jitter {
+            match (parse_duration(jitter), interval) {
+                (None, _) => validation_errors.push(format!("invalid jitter \"{jitter}\", expected a duration like 10s")),
+                (Some(jitter_duration), Some(interval)) if jitter_duration >= interval => {
+                    validation_errors.push(format!("jitter {jitter} must be shorter than the interval {}", self.schedule));
+                }
+                _ => {}
+            }
+        }
+        if let Some(timeout) = &self.timeout {
+            if parse_duration(timeout).is_none() {
+                validation_errors.push(format!("invalid timeout \"{timeout}\", expected a duration like 10s"));
+            }
+        }
+        if self.failure_threshold == 0 {
+            validation_errors.push("failureThreshold must be at least 1".to_string());
+        }
+        if self.success_threshold == 0 {
+            validation_errors.push("successThreshold must be at least 1".to_string());
+        }
+        if let Some(flap_detection) = &self.flap_detection {
+            if flap_detection.window == 0 {
+                validation_errors.push("flapDetection.window must be at least 1".to_string());
+            }
+            if flap_detection.max_transitions == 0 {
+                validation_errors.push("flapDetection.maxTransitions must be at least 1".to_string());
+            }
+        }
+        match &self.details {
+            MonitorType::Tcp { host, port, .. } => {
+                if host.is_empty() {
+                    validation_errors.push("host must not be empty".to_string());
+                }
+                if *port == 0 {
+                    validation_errors.push("port must not be 0".to_string());
+                }
+            }
+            MonitorType::Http { url, root_certificate, identity, .. } => {
+                validation_errors.extend(validate_url(url));
+                validation_errors.extend(validate_file("rootCertificate", root_certificate.as_ref()));
+                validation_errors.extend(validate_file("identity", identity.as_ref()));
+            }
+            MonitorType::Command { command, .. } => {
+                if command.is_empty() {
+                    validation_errors.push("command must not be empty".to_string());
+                }
+            }
+            MonitorType::LoadAvg { threshold_1min, threshold_5min, threshold_10min, warning_threshold_1min, warning_threshold_5min, warning_threshold_10min, .. } => {
+                for (name, threshold, warning_name, warning_threshold) in [
+                    ("threshold1min", threshold_1min, "warningThreshold1min", warning_threshold_1min),
+                    ("threshold5min", threshold_5min, "warningThreshold5min", warning_threshold_5min),
+                    ("threshold10min", threshold_10min, "warningThreshold10min", warning_threshold_10min),
+                ] {
+                    validation_errors.extend(validate_thresholds(name, threshold.map(f64::from), warning_name, warning_threshold.map(f64::from), None));
+                }
+            }
+            MonitorType::Mem { max_percentage_mem, max_percentage_swap, warning_percentage_mem, warning_percentage_swap, .. } => {
+                validation_errors.extend(validate_thresholds("maxPercentageMemUsed", *max_percentage_mem, "warningPercentageMemUsed", *warning_percentage_mem, Some(100.0)));
+                validation_errors.extend(validate_thresholds("maxPercentageSwapUsed", *max_percentage_swap, "warningPercentageSwapUsed", *warning_percentage_swap, Some(100.0)));
+            }
+            MonitorType::Disk { mount_points, max_percentage_used, max_percentage_inodes_used, warning_percentage_used, warning_percentage_inodes_used, .. } => {
+                if mount_points.as_ref().is_some_and(|mount_points| mount_points.is_empty() || mount_points.iter().any(|mount_point| !mount_point.starts_with('/'))) {
+                    validation_errors.push("mountPoints must list absolute paths, or be left out to check all filesystems".to_string());
+                }
+                validation_errors.extend(validate_thresholds("maxPercentageUsed", *max_percentage_used, "warningPercentageUsed", *warning_percentage_used, Some(100.0)));
+                validation_errors.extend(validate_thresholds("maxPercentageInodesUsed", *max_percentage_inodes_used, "warningPercentageInodesUsed", *warning_percentage_inodes_used, Some(100.0)));
+            }
+            MonitorType::Cpu { max_percentage_used, warning_percentage_used, max_percentage_iowait, warning_percentage_iowait, max_percentage_steal, warning_percentage_steal, .. } => {
+                validation_errors.extend(validate_thresholds("maxPercentageUsed", *max_percentage_used, "warningPercentageUsed", *warning_percentage_used, Some(100.0)));
+                validation_errors.extend(validate_thresholds("maxPercentageIowait", *max_percentage_iowait, "warningPercentageIowait", *warning_percentage_iowait, Some(100.0)));
+                validation_errors.extend(validate_thresholds("maxPercentageSteal", *max_percentage_steal, "warningPercentageSteal", *warning_percentage_steal, Some(100.0)));
+            }
+            MonitorType::Certificate { host, port, files, root_certificate, warning_days, error_days, .. } => {
+                if host.is_none() && files.is_none() {
+                    validation_errors.push("certificate monitor needs a host or files".to_string());
+                }
+                if host.as_ref().is_some_and(String::is_empty) {
+                    validation_errors.push("host must not be empty".to_string());
+                }
+                if *port == 0 {
+                    validation_errors.push("port must not be 0".to_string());
+                }
+                if files.as_ref().is_some_and(Vec::is_empty) {
+                    validation_errors.push("files must list at least one file".to_string());
+                }
+                for file in files.iter().flatten() {
+                    validation_errors.extend(validate_file("files", Some(file)));
+                }
+                validation_errors.extend(validate_file("rootCertificate", root_certificate.as_ref()));
+                if error_days > warning_days {
+                    validation_errors.push(format!("errorDays {error_days} must not be above warningDays {warning_days}"));
+                }
+            }
+            MonitorType::Dns { name, nameserver, expected, .. } => {
+                if name.is_empty() {
+                    validation_errors.push("name must not be empty".to_string());
+                }
+                if let Some(nameserver) = nameserver {
+                    if parse_nameserver(nameserver).is_none() {
+                        validation_errors.push(format!("nameserver {nameserver} must be an ip address with an optional port"));
+                    }
+                }
+                if expected.as_ref().is_some_and(Vec::is_empty) {
+                    validation_errors.push("expected must list at least one answer".to_string());
+                }
+            }
+            MonitorType::Process { name, command_line, min_processes, max_processes, max_threads, .. } => {
+                if name.is_none() && command_line.is_none() {
+                    validation_errors.push("process monitor needs a name or commandLine".to_string());
+                }
+                if name.as_ref().is_some_and(String::is_empty) {
+                    validation_errors.push("name must not be empty".to_string());
+                }
+                if let Some(command_line) = command_line {
+                    if let Err(err) = regex::Regex::new(command_line) {
+                        validation_errors.push(format!("commandLine {command_line} is not a valid regular expression: {err}"));
+                    }
+                }
+                if let Some(max_processes) = max_processes {
+                    if max_processes < min_processes {
+                        validation_errors.push(format!("maxProcesses {max_processes} must not be below minProcesses {min_processes}"));
+                    }
+                }
+                if *max_threads == Some(0) {
+                    validation_errors.push("maxThreads must be at least 1".to_string());
+                }
+            }
+            MonitorType::Systemctl { active } => {
+                if active.is_empty() {
+                    validation_errors.push("active must list at least one service".to_string());
+                }
+            }
+            MonitorType::Database { database_config, .. } => {
+                if database_config.is_none() && !has_database {
+                    validation_errors.push("database monitor without config, and no database is configured".to_string());
+                }
+            }
+        }
+        validation_errors
+    }
+}
+
+/**
+ * Parse a duration like "30s", "5m", "2h" or "1d".
+ *
+ * `text`: The duration.
+ *
+ * result: The duration, or None if the text is not a positive number followed by s, m, h or d.
+ */
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let unit_start = text.find(|character: char| !character.is_ascii_digit())?;
+    let (value, unit) = text.split_at(unit_start);
+    let value: u64 = value.parse().ok()?;
+    let seconds = match unit {
+        "s" => Some(value),
+        "m" => value.checked_mul(60),
+        "h" => value.checked_mul(60 * 60),
+        "d" => value.checked_mul(24 * 60 * 60),
+        _ => None,
+    }?;
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+/**
+ * Parse a nameserver like "192.168.1.1", "192.168.1.1:5353" or "[2001:db8::1]:53".
+ *
+ * `text`: The nameserver.
+ *
+ * result: The address of the nameserver, port 53 if not given, or None if the text is not an ip address.
+ */
+pub fn parse_nameserver(text: &str) -> Option<SocketAddr> {
+    text.parse::<SocketAddr>().ok()
+        .or_else(|| text.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53)))
+}
+
+/**
+ * Validate the syntax of an url.
+ *
+ * `url`: The url.
+ *
+ * result: The problem found, or None if the url is valid.
+ */
+fn validate_url(url: &str) -> Option<String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed_url) if matches!(parsed_url.scheme(), "http" | "https") => None,
+        Ok(parsed_url) => Some(format!("invalid url \"{}\": scheme {} is not http or https", secrets::redact(url), parsed_url.scheme())),
+        Err(err) => Some(format!("invalid url \"{}\": {err}", secrets::redact(url))),
+    }
+}
+
+/**
+ * Validate that a file can be read.
+ *
+ * `name`: The name of the configuration field.
+ * `path`: The path of the file, if given.
+ *
+ * result: The problem found, or None if the file is not given or can be read.
+ */
+fn validate_file(name: &str, path: Option<&String>) -> Option<String> {
+    let path = path?;
+    fs::File::open(path).err().map(|err| format!("{name} {path} can not be read: {err}"))
+}
+
+/**
+ * Validate the range of an error threshold and its warning threshold.
+ *
+ * `name`: The name of the error threshold.
+ * `threshold`: The error threshold, if given.
+ * `warning_name`: The name of the warning threshold.
+ * `warning_threshold`: The warning threshold, if given.
+ * `max`: The maximum value of the thresholds, if any.
+ *
+ * result: The problems found.
+ */
+fn validate_thresholds(name: &str, threshold: Option<f64>, warning_name: &str, warning_threshold: Option<f64>, max: Option<f64>) -> Vec<String> {
+    let mut validation_errors = Vec::new();
+    for (name, value) in [(name, threshold), (warning_name, warning_threshold)] {
+        match (value, max) {
+            (Some(value), Some(max)) if !(0.0..=max).contains(&value) => {
+                validation_errors.push(format!("{name} {value} must be between 0 and {max}"));
+            }
+            (Some(value), None) if value < 0.0 => {
+                validation_errors.push(format!("{name} {value} must not be negative"));
+            }
+            _ => {}
+        }
+    }
+    if let (Some(threshold), Some(warning_threshold)) = (threshold, warning_threshold) {
+        if warning_threshold > threshold {
+            validation_errors.push(format!("{warning_name} {warning_threshold} must not be above {name} {threshold}"));
+        }
+    }
+    validation_errors
+}
+
+/**
+ * Flap detection configuration.
+ *
+ * A monitor is flapping when it changes status more than `max_transitions` times within `window` seconds.
+ */
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct FlapDetectionConfig {
+    /// The window in seconds.
+    #[serde(rename = "window", default = "default_flap_window")]
+    pub window: u64,
+    /// The maximum number of status changes within the window.
+    #[serde(rename = "maxTransitions", default = "default_flap_max_transitions")]
+    pub max_transitions: u32,
 }
 
 /**
  * Database store level.
  */
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub enum DatabaseStoreLevel {
     /// Store nothing.
     None,
@@ -144,7 +608,7 @@ pub enum DatabaseStoreLevel {
  * monitors: List of monitors.
  *
  */
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MonitoringConfig {
     /// The server configuration. Example ip and port where web services are made available.
     #[serde(rename = "server", default = "default_server")]
@@ -152,11 +616,28 @@ pub struct MonitoringConfig {
     /// The database configuration. If non is provided, then no storage is used.
     #[serde(rename = "database")]
     pub database: Option<DatabaseConfig>,
+    /// The notification configuration. If none is provided, then no notifications are sent.
+    #[serde(rename = "notifications", skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationConfig>,
     /// The list of monitors.
-    #[serde(rename = "monitors")]
+    #[serde(rename = "monitors", default)]
     pub monitors: Vec<Monitor>,
+    /// The directory, or directory and file pattern, of the monitor fragments to include. Example "conf.d/*.json".
+    #[serde(rename = "include", skip_serializing_if = "Option::is_none")]
+    pub include: Option<String>,
+}
 
-
+/**
+ * Monitor fragment.
+ *
+ * A file in the include directory holding only a list of monitors.
+ *
+ */
+#[derive(Debug, Deserialize)]
+struct MonitorFragment {
+    /// The list of monitors.
+    #[serde(rename = "monitors", default)]
+    monitors: Vec<Monitor>,
 }
 
 impl MonitoringConfig {
@@ -164,12 +645,155 @@ impl MonitoringConfig {
      * Create a new monitoring configuration.
      * 
      * input: The input file.
+     * format: The format of the input file. If not given, the format is detected from the file extension.
      * 
      * result: The result of creating the monitoring configuration.
      */
-    pub fn new(input: &str) -> Result<MonitoringConfig, ApplicationError> {
+    pub fn new(input: &str, format: Option<ConfigFormat>) -> Result<MonitoringConfig, ApplicationError> {
         let monitor_data: String = MonitoringConfig::get_monitor_data(input)?;
-        MonitoringConfig::get_monitor_config(monitor_data.as_str())
+        let format = format.unwrap_or_else(|| ConfigFormat::from_path(input));
+        let mut monitoring_config = MonitoringConfig::get_monitor_config(monitor_data.as_str(), format)?;
+        if let Some(include) = monitoring_config.include.clone() {
+            monitoring_config.include_fragments(input, &include)?;
+        }
+        Ok(monitoring_config)
+    }
+
+    /**
+     * Add the monitors of the fragments in the include directory. The fragments are read in file name order, and
+     * their format is detected from the file extension.
+     *
+     * input: The input file. A relative include is relative to the directory of the input file.
+     * include: The include directory, optionally followed by a file pattern with one *. Example "conf.d" or "conf.d" with the pattern "*.json".
+     *
+     * result: Ok if the fragments were added.
+     *
+     * throws: `ApplicationError`: If a fragment can not be read or parsed, or a monitor name is used in two files.
+     */
+    fn include_fragments(&mut self, input: &str, include: &str) -> Result<(), ApplicationError> {
+        let mut sources: HashMap<String, String> = self.monitors.iter()
+            .map(|monitor| (monitor.name.clone(), input.to_string()))
+            .collect();
+        for fragment_path in MonitoringConfig::get_fragment_paths(input, include)? {
+            let fragment_data = MonitoringConfig::get_monitor_data(&fragment_path)?;
+            let fragment: MonitorFragment = MonitoringConfig::get_config(&fragment_data, ConfigFormat::from_path(&fragment_path))
+                .map_err(|err| ApplicationError::new(format!("{fragment_path}: {}", err.get_message()).as_str()))?;
+            for monitor in fragment.monitors {
+                if let Some(source) = sources.get(&monitor.name) {
+                    return Err(ApplicationError::new(
+                        format!("Duplicate monitor name: {} in {source} and {fragment_path}", monitor.name).as_str(),
+                    ));
+                }
+                sources.insert(monitor.name.clone(), fragment_path.clone());
+                self.monitors.push(monitor);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Get the paths of the fragments in the include directory, sorted by file name.
+     *
+     * input: The input file.
+     * include: The include directory, optionally followed by a file pattern with one *.
+     *
+     * result: The paths of the fragments. Without a pattern, all json, yaml, yml and toml files are included.
+     *
+     * throws: `ApplicationError`: If the include directory can not be read.
+     */
+    fn get_fragment_paths(input: &str, include: &str) -> Result<Vec<String>, ApplicationError> {
+        let include_path = Path::new(input).parent().unwrap_or(Path::new("")).join(include);
+        let (directory, pattern) = match include_path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.contains('*') => (include_path.parent().unwrap_or(Path::new("")).to_path_buf(), Some(name.to_string())),
+            _ => (include_path.clone(), None),
+        };
+        let entries = fs::read_dir(&directory).map_err(|err| ApplicationError::new(
+            format!("Could not read include directory {}, error: {err}", directory.display()).as_str(),
+        ))?;
+        let mut paths: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| match &pattern {
+                Some(pattern) => MonitoringConfig::matches_pattern(name, pattern),
+                None => Path::new(name).extension().and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| matches!(extension.to_lowercase().as_str(), "json" | "yaml" | "yml" | "toml")),
+            })
+            .map(|name| directory.join(name).to_string_lossy().to_string())
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /**
+     * Check if a file name matches a pattern with one *.
+     *
+     * name: The file name.
+     * pattern: The pattern. Example "*.json".
+     *
+     * result: True if the file name matches the pattern.
+     */
+    fn matches_pattern(name: &str, pattern: &str) -> bool {
+        match pattern.split_once('*') {
+            Some((prefix, suffix)) => name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix),
+            None => name == pattern,
+        }
+    }
+
+    /**
+     * Get the JSON schema of the configuration file. The schema is generated from the configuration structs, so it
+     * follows their serde attributes.
+     * 
+     * result: The JSON schema.
+     */
+    pub fn get_schema() -> RootSchema {
+        schemars::schema_for!(MonitoringConfig)
+    }
+
+    /**
+     * Validate the monitoring configuration.
+     * 
+     * result: Ok if the configuration is valid.
+     * 
+     * throws: `ApplicationError`: With every problem found, one per line.
+     */
+    pub fn validate(&self) -> Result<(), ApplicationError> {
+        let validation_errors = self.get_validation_errors();
+        if validation_errors.is_empty() {
+            return Ok(());
+        }
+        Err(ApplicationError::new(validation_errors.join("\n").as_str()))
+    }
+
+    /**
+     * Get every problem in the monitoring configuration. Checks the monitor names are unique, the schedules, urls,
+     * files, thresholds and database references of the monitors, and the webhook urls.
+     * 
+     * result: The problems found, prefixed with the monitor name. Empty if the configuration is valid.
+     */
+    pub fn get_validation_errors(&self) -> Vec<String> {
+        let mut validation_errors = Vec::new();
+        let mut names = HashSet::new();
+        for monitor in &self.monitors {
+            if !names.insert(monitor.name.as_str()) {
+                validation_errors.push(format!("Duplicate monitor name: {}", monitor.name));
+            }
+            validation_errors.extend(monitor.get_validation_errors(self.database.is_some()).into_iter()
+                .map(|validation_error| format!("Monitor {}: {validation_error}", monitor.name)));
+        }
+        if let Some(notifications) = &self.notifications {
+            for webhook in &notifications.webhooks {
+                if let Some(validation_error) = validate_url(&webhook.url) {
+                    validation_errors.push(format!("Webhook: {validation_error}"));
+                }
+            }
+            if let Some(email) = &notifications.email {
+                if email.host.is_empty() {
+                    validation_errors.push("Email: host must not be empty".to_string());
+                }
+            }
+        }
+        validation_errors
     }
 
     /**
@@ -192,23 +816,98 @@ impl MonitoringConfig {
      * Get monitor configuration.
      * 
      * data: The monitor data.
+     * format: The format of the monitor data.
      * 
      * result: The result of getting the monitor configuration.
      */
-    fn get_monitor_config(data: &str) -> Result<MonitoringConfig, ApplicationError> {
-        match serde_json::from_str(data) {
-            Ok(monitor_config) => Ok(monitor_config),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not parse config file: Line {}", err.line()).as_str(),
-            )),
+    fn get_monitor_config(data: &str, format: ConfigFormat) -> Result<MonitoringConfig, ApplicationError> {
+        MonitoringConfig::get_config(data, format)
+    }
+
+    /**
+     * Get a configuration with the environment variables and secret files interpolated.
+     * 
+     * data: The configuration data.
+     * format: The format of the configuration data.
+     * 
+     * result: The result of getting the configuration.
+     */
+    fn get_config<T: serde::de::DeserializeOwned>(data: &str, format: ConfigFormat) -> Result<T, ApplicationError> {
+        /*
+         * Interpolate the environment variables and secret files. If there is nothing to interpolate, the data is
+         * parsed again directly, so errors report the line and column.
+         */
+        let Ok(mut value) = MonitoringConfig::parse::<serde_json::Value>(data, format) else {
+            return MonitoringConfig::parse(data, format);
+        };
+        if secrets::interpolate(&mut value)? {
+            return serde_json::from_value(value).map_err(|err| ApplicationError::new(
+                secrets::redact(format!("Could not parse config file: {err}").as_str()).as_str(),
+            ));
+        }
+        MonitoringConfig::parse(data, format)
+    }
+
+    /**
+     * Parse the data.
+     * 
+     * data: The data.
+     * format: The format of the data.
+     * 
+     * result: The result of parsing the data.
+     */
+    fn parse<T: serde::de::DeserializeOwned>(data: &str, format: ConfigFormat) -> Result<T, ApplicationError> {
+        match format {
+            ConfigFormat::Json => serde_json::from_str(data).map_err(|err| {
+                MonitoringConfig::get_parse_error(err.line(), err.column(), &err.to_string())
+            }),
+            ConfigFormat::Yaml => serde_yaml::from_str(data).map_err(|err| {
+                let (line, column) = err.location().map_or((0, 0), |location| (location.line(), location.column()));
+                MonitoringConfig::get_parse_error(line, column, &err.to_string())
+            }),
+            ConfigFormat::Toml => toml::from_str(data).map_err(|err| {
+                let (line, column) = err.span().map_or((0, 0), |span| MonitoringConfig::get_line_and_column(data, span.start));
+                MonitoringConfig::get_parse_error(line, column, err.message())
+            }),
         }
     }
+
+    /**
+     * Get the error for a configuration file that could not be parsed.
+     * 
+     * line: The line of the error, starting at 1.
+     * column: The column of the error, starting at 1.
+     * message: The error message. The location at the end of the message is removed.
+     * 
+     * result: The error.
+     */
+    fn get_parse_error(line: usize, column: usize, message: &str) -> ApplicationError {
+        let message = message.strip_suffix(format!(" at line {line} column {column}").as_str()).unwrap_or(message);
+        ApplicationError::new(
+            format!("Could not parse config file: Line {line}, column {column}: {message}").as_str(),
+        )
+    }
+
+    /**
+     * Get the line and column of a position in the data.
+     * 
+     * data: The data.
+     * offset: The byte offset of the position.
+     * 
+     * result: The line and column, both starting at 1.
+     */
+    fn get_line_and_column(data: &str, offset: usize) -> (usize, usize) {
+        let before = &data[..offset.min(data.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|character| *character != '\n').count() + 1;
+        (line, column)
+    }
 }
 
 /**
  * Server configuration.
  */
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
 pub struct ServerConfig {
     /// The port of the server.
     #[serde(rename = "port", default = "default_server_port")]
@@ -224,7 +923,7 @@ pub struct ServerConfig {
 /**
  * Database type.
  */
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
 pub enum DatabaseType {
     Postgres,
     Mysql,
@@ -234,7 +933,7 @@ pub enum DatabaseType {
 /**
  * Database configuration.
  */
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema, PartialEq)]
 pub struct DatabaseConfig {
     /// The type of database.
     #[serde(rename = "type")]
@@ -250,6 +949,7 @@ pub struct DatabaseConfig {
     pub user: String,
     /// The password.
     #[serde(rename = "password")]
+    #[schemars(schema_with = "secret_schema")]
     pub password: String,
     /// The port.
     #[serde(rename = "port")]
@@ -262,6 +962,89 @@ pub struct DatabaseConfig {
     pub max_connections: u32,    
 }
 
+/**
+ * Notification configuration.
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct NotificationConfig {
+    /// The webhooks to notify when a monitor changes status.
+    #[serde(rename = "webhooks", default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// The email configuration. If none is provided, then no emails are sent.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "email")]
+    pub email: Option<EmailConfig>,
+}
+
+/**
+ * Webhook configuration.
+ */
+#[derive(Clone, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct WebhookConfig {
+    /// The url to post the notification to.
+    #[serde(rename = "url")]
+    pub url: String,
+    /// Additional headers to send with the notification.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "headers")]
+    pub headers: Option<HashMap<String, String>>,
+    /// The timeout of each request in seconds.
+    #[serde(rename = "timeout", default = "default_notification_timeout")]
+    pub timeout: u64,
+    /// The number of retries if the request fails.
+    #[serde(rename = "retries", default = "default_webhook_retries")]
+    pub retries: u32,
+    /// The delay between retries in seconds.
+    #[serde(rename = "retryDelay", default = "default_webhook_retry_delay")]
+    pub retry_delay: u64,
+}
+
+/**
+ * Smtp connection security.
+ */
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub enum SmtpSecurity {
+    /// Plain connection without encryption.
+    None,
+    /// Plain connection upgraded with STARTTLS.
+    StartTls,
+    /// Implicit TLS.
+    Tls,
+}
+
+/**
+ * Email configuration.
+ */
+#[derive(Clone, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct EmailConfig {
+    /// The host of the smtp server.
+    #[serde(rename = "host")]
+    pub host: String,
+    /// The port of the smtp server. If not given, then the default port of the security type is used.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "port")]
+    pub port: Option<u16>,
+    /// The connection security.
+    #[serde(rename = "security", default = "default_smtp_security")]
+    pub security: SmtpSecurity,
+    /// The username used for authentication. If not given, then no authentication is done.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "username")]
+    pub username: Option<String>,
+    /// The password used for authentication.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "password")]
+    #[schemars(schema_with = "secret_schema", default)]
+    pub password: Option<String>,
+    /// The sender address.
+    #[serde(rename = "from")]
+    pub from: String,
+    /// The default recipients.
+    #[serde(rename = "to", default)]
+    pub to: Vec<String>,
+    /// The timeout in seconds.
+    #[serde(rename = "timeout", default = "default_notification_timeout")]
+    pub timeout: u64,
+    /// Accept invalid certificates from the smtp server.
+    #[serde(rename = "acceptInvalidCerts", default = "default_as_false")]
+    pub accept_invalid_certs: bool,
+}
+
 /**
  * Default server configuration.
  * 
@@ -309,6 +1092,84 @@ fn default_server_ip() -> String {
     debug!("Using default server ip");
     "127.0.0.1".to_string()
 }
+/**
+ * Default notification timeout in seconds.
+ */
+fn default_notification_timeout() -> u64 {
+    debug!("Using default notification timeout");
+    10
+}
+/**
+ * Default number of webhook retries.
+ */
+fn default_webhook_retries() -> u32 {
+    debug!("Using default webhook retries");
+    3
+}
+/**
+ * Default delay between webhook retries in seconds.
+ */
+fn default_webhook_retry_delay() -> u64 {
+    debug!("Using default webhook retry delay");
+    5
+}
+/**
+ * Default number of consecutive checks before a monitor changes status.
+ */
+fn default_threshold() -> u32 {
+    1
+}
+/**
+ * Default port of certificate monitors.
+ */
+fn default_certificate_port() -> u16 {
+    443
+}
+/**
+ * Default number of days before expiry a certificate gives a warning.
+ */
+fn default_certificate_warning_days() -> u32 {
+    30
+}
+/**
+ * Default number of days before expiry a certificate gives an error.
+ */
+fn default_certificate_error_days() -> u32 {
+    7
+}
+/**
+ * Default record type of dns monitors.
+ */
+fn default_dns_record_type() -> DnsRecordType {
+    DnsRecordType::A
+}
+/**
+ * Default minimum number of processes of process monitors.
+ */
+fn default_min_processes() -> u32 {
+    1
+}
+/**
+ * Default flap detection window in seconds.
+ */
+fn default_flap_window() -> u64 {
+    debug!("Using default flap detection window");
+    600
+}
+/**
+ * Default maximum number of status changes within the flap detection window.
+ */
+fn default_flap_max_transitions() -> u32 {
+    debug!("Using default flap detection max transitions");
+    4
+}
+/**
+ * Default smtp security.
+ */
+fn default_smtp_security() -> SmtpSecurity {
+    debug!("Using default smtp security");
+    SmtpSecurity::None
+}
 /**
  * Default database store level.
  */
@@ -317,6 +1178,60 @@ fn default_database_store_level() -> DatabaseStoreLevel {
     DatabaseStoreLevel::Errors
 }
 
+/**
+ * Get the JSON schema of a secret. A secret is a string, or an object with the path to a secret file.
+ *
+ * `generator`: The schema generator.
+ *
+ * result: The JSON schema.
+ */
+fn secret_schema(generator: &mut SchemaGenerator) -> Schema {
+    let file_schema = SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(ObjectValidation {
+            properties: [("file".to_string(), generator.subschema_for::<String>())].into_iter().collect(),
+            required: ["file".to_string()].into_iter().collect(),
+            additional_properties: Some(Box::new(Schema::Bool(false))),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![generator.subschema_for::<String>(), file_schema.into()]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }.into()
+}
+
+/*
+ * The debug output of the configurations holding secrets redacts the passwords, the headers and the interpolated secrets.
+ */
+impl fmt::Debug for MonitorType {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        secrets::fmt_redacted("MonitorType", self, formatter)
+    }
+}
+
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        secrets::fmt_redacted("DatabaseConfig", self, formatter)
+    }
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        secrets::fmt_redacted("WebhookConfig", self, formatter)
+    }
+}
+
+impl fmt::Debug for EmailConfig {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        secrets::fmt_redacted("EmailConfig", self, formatter)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -328,7 +1243,7 @@ mod tests {
     #[test]
     fn test_simple_tcp_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -336,7 +1251,8 @@ mod tests {
             monitor,
             MonitorType::Tcp {
                 host: "192.168.1.1".to_string(),
-                port: 8080
+                port: 8080,
+                max_response_time: None,
             }
         );
         assert_eq!(&8080, &monitoring.server.clone().port);
@@ -344,13 +1260,178 @@ mod tests {
         Ok(())
     }
 
+    /**
+     * Test that yaml and toml files give the same configuration as json.
+     */
+    #[test]
+    fn test_yaml_and_toml_files() -> Result<(), ApplicationError> {
+        let json = MonitoringConfig::new("resources/test/test_full_configuration.json", None)?;
+        assert_eq!(MonitoringConfig::new("resources/test/test_full_configuration.yaml", None)?, json);
+        assert_eq!(MonitoringConfig::new("resources/test/test_full_configuration.toml", None)?, json);
+        Ok(())
+    }
+
+    /**
+     * Test that the format given explicitly is used instead of the file extension.
+     */
+    #[test]
+    fn test_explicit_format() {
+        assert!(MonitoringConfig::new("resources/test/test_full_configuration.yaml", Some(ConfigFormat::Yaml)).is_ok());
+        assert!(MonitoringConfig::new("resources/test/test_full_configuration.yaml", Some(ConfigFormat::Json)).is_err());
+    }
+
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(ConfigFormat::from_path("/etc/monitoring-agent/config.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("config.yaml"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("config.YML"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("config.toml"), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path("config"), ConfigFormat::Json);
+    }
+
+    /**
+     * Test that parse errors report the line and column for all formats.
+     */
+    #[test]
+    fn test_parse_errors() {
+        let json = "{\n    \"monitors\": [\n        {\"name\": 1}\n    ]\n}";
+        let err = MonitoringConfig::get_monitor_config(json, ConfigFormat::Json).unwrap_err();
+        assert_eq!(err.get_message(), "Could not parse config file: Line 3, column 18: invalid type: integer `1`, expected a string");
+        let yaml = "monitors:\n  - name: test\n    schedule: [\n";
+        let err = MonitoringConfig::get_monitor_config(yaml, ConfigFormat::Yaml).unwrap_err();
+        assert_eq!(err.get_message(), "Could not parse config file: Line 3, column 15: monitors[0].schedule: invalid type: sequence, expected a string");
+        let toml = "[[monitors]]\nname = \"test\"\nschedule = 1\n";
+        let err = MonitoringConfig::get_monitor_config(toml, ConfigFormat::Toml).unwrap_err();
+        assert!(err.get_message().starts_with("Could not parse config file: Line 3, column 12: "), "{}", err.get_message());
+    }
+
+    /**
+     * Test that duplicate monitor names are rejected.
+     */
+    #[test]
+    fn test_validate_duplicate_names() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None)?;
+        monitoring.monitors[0].schedule = "0 * * * * *".to_string();
+        assert!(monitoring.validate().is_ok());
+        monitoring.monitors.push(monitoring.monitors[0].clone());
+        assert_eq!(monitoring.validate().unwrap_err().get_message(), "Duplicate monitor name: ");
+        Ok(())
+    }
+
+    /**
+     * Test that every problem in the configuration is reported with its monitor name.
+     */
+    #[test]
+    fn test_validation_errors() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/validation_test/test_invalid.json", None)?;
+        let validation_errors = monitoring.get_validation_errors();
+        assert_eq!(10, validation_errors.len(), "{validation_errors:?}");
+        assert!(validation_errors[0].starts_with("Monitor tcp: invalid schedule \"0 0 0 0 0 0 0\""));
+        assert_eq!(&validation_errors[1..], &[
+            "Monitor tcp: failureThreshold must be at least 1".to_string(),
+            "Monitor tcp: host must not be empty".to_string(),
+            "Duplicate monitor name: tcp".to_string(),
+            "Monitor http: invalid url \"not a url\": relative URL without a base".to_string(),
+            "Monitor http: rootCertificate ./resources/test/server_cert/missing.cer can not be read: No such file or directory (os error 2)".to_string(),
+            "Monitor mem: warningPercentageMemUsed 90 must not be above maxPercentageMemUsed 80".to_string(),
+            "Monitor mem: maxPercentageSwapUsed 120 must be between 0 and 100".to_string(),
+            "Monitor database: database monitor without config, and no database is configured".to_string(),
+            "Webhook: invalid url \"hooks.example.com/monitoring\": relative URL without a base".to_string(),
+        ]);
+        assert_eq!(monitoring.validate().unwrap_err().get_message(), validation_errors.join("\n"));
+        Ok(())
+    }
+
+    /**
+     * Test for interval schedules, jitter and timeouts.
+     */
+    #[test]
+    fn test_simple_interval_file() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_interval.json", None)?;
+        assert_eq!("30s", monitoring.monitors[0].schedule);
+        assert_eq!(Some("5s".to_string()), monitoring.monitors[0].jitter);
+        assert_eq!(Some("1m".to_string()), monitoring.monitors[1].jitter);
+        assert_eq!(Some("10s".to_string()), monitoring.monitors[0].timeout);
+        assert_eq!(None, monitoring.monitors[1].timeout);
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+
+        monitoring.monitors[0].jitter = Some("30s".to_string());
+        monitoring.monitors[1].jitter = Some("often".to_string());
+        monitoring.monitors[1].timeout = Some("0s".to_string());
+        assert_eq!(monitoring.get_validation_errors(), vec![
+            "Monitor interval: jitter 30s must be shorter than the interval 30s".to_string(),
+            "Monitor cron: invalid jitter \"often\", expected a duration like 10s".to_string(),
+            "Monitor cron: invalid timeout \"0s\", expected a duration like 10s".to_string(),
+        ]);
+        Ok(())
+    }
+
+    /**
+     * Test parsing durations.
+     */
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Some(Duration::from_secs(30)), parse_duration("30s"));
+        assert_eq!(Some(Duration::from_secs(300)), parse_duration("5m"));
+        assert_eq!(Some(Duration::from_secs(7200)), parse_duration("2h"));
+        assert_eq!(Some(Duration::from_secs(86400)), parse_duration("1d"));
+        assert_eq!(None, parse_duration("0s"));
+        assert_eq!(None, parse_duration("30"));
+        assert_eq!(None, parse_duration("s"));
+        assert_eq!(None, parse_duration("5 m"));
+        assert_eq!(None, parse_duration("0 * * * * *"));
+        assert_eq!(None, parse_duration("99999999999999999999d"));
+    }
+
+    #[test]
+    fn test_parse_nameserver() {
+        assert_eq!(Some("192.168.1.1:53".parse().unwrap()), parse_nameserver("192.168.1.1"));
+        assert_eq!(Some("192.168.1.1:5353".parse().unwrap()), parse_nameserver("192.168.1.1:5353"));
+        assert_eq!(Some("[2001:db8::1]:53".parse().unwrap()), parse_nameserver("2001:db8::1"));
+        assert_eq!(Some("[2001:db8::1]:5353".parse().unwrap()), parse_nameserver("[2001:db8::1]:5353"));
+        assert_eq!(None, parse_nameserver("dns.example.com"));
+        assert_eq!(None, parse_nameserver("192.168.1.1:port"));
+    }
+
+    /**
+     * Test that the JSON schema follows the serde attributes of the configuration.
+     */
+    #[test]
+    fn test_schema() {
+        let schema = serde_json::to_value(MonitoringConfig::get_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        assert!(["server", "database", "notifications", "monitors", "include"].iter().all(|name| properties.contains_key(*name)));
+        let monitor_types: Vec<&str> = schema["definitions"]["MonitorType"]["oneOf"].as_array().unwrap().iter()
+            .map(|monitor_type| monitor_type["properties"]["type"]["enum"][0].as_str().unwrap())
+            .collect();
+        assert_eq!(vec!["tcp", "http", "command", "loadAvg", "mem", "disk", "cpu", "certificate", "dns", "process", "systemctl", "database"], monitor_types);
+        let database_config = &schema["definitions"]["DatabaseConfig"];
+        assert!(database_config["properties"]["minConnections"].is_object());
+        assert_eq!(2, database_config["properties"]["password"]["anyOf"].as_array().unwrap().len());
+    }
+
+    /**
+     * Test that the example configurations are valid.
+     */
+    #[test]
+    fn test_valid_configurations() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig = MonitoringConfig::new("resources/prod/etc/config.json", None)?;
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+        let monitoring: MonitoringConfig = MonitoringConfig::new("resources/test/include_test/test_include.json", None)?;
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+        Ok(())
+    }
+
     /**
      * Test for a simple http monitor.
      */
     #[test]
     fn test_simple_http_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_http.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_http.json", None)?;
         assert_eq!("1 2 3 4 5 6 7".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -366,7 +1447,8 @@ mod tests {
                 tls_info: false,
                 root_certificate: None,
                 identity: None,
-                identity_password: None
+                identity_password: None,
+                max_response_time: None,
             }
         );
         assert_eq!(&65000, &monitoring.server.clone().port);
@@ -374,13 +1456,36 @@ mod tests {
         Ok(())
     }
 
+    /**
+     * Test for tcp and http monitors with max response time.
+     */
+    #[test]
+    fn test_simple_max_response_time_file() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_max_response_time.json", None)?;
+        assert_eq!(2, monitoring.monitors.len());
+        assert_eq!(
+            monitoring.monitors[0].details,
+            MonitorType::Tcp {
+                host: "192.168.1.1".to_string(),
+                port: 8080,
+                max_response_time: Some(200),
+            }
+        );
+        let MonitorType::Http { max_response_time, .. } = &monitoring.monitors[1].details else {
+            panic!("Expected http monitor");
+        };
+        assert_eq!(&Some(1500), max_response_time);
+        Ok(())
+    }
+
     /**
      * Test for a simple command monitor.
      */
     #[test]
     fn test_simple_command_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_command.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_command.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -401,7 +1506,7 @@ mod tests {
     #[test]
     fn test_simple_db_mariadb_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_db_mariadb.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_db_mariadb.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -430,7 +1535,7 @@ mod tests {
     #[test]
     fn test_simple_db_postgres_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_db_postgres.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_db_postgres.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -459,7 +1564,7 @@ mod tests {
     #[test]
     fn test_simple_loadavg_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_loadavg.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_loadavg.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -469,6 +1574,9 @@ mod tests {
                 threshold_1min: Some(1.0),
                 threshold_5min: Some(2.0),
                 threshold_10min: Some(3.0),
+                warning_threshold_1min: Some(0.5),
+                warning_threshold_5min: Some(1.5),
+                warning_threshold_10min: None,
                 store_values: true,               
             }
         );
@@ -482,7 +1590,7 @@ mod tests {
     #[test]
     fn test_simple_meminfo_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_meminfo.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_meminfo.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -491,6 +1599,8 @@ mod tests {
             MonitorType::Mem {
                 max_percentage_mem: Some(70.0),
                 max_percentage_swap: Some(80.0),
+                warning_percentage_mem: Some(60.0),
+                warning_percentage_swap: None,
                 store_values: true,                            
             }
         );
@@ -498,13 +1608,257 @@ mod tests {
     }    
 
 
+    /**
+     * Test for simple disk monitors.
+     */
+    #[test]
+    fn test_simple_disk_file() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_disk.json", None)?;
+        assert_eq!(2, monitoring.monitors.len());
+        assert_eq!(
+            monitoring.monitors[0].details,
+            MonitorType::Disk {
+                mount_points: Some(vec!["/".to_string(), "/var".to_string()]),
+                max_percentage_used: Some(90.0),
+                max_percentage_inodes_used: Some(95.0),
+                warning_percentage_used: Some(80.0),
+                warning_percentage_inodes_used: None,
+                store_values: true,
+            }
+        );
+        assert_eq!(
+            monitoring.monitors[1].details,
+            MonitorType::Disk {
+                mount_points: None,
+                max_percentage_used: Some(95.0),
+                max_percentage_inodes_used: None,
+                warning_percentage_used: None,
+                warning_percentage_inodes_used: None,
+                store_values: false,
+            }
+        );
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+
+        monitoring.monitors[1].details = MonitorType::Disk {
+            mount_points: Some(vec!["var".to_string()]),
+            max_percentage_used: Some(120.0),
+            max_percentage_inodes_used: Some(80.0),
+            warning_percentage_used: None,
+            warning_percentage_inodes_used: Some(90.0),
+            store_values: false,
+        };
+        assert_eq!(monitoring.get_validation_errors(), vec![
+            "Monitor AllDisks: mountPoints must list absolute paths, or be left out to check all filesystems".to_string(),
+            "Monitor AllDisks: maxPercentageUsed 120 must be between 0 and 100".to_string(),
+            "Monitor AllDisks: warningPercentageInodesUsed 90 must not be above maxPercentageInodesUsed 80".to_string(),
+        ]);
+        Ok(())
+    }
+
+    /**
+     * Test for a simple cpu monitor.
+     */
+    #[test]
+    fn test_simple_cpu_file() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_cpu.json", None)?;
+        assert_eq!(
+            monitoring.monitors[0].details,
+            MonitorType::Cpu {
+                max_percentage_used: Some(95.0),
+                warning_percentage_used: Some(85.0),
+                max_percentage_iowait: Some(30.0),
+                warning_percentage_iowait: None,
+                max_percentage_steal: Some(20.0),
+                warning_percentage_steal: None,
+                per_core: true,
+                store_values: true,
+            }
+        );
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+
+        monitoring.monitors[0].details = MonitorType::Cpu {
+            max_percentage_used: Some(80.0),
+            warning_percentage_used: Some(90.0),
+            max_percentage_iowait: None,
+            warning_percentage_iowait: None,
+            max_percentage_steal: Some(-1.0),
+            warning_percentage_steal: None,
+            per_core: false,
+            store_values: false,
+        };
+        assert_eq!(monitoring.get_validation_errors(), vec![
+            "Monitor Cpu: warningPercentageUsed 90 must not be above maxPercentageUsed 80".to_string(),
+            "Monitor Cpu: maxPercentageSteal -1 must be between 0 and 100".to_string(),
+        ]);
+        Ok(())
+    }
+
+    /**
+     * Test for simple certificate monitors.
+     */
+    #[test]
+    fn test_simple_certificate_file() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_certificate.json", None)?;
+        assert_eq!(
+            monitoring.monitors[0].details,
+            MonitorType::Certificate {
+                host: Some("example.com".to_string()),
+                port: 443,
+                server_name: Some("www.example.com".to_string()),
+                files: None,
+                root_certificate: Some("./resources/test/server_cert/server.cer".to_string()),
+                accept_invalid_certs: false,
+                warning_days: 21,
+                error_days: 5,
+            }
+        );
+        assert_eq!(
+            monitoring.monitors[1].details,
+            MonitorType::Certificate {
+                host: None,
+                port: 443,
+                server_name: None,
+                files: Some(vec!["./resources/test/server_cert/server.cer".to_string(), "./resources/test/client_cert/client.cer".to_string()]),
+                root_certificate: None,
+                accept_invalid_certs: false,
+                warning_days: 30,
+                error_days: 7,
+            }
+        );
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+
+        monitoring.monitors[1].details = MonitorType::Certificate {
+            host: None,
+            port: 0,
+            server_name: None,
+            files: None,
+            root_certificate: Some("./resources/test/server_cert/missing.cer".to_string()),
+            accept_invalid_certs: false,
+            warning_days: 7,
+            error_days: 30,
+        };
+        assert_eq!(monitoring.get_validation_errors(), vec![
+            "Monitor CertificateFiles: certificate monitor needs a host or files".to_string(),
+            "Monitor CertificateFiles: port must not be 0".to_string(),
+            "Monitor CertificateFiles: rootCertificate ./resources/test/server_cert/missing.cer can not be read: No such file or directory (os error 2)".to_string(),
+            "Monitor CertificateFiles: errorDays 30 must not be above warningDays 7".to_string(),
+        ]);
+        Ok(())
+    }
+
+    /**
+     * Test for simple dns monitors.
+     */
+    #[test]
+    fn test_simple_dns_file() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_dns.json", None)?;
+        assert_eq!(
+            monitoring.monitors[0].details,
+            MonitorType::Dns {
+                name: "example.com".to_string(),
+                record_type: DnsRecordType::Mx,
+                nameserver: Some("192.168.1.1:5353".to_string()),
+                expected: Some(vec!["10 mail.example.com".to_string()]),
+                max_response_time: Some(500),
+            }
+        );
+        assert_eq!(
+            monitoring.monitors[1].details,
+            MonitorType::Dns {
+                name: "example.com".to_string(),
+                record_type: DnsRecordType::A,
+                nameserver: None,
+                expected: None,
+                max_response_time: None,
+            }
+        );
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+
+        monitoring.monitors[1].details = MonitorType::Dns {
+            name: String::new(),
+            record_type: DnsRecordType::Aaaa,
+            nameserver: Some("dns.example.com".to_string()),
+            expected: Some(Vec::new()),
+            max_response_time: None,
+        };
+        assert_eq!(monitoring.get_validation_errors(), vec![
+            "Monitor SystemDns: name must not be empty".to_string(),
+            "Monitor SystemDns: nameserver dns.example.com must be an ip address with an optional port".to_string(),
+            "Monitor SystemDns: expected must list at least one answer".to_string(),
+        ]);
+        Ok(())
+    }
+
+    /**
+     * Test for simple process monitors.
+     */
+    #[test]
+    fn test_simple_process_file() -> Result<(), ApplicationError> {
+        let mut monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_process.json", None)?;
+        assert_eq!(
+            monitoring.monitors[0].details,
+            MonitorType::Process {
+                name: Some("nginx".to_string()),
+                command_line: None,
+                min_processes: 2,
+                max_processes: Some(9),
+                max_threads: None,
+                forbidden_states: Some(vec![ProcessStateType::Zombie, ProcessStateType::Stopped]),
+            }
+        );
+        assert_eq!(
+            monitoring.monitors[1].details,
+            MonitorType::Process {
+                name: None,
+                command_line: Some("java .*-jar /opt/service/service\\.jar".to_string()),
+                min_processes: 1,
+                max_processes: None,
+                max_threads: Some(200),
+                forbidden_states: None,
+            }
+        );
+        assert_eq!(Vec::<String>::new(), monitoring.get_validation_errors());
+
+        monitoring.monitors[0].details = MonitorType::Process {
+            name: None,
+            command_line: None,
+            min_processes: 2,
+            max_processes: Some(1),
+            max_threads: Some(0),
+            forbidden_states: None,
+        };
+        monitoring.monitors[1].details = MonitorType::Process {
+            name: Some(String::new()),
+            command_line: Some("java (".to_string()),
+            min_processes: 1,
+            max_processes: None,
+            max_threads: None,
+            forbidden_states: None,
+        };
+        let errors = monitoring.get_validation_errors();
+        assert_eq!(errors[..3], [
+            "Monitor Nginx: process monitor needs a name or commandLine".to_string(),
+            "Monitor Nginx: maxProcesses 1 must not be below minProcesses 2".to_string(),
+            "Monitor Nginx: maxThreads must be at least 1".to_string(),
+        ]);
+        assert_eq!(errors[3], "Monitor Service: name must not be empty");
+        assert!(errors[4].starts_with("Monitor Service: commandLine java ( is not a valid regular expression"));
+        assert_eq!(errors.len(), 5);
+        Ok(())
+    }
+
     /**
      * Test for a simple systemctl monitor.
      */
     #[test]
     fn test_simple_systemctl_file() -> Result<(), ApplicationError> {
         let monitoring: MonitoringConfig =
-            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_systemctl.json")?;
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_systemctl.json", None)?;
         assert_eq!("0 0 0 0 0 0 0".to_string(), monitoring.monitors[0].schedule);
         assert_eq!(1, monitoring.monitors.len());
         let monitor = monitoring.monitors[0].details.clone();
@@ -517,4 +1871,130 @@ mod tests {
         Ok(())
     }        
 
+    /**
+     * Test for webhook notifications.
+     */
+    #[test]
+    fn test_simple_webhook_file() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_webhook.json", None)?;
+        let webhooks = monitoring.notifications.unwrap().webhooks;
+        assert_eq!(2, webhooks.len());
+        assert_eq!("http://127.0.0.1:9000/hook", webhooks[0].url);
+        assert_eq!(Some(&"Bearer token".to_string()), webhooks[0].headers.as_ref().unwrap().get("Authorization"));
+        assert_eq!(2, webhooks[0].timeout);
+        assert_eq!(1, webhooks[0].retries);
+        assert_eq!(1, webhooks[0].retry_delay);
+        assert_eq!(None, webhooks[1].headers);
+        assert_eq!(10, webhooks[1].timeout);
+        assert_eq!(3, webhooks[1].retries);
+        assert_eq!(5, webhooks[1].retry_delay);
+        Ok(())
+    }
+
+    /**
+     * Test for email notifications.
+     */
+    #[test]
+    fn test_simple_email_file() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_email.json", None)?;
+        let email = monitoring.notifications.unwrap().email.unwrap();
+        assert_eq!("smtp.example.com", email.host);
+        assert_eq!(Some(587), email.port);
+        assert_eq!(SmtpSecurity::StartTls, email.security);
+        assert_eq!(Some("user".to_string()), email.username);
+        assert_eq!(Some("password".to_string()), email.password);
+        assert_eq!("agent@example.com", email.from);
+        assert_eq!(vec!["ops@example.com".to_string()], email.to);
+        assert_eq!(10, email.timeout);
+        assert!(!email.accept_invalid_certs);
+        assert_eq!(Some(vec!["db@example.com".to_string()]), monitoring.monitors[0].email_recipients);
+        assert_eq!(None, monitoring.monitors[1].email_recipients);
+        Ok(())
+    }
+
+    /**
+     * Test for monitor thresholds.
+     */
+    #[test]
+    fn test_simple_thresholds_file() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_simple_thresholds.json", None)?;
+        assert_eq!(3, monitoring.monitors[0].failure_threshold);
+        assert_eq!(2, monitoring.monitors[0].success_threshold);
+        assert_eq!(1, monitoring.monitors[1].failure_threshold);
+        assert_eq!(1, monitoring.monitors[1].success_threshold);
+        assert_eq!(Some(FlapDetectionConfig { window: 300, max_transitions: 4 }), monitoring.monitors[0].flap_detection);
+        assert_eq!(None, monitoring.monitors[1].flap_detection);
+        Ok(())
+    }
+
+    /**
+     * Test that the monitors of the fragments in the include directory are merged in file name order.
+     */
+    #[test]
+    fn test_include_file() -> Result<(), ApplicationError> {
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/include_test/test_include.json", None)?;
+        let names: Vec<&str> = monitoring.monitors.iter().map(|monitor| monitor.name.as_str()).collect();
+        assert_eq!(vec!["main", "app1", "app2"], names);
+        assert_eq!(Some("conf.d".to_string()), monitoring.include);
+
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/include_test/test_include_pattern.json", None)?;
+        let names: Vec<&str> = monitoring.monitors.iter().map(|monitor| monitor.name.as_str()).collect();
+        assert_eq!(vec!["app1"], names);
+        Ok(())
+    }
+
+    /**
+     * Test that a monitor name used in two files is rejected with both files in the error.
+     */
+    #[test]
+    fn test_include_duplicate_names() {
+        let err = MonitoringConfig::new("resources/test/include_test/test_include_duplicate.json", None).unwrap_err();
+        assert_eq!(
+            "Duplicate monitor name: app1 in resources/test/include_test/test_include_duplicate.json and resources/test/include_test/conf.d/10-app1.json",
+            err.get_message()
+        );
+    }
+
+    /**
+     * Test that a missing include directory is reported.
+     */
+    #[test]
+    fn test_include_missing_directory() {
+        assert!(!MonitoringConfig::matches_pattern("app.yaml", "*.json"));
+        assert!(MonitoringConfig::matches_pattern("app.json", "*.json"));
+        let err = MonitoringConfig::get_fragment_paths("resources/test/include_test/test_include.json", "missing").unwrap_err();
+        assert!(err.get_message().starts_with("Could not read include directory resources/test/include_test/missing"));
+    }
+
+    /**
+     * Test for environment variables and secret files in the configuration.
+     */
+    #[test]
+    fn test_secrets_file() -> Result<(), ApplicationError> {
+        std::env::set_var("MONITORING_AGENT_TEST_DB_PASSWORD", "env-db-password");
+        std::env::set_var("MONITORING_AGENT_TEST_HOST", "health.example.com");
+        std::env::set_var("MONITORING_AGENT_TEST_BEARER_TOKEN", "env-bearer-token");
+        let monitoring: MonitoringConfig =
+            MonitoringConfig::new("resources/test/configuration_import_test/test_secrets.json", None)?;
+        assert_eq!("env-db-password", monitoring.database.clone().unwrap().password);
+        let MonitorType::Http { url, headers, identity_password, .. } = monitoring.monitors[0].details.clone() else {
+            panic!("Expected http monitor");
+        };
+        assert_eq!("https://health.example.com/health", url);
+        assert_eq!(Some(&"Bearer env-bearer-token".to_string()), headers.unwrap().get("Authorization"));
+        assert_eq!(Some("file-secret-password".to_string()), identity_password);
+
+        let debug = format!("{monitoring:?}");
+        assert!(!debug.contains("env-db-password"));
+        assert!(!debug.contains("env-bearer-token"));
+        assert!(!debug.contains("file-secret-password"));
+        assert!(debug.contains("***"));
+        Ok(())
+    }
+
 }
@@ -5,13 +5,15 @@
  * `monitorstatus`: The monitor status. Used to represent the status of a monitor in the services.
  * `configuration`: The configuration. Used to represent the configuration of the monitoring agent daemon.
  * `args`: The application arguments. Used to represent the arguments passed to the application.
+ * `secrets`: The secrets interpolated in the configuration. Used to redact the secrets from logs and debug output.
  */
 mod applicationerror;
 mod monitorstatus;
 pub mod configuration;
 pub mod args;
+pub mod secrets;
 
 pub use crate::common::applicationerror::ApplicationError;
-pub use crate::common::monitorstatus::{MonitorStatus, Status};
-pub use crate::common::configuration::{Monitor, MonitorType, HttpMethod, DatabaseConfig};
+pub use crate::common::monitorstatus::{MonitorStatus, MonitorHistoryEntry, Status};
+pub use crate::common::configuration::{ConfigFormat, Monitor, MonitorType, HttpMethod, DnsRecordType, ProcessStateType, DatabaseConfig, NotificationConfig, WebhookConfig, EmailConfig, SmtpSecurity};
 pub use crate::common::args::ApplicationArguments;
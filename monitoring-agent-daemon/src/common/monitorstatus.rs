@@ -1,5 +1,13 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
 
+use crate::common::configuration::FlapDetectionConfig;
+
+/// The maximum number of check results kept in the history of each monitor.
+pub const MAX_HISTORY_ENTRIES: usize = 1000;
+
 /**
  * `MonitorStatus` struct
  * 
@@ -8,6 +16,21 @@ use chrono::{DateTime, Utc};
  * - `last_successful_time`: The last time the monitor was successful
  * - `last_error`: The last error message
  * - `last_error_time`: The last time the monitor encountered an error
+ * - `last_warning`: The last warning message
+ * - `last_warning_time`: The last time the monitor reported a warning
+ * - `failure_threshold`: The number of consecutive failed checks before the status changes to error
+ * - `success_threshold`: The number of consecutive successful checks before the status changes from error
+ * - `consecutive_failures`: The number of consecutive failed checks
+ * - `consecutive_successes`: The number of consecutive successful checks
+ * - `check_status`: The status given by the checks before flap detection is applied
+ * - `flap_detection`: The flap detection configuration
+ * - `transitions`: The times of the recent status transitions
+ * - `history`: The most recent check results
+ * - `last_duration`: The duration of the last check
+ * - `skipped_checks`: The number of scheduled checks skipped because the previous check was still in progress
+ * - `timed_out_checks`: The number of checks that timed out
+ * - `timeout`: The maximum duration of each check
+ * - `check_in_progress`: Whether a check of the monitor is in progress
  *
  */
 #[derive(Debug, Clone, PartialEq)]
@@ -22,6 +45,55 @@ pub struct MonitorStatus {
     pub last_error: Option<String>,
     /// The last time the monitor encountered an error.
     pub last_error_time: Option<DateTime<Utc>>,
+    /// The last warning message.
+    pub last_warning: Option<String>,
+    /// The last time the monitor reported a warning.
+    pub last_warning_time: Option<DateTime<Utc>>,
+    /// The number of consecutive failed checks before the status changes to error.
+    pub failure_threshold: u32,
+    /// The number of consecutive successful checks before the status changes from error.
+    pub success_threshold: u32,
+    /// The number of consecutive failed checks.
+    pub consecutive_failures: u32,
+    /// The number of consecutive successful checks.
+    pub consecutive_successes: u32,
+    /// The status given by the checks before flap detection is applied.
+    pub check_status: Status,
+    /// The flap detection configuration. If None, then flap detection is disabled.
+    pub flap_detection: Option<FlapDetectionConfig>,
+    /// The times of the recent status transitions. Only recorded when flap detection is enabled.
+    pub transitions: VecDeque<DateTime<Utc>>,
+    /// The most recent check results, oldest first. Bounded by `MAX_HISTORY_ENTRIES`.
+    pub history: VecDeque<MonitorHistoryEntry>,
+    /// The duration of the last check.
+    pub last_duration: Option<Duration>,
+    /// The number of scheduled checks skipped because the previous check was still in progress.
+    pub skipped_checks: u64,
+    /// The number of checks that timed out.
+    pub timed_out_checks: u64,
+    /// The maximum duration of each check, scheduled or on demand. If None, checks run until they finish.
+    pub timeout: Option<Duration>,
+    /// Whether a check of the monitor is in progress. Shared by the scheduled job and the checks run on demand.
+    pub check_in_progress: bool,
+}
+
+/**
+ * `MonitorHistoryEntry` struct
+ *
+ * This struct represents the result of a single check.
+ * - `time`: The time of the check
+ * - `status`: The status returned by the check
+ * - `duration`: The duration of the check
+ *
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorHistoryEntry {
+    /// The time of the check.
+    pub time: DateTime<Utc>,
+    /// The status returned by the check.
+    pub status: Status,
+    /// The duration of the check.
+    pub duration: Duration,
 }
 
 impl MonitorStatus {
@@ -34,31 +106,266 @@ impl MonitorStatus {
     pub fn new(name: String, status: Status) -> MonitorStatus {
         MonitorStatus {
             name,
+            check_status: status.clone(),
             status,
             last_successful_time: None,
             last_error: None,
             last_error_time: None,
+            last_warning: None,
+            last_warning_time: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            flap_detection: None,
+            transitions: VecDeque::new(),
+            history: VecDeque::new(),
+            last_duration: None,
+            skipped_checks: 0,
+            timed_out_checks: 0,
+            timeout: None,
+            check_in_progress: false,
+        }
+    }
+
+    /**
+     * Set the flap detection configuration.
+     *
+     * `flap_detection`: The flap detection configuration. If None, then flap detection is disabled.
+     *
+     */
+    pub fn set_flap_detection(&mut self, flap_detection: Option<FlapDetectionConfig>) {
+        self.flap_detection = flap_detection;
+        self.transitions.clear();
+    }
+
+    /**
+     * Set the thresholds of the monitor. A threshold of 0 is treated as 1.
+     *
+     * `failure_threshold`: The number of consecutive failed checks before the status changes to error.
+     * `success_threshold`: The number of consecutive successful checks before the status changes from error.
+     *
+     */
+    pub fn set_thresholds(&mut self, failure_threshold: u32, success_threshold: u32) {
+        self.failure_threshold = failure_threshold.max(1);
+        self.success_threshold = success_threshold.max(1);
+    }
+
+    /**
+     * Get the number of failed checks pending before the status changes to error.
+     *
+     * Returns: The number of consecutive failed checks, or 0 if the status already is error.
+     */
+    pub fn get_pending_failures(&self) -> u32 {
+        match self.check_status {
+            Status::Error { .. } => 0,
+            _ => self.consecutive_failures,
         }
     }
 
     /**
-     * Set the status of the monitor.
+     * Get the number of successful checks pending before the status changes from error.
+     *
+     * Returns: The number of consecutive successful checks, or 0 if the status is not error.
+     */
+    pub fn get_pending_successes(&self) -> u32 {
+        match self.check_status {
+            Status::Error { .. } => self.consecutive_successes,
+            _ => 0,
+        }
+    }
+
+    /**
+     * Set the status of the monitor. The status only changes to or from error when
+     * the number of consecutive checks reaches the threshold. If flap detection is enabled
+     * and the status changes too often, then the status is set to flapping until it stabilises.
      *
      * `status`: The new status.
+     * `duration`: The duration of the check.
      *
+     * Returns: The status of the monitor after the update.
      */
-    pub fn set_status(&mut self, status: &Status) {
+    pub fn set_status(&mut self, status: &Status, duration: Duration) -> Status {
+        self.last_duration = Some(duration);
+        self.add_history_entry(status, duration);
+        if self.status == Status::Paused {
+            return Status::Paused;
+        }
+        if !self.is_threshold_reached(status) {
+            return self.status.clone();
+        }
         match status {
             Status::Error { message } => {
                 self.last_error_time = Some(chrono::Utc::now());
                 self.last_error = Some(message.clone());
             }
+            Status::Warning { message } => {
+                self.last_warning_time = Some(chrono::Utc::now());
+                self.last_warning = Some(message.clone());
+            }
             Status::Ok => {
                 self.last_successful_time = Some(chrono::Utc::now());
             }
-            Status::Unknown => {}
+            Status::Unknown | Status::Flapping { .. } | Status::Paused => {}
+        }
+        self.record_transition(status);
+        self.check_status = status.clone();
+        self.status = self.get_flapping_status().unwrap_or_else(|| status.clone());
+        self.status.clone()
+    }
+
+    /**
+     * Pause the monitor. The status stays paused until the monitor is resumed.
+     */
+    pub fn pause(&mut self) {
+        self.status = Status::Paused;
+    }
+
+    /**
+     * Resume the monitor. The status is unknown until the next check.
+     */
+    pub fn resume(&mut self) {
+        if self.status != Status::Paused {
+            return;
+        }
+        self.status = Status::Unknown;
+        self.check_status = Status::Unknown;
+        self.consecutive_failures = 0;
+        self.consecutive_successes = 0;
+        self.transitions.clear();
+    }
+
+    /**
+     * Add a check result to the history. The oldest entry is removed when the history is full.
+     *
+     * `status`: The status returned by the check.
+     * `duration`: The duration of the check.
+     *
+     */
+    fn add_history_entry(&mut self, status: &Status, duration: Duration) {
+        if self.history.len() >= MAX_HISTORY_ENTRIES {
+            self.history.pop_front();
+        }
+        self.history.push_back(MonitorHistoryEntry {
+            time: Utc::now(),
+            status: status.clone(),
+            duration,
+        });
+    }
+
+    /**
+     * Get a copy of the status without the check history and the recorded transitions.
+     * Used when listing all monitors, where the history is not needed.
+     *
+     * Returns: The status without history.
+     */
+    pub fn without_history(&self) -> MonitorStatus {
+        MonitorStatus {
+            name: self.name.clone(),
+            status: self.status.clone(),
+            last_successful_time: self.last_successful_time,
+            last_error: self.last_error.clone(),
+            last_error_time: self.last_error_time,
+            last_warning: self.last_warning.clone(),
+            last_warning_time: self.last_warning_time,
+            failure_threshold: self.failure_threshold,
+            success_threshold: self.success_threshold,
+            consecutive_failures: self.consecutive_failures,
+            consecutive_successes: self.consecutive_successes,
+            check_status: self.check_status.clone(),
+            flap_detection: self.flap_detection.clone(),
+            transitions: VecDeque::new(),
+            history: VecDeque::new(),
+            last_duration: self.last_duration,
+            skipped_checks: self.skipped_checks,
+            timed_out_checks: self.timed_out_checks,
+            timeout: self.timeout,
+            check_in_progress: self.check_in_progress,
+        }
+    }
+
+    /**
+     * Get the check results from the history.
+     *
+     * `since`: Only return results after this time.
+     * `limit`: The maximum number of results. The most recent results are returned.
+     *
+     * Returns: The check results, oldest first.
+     */
+    pub fn get_history(&self, since: Option<DateTime<Utc>>, limit: Option<usize>) -> Vec<MonitorHistoryEntry> {
+        let entries: Vec<MonitorHistoryEntry> = self
+            .history
+            .iter()
+            .filter(|entry| since.is_none_or(|since| entry.time > since))
+            .cloned()
+            .collect();
+        match limit {
+            Some(limit) if limit < entries.len() => entries[entries.len() - limit..].to_vec(),
+            _ => entries,
+        }
+    }
+
+    /**
+     * Record the time of a status transition if flap detection is enabled.
+     * Transitions from the initial unknown status are not recorded.
+     *
+     * `status`: The new status.
+     *
+     */
+    fn record_transition(&mut self, status: &Status) {
+        if self.flap_detection.is_none() || self.check_status == Status::Unknown {
+            return;
+        }
+        if std::mem::discriminant(&self.check_status) != std::mem::discriminant(status) {
+            self.transitions.push_back(Utc::now());
+        }
+    }
+
+    /**
+     * Remove transitions outside the flap detection window and check if the monitor is flapping.
+     *
+     * Returns: The flapping status if the number of transitions within the window exceeds the limit.
+     */
+    fn get_flapping_status(&mut self) -> Option<Status> {
+        let flap_detection = self.flap_detection.as_ref()?;
+        let window_start = Utc::now() - Duration::from_secs(flap_detection.window);
+        while self.transitions.front().is_some_and(|time| *time < window_start) {
+            self.transitions.pop_front();
+        }
+        if self.transitions.len() > flap_detection.max_transitions as usize {
+            Some(Status::Flapping {
+                message: format!("Changed status {} times within {} seconds", self.transitions.len(), flap_detection.window),
+            })
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Count the check result and verify if the threshold for changing status is reached.
+     *
+     * `status`: The status of the check.
+     *
+     * Returns: True if the status should be changed.
+     */
+    fn is_threshold_reached(&mut self, status: &Status) -> bool {
+        match status {
+            Status::Error { .. } => {
+                self.consecutive_failures += 1;
+                self.consecutive_successes = 0;
+                matches!(self.check_status, Status::Error { .. }) || self.consecutive_failures >= self.failure_threshold
+            }
+            Status::Ok | Status::Warning { .. } => {
+                self.consecutive_successes += 1;
+                self.consecutive_failures = 0;
+                !matches!(self.check_status, Status::Error { .. }) || self.consecutive_successes >= self.success_threshold
+            }
+            Status::Unknown | Status::Flapping { .. } | Status::Paused => {
+                self.consecutive_failures = 0;
+                self.consecutive_successes = 0;
+                true
+            }
         }
-        self.status = status.clone();
     }
 }
 
@@ -68,7 +375,10 @@ impl MonitorStatus {
  * This enum is used to represent the status of a monitor. It can be one of the following:
  * - Ok: The monitor is working correctly
  * - Unknown: The monitor status is unknown
+ * - Warning: The monitor is working, but a warning threshold has been passed. The warning message is stored in the message field
  * - Error: The monitor has encountered an error. The error message is stored in the message field
+ * - Flapping: The monitor changes status too often. Further status changes are suppressed until it stabilises
+ * - Paused: The monitor is paused. The monitor is not scheduled and the status does not change until it is resumed
  *
  */
 #[derive(Debug, Clone, PartialEq)]
@@ -77,8 +387,14 @@ pub enum Status {
     Ok,
     /// The monitor status is unknown.
     Unknown,
+    /// The monitor has passed a warning threshold. The warning message is stored in the message field.
+    Warning { message: String },
     /// The monitor has encountered an error. The error message is stored in the message field.
     Error { message: String },
+    /// The monitor changes status too often. The reason is stored in the message field.
+    Flapping { message: String },
+    /// The monitor is paused.
+    Paused,
 }
 
 #[cfg(test)]
@@ -103,7 +419,7 @@ mod test {
         let name = "test_monitor";
         let status = Status::Ok;
         let mut monitorstatus = MonitorStatus::new(name.to_string(), status.clone());
-        monitorstatus.set_status(&status);
+        monitorstatus.set_status(&status, Duration::ZERO);
         assert_eq!(monitorstatus.status, status);
         assert!(monitorstatus.last_successful_time.is_some());
         assert_eq!(monitorstatus.last_error, None);
@@ -112,11 +428,159 @@ mod test {
         let status = Status::Error {
             message: "test error".to_string(),
         };
-        monitorstatus.set_status(&status);
+        monitorstatus.set_status(&status, Duration::ZERO);
         assert_eq!(monitorstatus.status, status);
         assert!(monitorstatus.last_successful_time.is_some());
         assert_eq!(monitorstatus.last_error, Some("test error".to_string()));
         assert!(monitorstatus.last_error_time.is_some());
+
+        let status = Status::Warning {
+            message: "test warning".to_string(),
+        };
+        monitorstatus.set_status(&status, Duration::ZERO);
+        assert_eq!(monitorstatus.status, status);
+        assert_eq!(monitorstatus.last_error, Some("test error".to_string()));
+        assert_eq!(monitorstatus.last_warning, Some("test warning".to_string()));
+        assert!(monitorstatus.last_warning_time.is_some());
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_monitorstatus_failure_threshold() {
+        let error = Status::Error { message: "test error".to_string() };
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Ok);
+        monitorstatus.set_thresholds(3, 2);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.get_pending_failures(), 1);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.get_pending_failures(), 2);
+        assert_eq!(monitorstatus.last_error, None);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), error);
+        assert_eq!(monitorstatus.get_pending_failures(), 0);
+        assert_eq!(monitorstatus.last_error, Some("test error".to_string()));
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), error);
+        assert_eq!(monitorstatus.get_pending_successes(), 1);
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.get_pending_successes(), 0);
+    }
+
+    #[test]
+    fn test_monitorstatus_failure_threshold_reset() {
+        let error = Status::Error { message: "test error".to_string() };
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Ok);
+        monitorstatus.set_thresholds(2, 1);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.get_pending_failures(), 0);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), error);
+    }
+
+    #[test]
+    fn test_monitorstatus_flapping() {
+        let error = Status::Error { message: "test error".to_string() };
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Unknown);
+        monitorstatus.set_flap_detection(Some(FlapDetectionConfig { window: 600, max_transitions: 2 }));
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+        assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), error);
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+        assert_eq!(
+            monitorstatus.set_status(&error, Duration::ZERO),
+            Status::Flapping { message: "Changed status 3 times within 600 seconds".to_string() }
+        );
+        assert_eq!(monitorstatus.check_status, error);
+        assert!(matches!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Flapping { .. }));
+        assert_eq!(monitorstatus.last_error, Some("test error".to_string()));
+    }
+
+    #[test]
+    fn test_monitorstatus_flapping_stabilised() {
+        let error = Status::Error { message: "test error".to_string() };
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Ok);
+        monitorstatus.set_flap_detection(Some(FlapDetectionConfig { window: 600, max_transitions: 1 }));
+        monitorstatus.set_status(&error, Duration::ZERO);
+        assert!(matches!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Flapping { .. }));
+        let old_transition = Utc::now() - Duration::from_secs(1200);
+        monitorstatus.transitions = VecDeque::from([old_transition, old_transition]);
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+        assert!(monitorstatus.transitions.is_empty());
+    }
+
+    #[test]
+    fn test_monitorstatus_without_flap_detection() {
+        let error = Status::Error { message: "test error".to_string() };
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Ok);
+        for _ in 0..10 {
+            assert_eq!(monitorstatus.set_status(&error, Duration::ZERO), error);
+            assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+        }
+        assert!(monitorstatus.transitions.is_empty());
+    }
+
+    #[test]
+    fn test_monitorstatus_pause_resume() {
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Unknown);
+        monitorstatus.set_status(&Status::Error { message: "error".to_string() }, Duration::ZERO);
+        monitorstatus.pause();
+        assert_eq!(monitorstatus.status, Status::Paused);
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Paused);
+        assert_eq!(monitorstatus.history.len(), 2);
+        monitorstatus.resume();
+        assert_eq!(monitorstatus.status, Status::Unknown);
+        assert_eq!(monitorstatus.get_pending_failures(), 0);
+        assert_eq!(monitorstatus.set_status(&Status::Ok, Duration::ZERO), Status::Ok);
+    }
+
+    #[test]
+    fn test_monitorstatus_duration() {
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Unknown);
+        assert_eq!(monitorstatus.last_duration, None);
+        monitorstatus.set_status(&Status::Ok, Duration::from_millis(150));
+        assert_eq!(monitorstatus.last_duration, Some(Duration::from_millis(150)));
+        assert_eq!(monitorstatus.history[0].duration, Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_monitorstatus_history() {
+        let error = Status::Error { message: "test error".to_string() };
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Unknown);
+        monitorstatus.set_thresholds(2, 1);
+        monitorstatus.set_status(&Status::Ok, Duration::ZERO);
+        monitorstatus.set_status(&error, Duration::ZERO);
+        monitorstatus.set_status(&Status::Ok, Duration::ZERO);
+        let history = monitorstatus.get_history(None, None);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].status, Status::Ok);
+        assert_eq!(history[1].status, error);
+        assert_eq!(history[2].status, Status::Ok);
+        assert_eq!(history[2].duration, Duration::ZERO);
+        let history = monitorstatus.get_history(None, Some(2));
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status, error);
+        let history = monitorstatus.get_history(Some(Utc::now()), None);
+        assert!(history.is_empty());
+        let history = monitorstatus.get_history(Some(Utc::now() - Duration::from_secs(60)), Some(10));
+        assert_eq!(history.len(), 3);
+    }
+
+    #[test]
+    fn test_monitorstatus_without_history() {
+        let mut monitorstatus = MonitorStatus::new("test".to_string(), Status::Unknown);
+        monitorstatus.set_status(&Status::Ok, Duration::from_millis(10));
+        monitorstatus.skipped_checks = 2;
+        let view = monitorstatus.without_history();
+        assert!(view.history.is_empty());
+        assert_eq!(view.status, Status::Ok);
+        assert_eq!(view.last_duration, Some(Duration::from_millis(10)));
+        assert_eq!(view.skipped_checks, 2);
+        assert_eq!(monitorstatus.history.len(), 1);
+    }
+
+    #[test]
+    fn test_monitorstatus_history_bounded() {
+        let mut monitorstatus = MonitorStatus::new("test_monitor".to_string(), Status::Unknown);
+        for _ in 0..MAX_HISTORY_ENTRIES + 10 {
+            monitorstatus.set_status(&Status::Ok, Duration::ZERO);
+        }
+        assert_eq!(monitorstatus.history.len(), MAX_HISTORY_ENTRIES);
+    }
+}
@@ -0,0 +1,356 @@
+use std::fmt;
+use std::io::Write;
+use std::sync::RwLock;
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::common::ApplicationError;
+
+/// The text shown instead of a secret.
+const REDACTED: &str = "***";
+
+/// The configuration keys holding secrets. Their values are always redacted in debug output.
+const SECRET_KEYS: [&str; 3] = ["password", "identityPassword", "headers"];
+
+/// The secrets resolved from the environment and secret files. Redacted from the logs and debug output.
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/**
+ * Interpolate the environment variables and secret files in the configuration.
+ *
+ * `${NAME}` in a string is replaced with the value of the environment variable NAME, and `$${` is replaced with `${`.
+ * An object with only a file field, e.g. `{"file": "/run/secrets/db"}`, is replaced with the content of the file
+ * without trailing newlines. The resolved values are registered as secrets.
+ *
+ * `value`: The configuration.
+ *
+ * result: True if anything was interpolated.
+ *
+ * throws: `ApplicationError`: If an environment variable is not set or a secret file can not be read.
+ */
+pub fn interpolate(value: &mut Value) -> Result<bool, ApplicationError> {
+    interpolate_value(value, "")
+}
+
+/**
+ * Interpolate a configuration value.
+ *
+ * `value`: The configuration value.
+ * `path`: The path of the value, used in error messages.
+ *
+ * result: True if anything was interpolated.
+ *
+ * throws: `ApplicationError`: If an environment variable is not set or a secret file can not be read.
+ */
+fn interpolate_value(value: &mut Value, path: &str) -> Result<bool, ApplicationError> {
+    match value {
+        Value::String(text) => {
+            if !text.contains("${") {
+                return Ok(false);
+            }
+            *text = interpolate_string(text, path)?;
+            Ok(true)
+        }
+        Value::Object(object) => {
+            if let (1, Some(Value::String(file))) = (object.len(), object.get("file")) {
+                let secret = read_secret_file(file, path)?;
+                *value = Value::String(secret);
+                return Ok(true);
+            }
+            let mut interpolated = false;
+            for (key, child) in object.iter_mut() {
+                interpolated |= interpolate_value(child, &format!("{path}.{key}"))?;
+            }
+            Ok(interpolated)
+        }
+        Value::Array(array) => {
+            let mut interpolated = false;
+            for (index, child) in array.iter_mut().enumerate() {
+                interpolated |= interpolate_value(child, &format!("{path}[{index}]"))?;
+            }
+            Ok(interpolated)
+        }
+        _ => Ok(false),
+    }
+}
+
+/**
+ * Replace the environment variables in a string.
+ *
+ * `text`: The string.
+ * `path`: The path of the string, used in error messages.
+ *
+ * result: The string with the environment variables replaced.
+ *
+ * throws: `ApplicationError`: If an environment variable is not set or not terminated.
+ */
+fn interpolate_string(text: &str, path: &str) -> Result<String, ApplicationError> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(ApplicationError::new(
+                format!("Unterminated environment variable in {}", get_path(path)).as_str(),
+            ));
+        };
+        let name = &rest[start + 2..start + end];
+        let secret = std::env::var(name).map_err(|_| ApplicationError::new(
+            format!("Environment variable {name} used in {} is not set", get_path(path)).as_str(),
+        ))?;
+        register(&secret);
+        result.push_str(&secret);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/**
+ * Read a secret file.
+ *
+ * `file`: The path to the secret file.
+ * `path`: The path of the configuration value, used in error messages.
+ *
+ * result: The content of the file without trailing newlines.
+ *
+ * throws: `ApplicationError`: If the file can not be read.
+ */
+fn read_secret_file(file: &str, path: &str) -> Result<String, ApplicationError> {
+    let content = std::fs::read_to_string(file).map_err(|err| ApplicationError::new(
+        format!("Could not read secret file {file} used in {}, error: {err}", get_path(path)).as_str(),
+    ))?;
+    let secret = content.trim_end_matches(['\n', '\r']).to_string();
+    register(&secret);
+    Ok(secret)
+}
+
+/**
+ * Get the path of a configuration value for error messages.
+ */
+fn get_path(path: &str) -> &str {
+    path.strip_prefix('.').unwrap_or(path)
+}
+
+/**
+ * Register a secret. The secret is redacted from the logs and debug output.
+ *
+ * `secret`: The secret.
+ */
+pub fn register(secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+    if let Ok(mut secrets) = SECRETS.write() {
+        if !secrets.iter().any(|registered| registered == secret) {
+            secrets.push(secret.to_string());
+            /*
+             * Replace the longest secrets first, so secrets containing other secrets are fully redacted.
+             */
+            secrets.sort_by_key(|registered| std::cmp::Reverse(registered.len()));
+        }
+    }
+}
+
+/**
+ * Redact the registered secrets from a text.
+ *
+ * `text`: The text.
+ *
+ * result: The text with the secrets replaced with ***.
+ */
+pub fn redact(text: &str) -> String {
+    let Ok(secrets) = SECRETS.read() else {
+        return text.to_string();
+    };
+    let mut redacted = text.to_string();
+    for secret in secrets.iter() {
+        if redacted.contains(secret.as_str()) {
+            redacted = redacted.replace(secret.as_str(), REDACTED);
+        }
+    }
+    redacted
+}
+
+/**
+ * Format a configuration value for debug output. The values of the secret keys and the registered secrets are
+ * redacted.
+ *
+ * `name`: The name of the type.
+ * `value`: The configuration value.
+ * `formatter`: The formatter.
+ *
+ * result: The result of formatting the value.
+ */
+pub fn fmt_redacted<T: Serialize>(name: &str, value: &T, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut value = serde_json::to_value(value).map_err(|_| fmt::Error)?;
+    redact_secret_keys(&mut value);
+    write!(formatter, "{name} {}", redact(&value.to_string()))
+}
+
+/**
+ * Redact the values of the secret keys.
+ *
+ * `value`: The configuration value.
+ */
+fn redact_secret_keys(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) {
+                    redact_all(child);
+                } else {
+                    redact_secret_keys(child);
+                }
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(redact_secret_keys),
+        _ => {}
+    }
+}
+
+/**
+ * Redact all strings in a configuration value.
+ *
+ * `value`: The configuration value.
+ */
+fn redact_all(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = REDACTED.to_string(),
+        Value::Object(object) => object.values_mut().for_each(redact_all),
+        Value::Array(array) => array.iter_mut().for_each(redact_all),
+        _ => {}
+    }
+}
+
+/**
+ * Log writer factory that redacts the registered secrets.
+ */
+pub struct RedactingMakeWriter<M> {
+    /// The writer factory to write the redacted logs to.
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    /**
+     * Create a new redacting writer factory.
+     *
+     * `inner`: The writer factory to write the redacted logs to.
+     *
+     * result: The redacting writer factory.
+     */
+    pub fn new(inner: M) -> RedactingMakeWriter<M> {
+        RedactingMakeWriter { inner }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
+
+/**
+ * Log writer that redacts the registered secrets. Each log event is written with a single write.
+ */
+pub struct RedactingWriter<W> {
+    /// The writer to write the redacted logs to.
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let redacted = redact(&String::from_utf8_lossy(buf));
+        self.inner.write_all(redacted.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_interpolate_environment_variables() {
+        std::env::set_var("MONITORING_AGENT_TEST_TOKEN", "secret-token-1");
+        let mut value = json!({"headers": {"Authorization": "Bearer ${MONITORING_AGENT_TEST_TOKEN}"}, "escaped": "$${HOME}", "port": 80});
+        assert!(interpolate(&mut value).unwrap());
+        assert_eq!(value, json!({"headers": {"Authorization": "Bearer secret-token-1"}, "escaped": "${HOME}", "port": 80}));
+        assert_eq!(redact("token secret-token-1 used"), "token *** used");
+
+        let mut value = json!({"name": "no variables"});
+        assert!(!interpolate(&mut value).unwrap());
+    }
+
+    /**
+     * Test that the values resolved in any field are registered as secrets, including short values.
+     */
+    #[test]
+    fn test_interpolate_registers_all_values() {
+        std::env::set_var("MONITORING_AGENT_TEST_SLACK_TOKEN", "slack-token-4");
+        std::env::set_var("MONITORING_AGENT_TEST_SHORT", "q7z");
+        let mut value = json!({"url": "https://hooks.example.com/${MONITORING_AGENT_TEST_SLACK_TOKEN}", "username": "${MONITORING_AGENT_TEST_SHORT}"});
+        assert!(interpolate(&mut value).unwrap());
+        assert_eq!(value, json!({"url": "https://hooks.example.com/slack-token-4", "username": "q7z"}));
+        assert_eq!(redact("posting to https://hooks.example.com/slack-token-4 as q7z"), "posting to https://hooks.example.com/*** as ***");
+    }
+
+    #[test]
+    fn test_interpolate_missing_environment_variable() {
+        let mut value = json!({"monitors": [{"url": "${MONITORING_AGENT_TEST_MISSING}"}]});
+        assert_eq!(
+            interpolate(&mut value).unwrap_err().get_message(),
+            "Environment variable MONITORING_AGENT_TEST_MISSING used in monitors[0].url is not set"
+        );
+        let mut value = json!({"url": "${MONITORING_AGENT_TEST_MISSING"});
+        assert_eq!(interpolate(&mut value).unwrap_err().get_message(), "Unterminated environment variable in url");
+    }
+
+    #[test]
+    fn test_interpolate_secret_file() {
+        let path = format!("/tmp/monitoring-agent-secret-{}", std::process::id());
+        std::fs::write(&path, "secret-password-2\n").unwrap();
+        let mut value = json!({"database": {"password": {"file": path}, "host": {"file": path}, "other": {"file": "a", "b": "c"}}});
+        assert!(interpolate(&mut value).unwrap());
+        assert_eq!(value, json!({"database": {"password": "secret-password-2", "host": "secret-password-2", "other": {"file": "a", "b": "c"}}}));
+        assert_eq!(redact("password secret-password-2"), "password ***");
+        let _ = std::fs::remove_file(&path);
+
+        let mut value = json!({"password": {"file": "/tmp/monitoring-agent-secret-missing"}});
+        assert!(interpolate(&mut value).unwrap_err().get_message().starts_with("Could not read secret file /tmp/monitoring-agent-secret-missing used in password"));
+    }
+
+    #[test]
+    fn test_redacting_writer() {
+        register("secret-log-3");
+        let mut output = Vec::new();
+        let mut writer = RedactingWriter { inner: &mut output };
+        writer.write_all(b"connecting with secret-log-3\n").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "connecting with ***\n");
+    }
+
+    #[test]
+    fn test_redact_secret_keys() {
+        let mut value = json!({"user": "root", "password": "pass", "headers": {"Authorization": "Bearer x"}, "list": [{"identityPassword": "p"}]});
+        redact_secret_keys(&mut value);
+        assert_eq!(value, json!({"user": "root", "password": "***", "headers": {"Authorization": "***"}, "list": [{"identityPassword": "***"}]}));
+    }
+}
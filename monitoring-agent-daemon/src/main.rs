@@ -3,24 +3,32 @@ mod services;
 mod api;
 
 use std::fs::File;
+use std::io::Write;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use clap::Parser;
-use common::configuration::{DatabaseConfig, MonitoringConfig, ServerConfig};
-use common::ApplicationError;
+use common::configuration::{DatabaseConfig, MonitoringConfig, NotificationConfig, ServerConfig};
+use common::{ApplicationError, ConfigFormat};
 use daemonize::Daemonize;
-use log::{debug, error, info};
-use actix_web::{web, App, HttpServer};
-use services::SchedulingService;
+use log::{debug, error, info, warn};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+use actix_web::{dev::ServerHandle, web, App, HttpServer};
+use services::{SchedulingService, SdNotifyService};
 use tracing_subscriber::{filter, prelude::*};
 
 use crate::common::ApplicationArguments;
+use crate::common::secrets::RedactingMakeWriter;
 use crate::api::StateApi;
-use crate::services::{MonitoringService, DbService};
+use crate::services::{MonitoringService, DbService, NotificationService};
 
-type StdioFilter = filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::layer::Layered<filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::Registry, tracing_subscriber::fmt::format::DefaultFields, tracing_subscriber::fmt::format::Format, Arc<File>>, filter::LevelFilter, tracing_subscriber::Registry>, tracing_subscriber::Registry>, tracing_subscriber::fmt::format::Pretty, tracing_subscriber::fmt::format::Format<tracing_subscriber::fmt::format::Pretty>>, filter::LevelFilter, tracing_subscriber::layer::Layered<filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::Registry, tracing_subscriber::fmt::format::DefaultFields, tracing_subscriber::fmt::format::Format, Arc<File>>, filter::LevelFilter, tracing_subscriber::Registry>, tracing_subscriber::Registry>>;
-type FileFilter = filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::Registry, tracing_subscriber::fmt::format::DefaultFields, tracing_subscriber::fmt::format::Format, Arc<File>>, filter::LevelFilter, tracing_subscriber::Registry>;
+/// The maximum time to wait for API requests and running checks to finish on shutdown.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+type StdioFilter = filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::layer::Layered<filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::Registry, tracing_subscriber::fmt::format::DefaultFields, tracing_subscriber::fmt::format::Format, RedactingMakeWriter<Arc<File>>>, filter::LevelFilter, tracing_subscriber::Registry>, tracing_subscriber::Registry>, tracing_subscriber::fmt::format::Pretty, tracing_subscriber::fmt::format::Format<tracing_subscriber::fmt::format::Pretty>, RedactingMakeWriter<fn() -> std::io::Stdout>>, filter::LevelFilter, tracing_subscriber::layer::Layered<filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::Registry, tracing_subscriber::fmt::format::DefaultFields, tracing_subscriber::fmt::format::Format, RedactingMakeWriter<Arc<File>>>, filter::LevelFilter, tracing_subscriber::Registry>, tracing_subscriber::Registry>>;
+type FileFilter = filter::Filtered<tracing_subscriber::fmt::Layer<tracing_subscriber::Registry, tracing_subscriber::fmt::format::DefaultFields, tracing_subscriber::fmt::format::Format, RedactingMakeWriter<Arc<File>>>, filter::LevelFilter, tracing_subscriber::Registry>;
 
 /**
  * Application entry point.
@@ -36,27 +44,50 @@ async fn main() -> Result<(), std::io::Error> {
      * Parse command line arguments.
      */
     let args: ApplicationArguments = ApplicationArguments::parse();
+    /*
+     * Print the configuration schema. Done before logging is set up, so the schema is the only output.
+     */
+    if args.print_schema {
+        let schema = serde_json::to_string_pretty(&MonitoringConfig::get_schema()).map_err(|err| {
+            std::io::Error::other(format!("Error creating configuration schema: {err}"))
+        })?;
+        writeln!(std::io::stdout(), "{schema}")?;
+        return Ok(());
+    }
     /*
      * Initialize logging.
      */
     setup_logging(args.logfile.as_str(), &args.stdout_errorlevel, &args.file_errorlevel).map_err(|err| {
         error!("Error setting up logging: {:?}", err);
-        std::io::Error::new(std::io::ErrorKind::Other, format!("Error setting up logging: {err:?}"))
+        std::io::Error::other(format!("Error setting up logging: {err:?}"))
     })?;
 
     /*
      * Load configuration.
      */
-    let monitoring_config = match MonitoringConfig::new(&args.config) {
+    let monitoring_config = match MonitoringConfig::new(&args.config, args.format) {
         Ok(monitoring_config) => {
             info!("Configuration loaded!");
             Ok(monitoring_config)
         }
         Err(err) => {
             error!("Error loading configuration: {:?}", err);
-            Err(std::io::Error::new(std::io::ErrorKind::Other, "Error loading configuration"))
+            Err(std::io::Error::other(format!("Error loading configuration: {}", err.get_message())))
         }
     }?;
+    /*
+     * Validate the configuration, reporting every problem found before exiting.
+     */
+    let validation_errors = monitoring_config.get_validation_errors();
+    if !validation_errors.is_empty() {
+        for validation_error in &validation_errors {
+            error!("Invalid configuration: {validation_error}");
+        }
+        return Err(std::io::Error::other(format!("Invalid configuration, {} problems found", validation_errors.len())));
+    }
+    if args.test {
+        info!("Configuration is valid!");
+    }
     /*
      * Start the application.
      */
@@ -89,6 +120,17 @@ async fn start_application(monitoring_config: &MonitoringConfig, args: &Applicat
         info!("No database configuration found!");
         Arc::new(None)
     };
+    /*
+     * Initialize notification service. A configured notification service that can not be created stops the agent,
+     * instead of running without notifications.
+     */
+    let notification_config = monitoring_config.notifications.clone();
+    let notification_service: Arc<Option<NotificationService>> = if let Some(notification_config) = notification_config {
+        Arc::new(Some(initialize_notification(&notification_config, &monitoring_config.server, &monitoring_config.monitors)?))
+    } else {
+        info!("No notification configuration found!");
+        Arc::new(None)
+    };
         
     /*
      * Initialize monitoring service.
@@ -97,21 +139,22 @@ async fn start_application(monitoring_config: &MonitoringConfig, args: &Applicat
     /*
      * Start the scheduling service.
      */
-    let cloned_monitoring_config = monitoring_config.clone();
-    let cloned_args = args.clone();
-    let monitor_statuses = monitoring_service.get_status();
-    let server_name = monitoring_config.server.name.clone();
-    tokio::spawn(async move {
-        let mut scheduling_service = SchedulingService::new(&server_name, &cloned_monitoring_config, &monitor_statuses, &database_service.clone());
-        match scheduling_service.start(cloned_args.test).await {
-            Ok(()) => {
-                info!("Scheduling service started!");
-            }
-            Err(err) => {
-                error!("Error starting scheduling service: {err:?}");
-            }
-        };
-    });
+    let scheduling_service = Arc::new(tokio::sync::Mutex::new(SchedulingService::new(
+        &monitoring_config.server.name,
+        monitoring_config,
+        &monitoring_service.get_status(),
+        &database_service,
+        &notification_service,
+        &monitoring_service.get_monitors(),
+    )));
+    match scheduling_service.lock().await.start(args.test).await {
+        Ok(()) => {
+            info!("Scheduling service started!");
+        }
+        Err(err) => {
+            error!("Error starting scheduling service: {err:?}");
+        }
+    };
     /*
      * Start the HTTP server.
      */
@@ -123,21 +166,172 @@ async fn start_application(monitoring_config: &MonitoringConfig, args: &Applicat
     if args.test {
         return Ok(());
     }
+    /*
+     * Reload the configuration on SIGHUP.
+     */
+    let reload_task = spawn_reload_on_hangup(&scheduling_service, &args.config, args.format)?;
+    let server_name = monitoring_config.server.name.clone();
+    let config_path = args.config.clone();
+    let config_format = args.format;
     info!("Starting HTTP server on {}:{}", ip, port);
-    HttpServer::new(move || {
+    let api_monitoring_service = monitoring_service.clone();
+    let api_scheduling_service = scheduling_service.clone();
+    let server = HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(StateApi::new(monitoring_service.clone())))
+            .app_data(web::Data::new(StateApi::new(api_monitoring_service.clone(), api_scheduling_service.clone(), &server_name, &config_path, config_format)))
             .service(api::get_current_meminfo)   
             .service(api::get_current_cpuinfo)   
             .service(api::get_current_loadavg)   
+            .service(api::get_current_cpu_usage)
             .service(api::get_processes)
             .service(api::get_process)
             .service(api::get_threads)
             .service(api::get_monitor_status)
+            .service(api::get_monitor_history)
+            .service(api::run_monitor)
+            .service(api::pause_monitor)
+            .service(api::resume_monitor)
+            .service(api::get_metrics)
+            .service(api::reload_config)
+            .service(api::get_config_schema)
     })
+    .disable_signals()
+    .shutdown_timeout(SHUTDOWN_TIMEOUT.as_secs())
     .bind((ip, port))?
-    .run()
-    .await
+    .run();
+    /*
+     * Stop accepting API requests on SIGTERM or SIGINT, then shut down the scheduling.
+     */
+    let sd_notify_service = SdNotifyService::from_env();
+    spawn_stop_on_shutdown_signal(server.handle(), sd_notify_service.clone())?;
+    /*
+     * Notify systemd that the scheduler and the HTTP server are up. The server socket is bound, so connections are
+     * accepted from now on.
+     */
+    if let Some(sd_notify_service) = &sd_notify_service {
+        notify_ready(&scheduling_service, sd_notify_service).await;
+    }
+    server.await?;
+    reload_task.abort();
+    shutdown_services(&scheduling_service, database_service).await;
+    Ok(())
+}
+
+/**
+ * Notify systemd that the agent is ready and start the watchdog pings. Systemd is not notified if the scheduler is
+ * not started or the watchdog job fails to be added, so systemd handles the agent as failing to start.
+ * 
+ * `scheduling_service`: The scheduling service.
+ * `sd_notify_service`: The systemd notify service.
+ * 
+ */
+async fn notify_ready(scheduling_service: &Arc<tokio::sync::Mutex<SchedulingService>>, sd_notify_service: &SdNotifyService) {
+    match scheduling_service.lock().await.notify_ready(sd_notify_service).await {
+        Ok(()) => {
+            info!("Systemd notified!");
+        }
+        Err(err) => {
+            error!("Error notifying systemd that the agent is ready: {err:?}");
+        }
+    }
+}
+
+/**
+ * Spawn a task that stops the HTTP server when the SIGTERM or SIGINT signal is received.
+ * The server stops accepting connections and finishes the running requests.
+ * 
+ * `server_handle`: The handle of the HTTP server.
+ * `sd_notify_service`: The systemd notify service. Notified that the agent is stopping.
+ * 
+ * Returns the result of registering the signal handlers.
+ * 
+ */
+fn spawn_stop_on_shutdown_signal(server_handle: ServerHandle, sd_notify_service: Option<SdNotifyService>) -> Result<(), std::io::Error> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = terminate.recv() => info!("SIGTERM received, shutting down"),
+            _ = interrupt.recv() => info!("SIGINT received, shutting down"),
+        }
+        if let Some(sd_notify_service) = sd_notify_service {
+            if let Err(err) = sd_notify_service.stopping() {
+                error!("Error notifying systemd: {err:?}");
+            }
+        }
+        server_handle.stop(true).await;
+    });
+    Ok(())
+}
+
+/**
+ * Shut down the services after the HTTP server is stopped. The scheduling is shut down and the running checks are
+ * given until the shutdown timeout to finish. The database connection pools are closed when the last reference
+ * to the database service is dropped.
+ * 
+ * `scheduling_service`: The scheduling service.
+ * `database_service`: The database service.
+ * 
+ */
+async fn shutdown_services(scheduling_service: &Arc<tokio::sync::Mutex<SchedulingService>>, mut database_service: Arc<Option<DbService>>) {
+    match scheduling_service.lock().await.shutdown(SHUTDOWN_TIMEOUT).await {
+        Ok(true) => {
+            info!("Scheduling service stopped!");
+        }
+        Ok(false) => {
+            warn!("Scheduling service stopped before all checks finished!");
+        }
+        Err(err) => {
+            error!("Error stopping scheduling service: {err:?}");
+        }
+    }
+    /*
+     * The scheduler drops the removed jobs asynchronously, so the last references may be released shortly after.
+     */
+    for _ in 0..20 {
+        match Arc::try_unwrap(database_service) {
+            Ok(Some(database_service)) => {
+                drop(database_service);
+                info!("Database connections closed!");
+                return;
+            }
+            Ok(None) => return,
+            Err(in_use) => {
+                database_service = in_use;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+    }
+    warn!("Database service still in use, connections are closed on exit");
+}
+
+/**
+ * Spawn a task that reloads the configuration when the SIGHUP signal is received.
+ * 
+ * `scheduling_service`: The scheduling service.
+ * `config_path`: The path to the configuration file.
+ * `config_format`: The format of the configuration file.
+ * 
+ * Returns the reload task.
+ * 
+ */
+fn spawn_reload_on_hangup(scheduling_service: &Arc<tokio::sync::Mutex<SchedulingService>>, config_path: &str, config_format: Option<ConfigFormat>) -> Result<JoinHandle<()>, std::io::Error> {
+    let mut hangup = signal(SignalKind::hangup())?;
+    let scheduling_service = scheduling_service.clone();
+    let config_path = config_path.to_string();
+    Ok(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading configuration from {}", config_path);
+            match scheduling_service.lock().await.reload_config(&config_path, config_format).await {
+                Ok(()) => {
+                    info!("Configuration reloaded!");
+                }
+                Err(err) => {
+                    error!("Error reloading configuration: {:?}", err);
+                }
+            }
+        }
+    }))
 }
 
 /**
@@ -161,6 +355,31 @@ async fn initialize_database(database_config: &DatabaseConfig, server_config: &S
     }
 }
 
+/**
+ * Initialize the notification service.
+ * 
+ * `notification_config`: The notification configuration.
+ * `server_config`: The server configuration.
+ * `monitors`: The monitors.
+ * 
+ * Returns the notification service or an error if the notification configuration is invalid.
+ * 
+ */
+fn initialize_notification(notification_config: &NotificationConfig, server_config: &ServerConfig, monitors: &[common::Monitor]) -> Result<NotificationService, std::io::Error> {
+    match NotificationService::new(notification_config, &server_config.name, monitors) {
+        Ok(notification_service) => {
+            info!("Notification service initialized!");
+            Ok(notification_service)
+        }
+        Err(err) => {
+            for notification_error in err.get_message().lines() {
+                error!("Invalid notification configuration: {notification_error}");
+            }
+            Err(std::io::Error::other(format!("Error initializing notification service: {}", err.get_message())))
+        }
+    }
+}
+
 /**
  * Start the daemon application.
  * 
@@ -205,7 +424,8 @@ async fn start_daemon_application(monitoring_config: &MonitoringConfig, args: &A
     match daemonize.start() {
         Ok(daemon) => {
             daemon.await;
-            info!("Started daemon!");
+            info!("Daemon stopped!");
+            remove_pid_file(&args.pidfile);
         }
         Err(err) => {
             error!("Error starting daemon: {:?}", err);
@@ -214,6 +434,23 @@ async fn start_daemon_application(monitoring_config: &MonitoringConfig, args: &A
     Ok(())
 }
 
+/**
+ * Remove the pid file written when the daemon was started.
+ * 
+ * `pidfile`: The path to the pid file.
+ * 
+ */
+fn remove_pid_file(pidfile: &str) {
+    match std::fs::remove_file(pidfile) {
+        Ok(()) => {
+            info!("Pid file removed: {}", pidfile);
+        }
+        Err(err) => {
+            error!("Error removing pid file {}: {:?}", pidfile, err);
+        }
+    }
+}
+
 /**
  * Setup logging.
  * 
@@ -266,6 +503,7 @@ fn get_stdout_logger(stdout_level_filter: filter::LevelFilter) -> StdioFilter {
         .with_timer(tracing_subscriber::fmt::time::SystemTime)
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
         .pretty()
+        .with_writer(RedactingMakeWriter::new(std::io::stdout as fn() -> std::io::Stdout))
         .with_filter(stdout_level_filter)
 }
 
@@ -288,7 +526,7 @@ fn get_file_logger(file: File, file_level_filter: filter::LevelFilter) -> FileFi
         .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
         .with_file(false)
         .with_line_number(false)        
-        .with_writer(Arc::new(file))
+        .with_writer(RedactingMakeWriter::new(Arc::new(file)))
         .with_filter(file_level_filter)
 }
 
@@ -300,14 +538,16 @@ mod test {
     async fn test_main_normal() -> Result<(), std::io::Error> {
         let args = ApplicationArguments {
             config: "./resources/test/test_full_configuration.json".to_string(),
+            format: None,
             daemon: false,
             test: true,
             file_errorlevel: "info".to_string(),
             stdout_errorlevel: "info".to_string(),
             pidfile: String::new(),
             logfile: "/tmp/monitoring-agent.log".to_string(),
+            print_schema: false,
         };
-        let monitoring_config = MonitoringConfig::new(&args.config).unwrap();
+        let monitoring_config = MonitoringConfig::new(&args.config, None).unwrap();
         start_application(&monitoring_config, &args).await?;
         Ok(())
     }
@@ -316,14 +556,16 @@ mod test {
     async fn test_main_daemon() -> Result<(), std::io::Error> {
         let args = ApplicationArguments {
             config: "./resources/test/test_full_configuration.json".to_string(),
+            format: None,
             daemon: true,
             test: true,
             file_errorlevel: "info".to_string(),
             stdout_errorlevel: "info".to_string(),            
             pidfile: String::new(),
             logfile: "/tmp/monitoring-agent.log".to_string(),
+            print_schema: false,
         };
-        let monitoring_config = MonitoringConfig::new(&args.config).unwrap();
+        let monitoring_config = MonitoringConfig::new(&args.config, None).unwrap();
         start_application(&monitoring_config, &args).await?;
         Ok(())
     }
@@ -332,30 +574,66 @@ mod test {
     async fn test_normal_application() {
         let args = ApplicationArguments {
             config: "./resources/test/test_full_configuration.json".to_string(),
+            format: None,
             daemon: false,
             test: true,
             file_errorlevel: "info".to_string(),
             stdout_errorlevel: "info".to_string(),
             pidfile: String::new(),
             logfile: "/tmp/monitoring-agent.log".to_string(),
+            print_schema: false,
         };
-        let monitoring_config = MonitoringConfig::new(&args.config).unwrap();
+        let monitoring_config = MonitoringConfig::new(&args.config, None).unwrap();
         let result = super::start_application(&monitoring_config, &args).await;
         assert!(result.is_ok());
     }
 
+    /**
+     * Test that an invalid notification configuration stops the agent instead of disabling the notifications.
+     */
+    #[tokio::test]
+    async fn test_application_invalid_notifications() {
+        let args = ApplicationArguments {
+            config: "./resources/test/test_full_configuration.json".to_string(),
+            format: None,
+            daemon: false,
+            test: true,
+            file_errorlevel: "info".to_string(),
+            stdout_errorlevel: "info".to_string(),
+            pidfile: String::new(),
+            logfile: "/tmp/monitoring-agent.log".to_string(),
+            print_schema: false,
+        };
+        let mut monitoring_config = MonitoringConfig::new(&args.config, None).unwrap();
+        let mut webhook_config: common::WebhookConfig = serde_json::from_str(r#"{"url": "http://127.0.0.1:9000/hook"}"#).unwrap();
+        webhook_config.headers = Some(std::collections::HashMap::from([("Invalid Header".to_string(), "value".to_string())]));
+        monitoring_config.notifications = Some(NotificationConfig { webhooks: vec![webhook_config], email: None });
+        let result = super::start_application(&monitoring_config, &args).await;
+        assert!(result.unwrap_err().to_string().starts_with("Error initializing notification service: Webhook: "));
+    }
+
+    #[test]
+    fn test_remove_pid_file() {
+        let pidfile = "/tmp/monitoring-agent-test-remove.pid";
+        std::fs::write(pidfile, "1").unwrap();
+        remove_pid_file(pidfile);
+        assert!(!std::path::Path::new(pidfile).exists());
+    }
+
     #[tokio::test]
     async fn test_daemonize_application() {
         let args = ApplicationArguments {
             config: "./resources/test/test_full_configuration.json".to_string(),
+            format: None,
             daemon: true,
             test: true,
             file_errorlevel: "info".to_string(),
             stdout_errorlevel: "info".to_string(),
             pidfile: "/tmp/monitoring-agent.pid".to_string(),
             logfile: "/tmp/monitoring-agent.log".to_string(),
+            print_schema: false,
         };
-        let monitoring_config = MonitoringConfig::new(&args.config).unwrap();
+        let monitoring_config = MonitoringConfig::new(&args.config, None).unwrap();
         let result = super::start_daemon_application(&monitoring_config, &args).await;
         assert!(result.is_ok());
     }
@@ -1,4 +1,4 @@
-use monitoring_agent_lib::proc::{ProcsLoadavg, ProcsMeminfo};
+use monitoring_agent_lib::proc::{ProcsCpuUsage, ProcsDisk, ProcsLoadavg, ProcsMeminfo};
 use r2d2::Pool;
 use r2d2_mysql::mysql::params;
 use r2d2_mysql::mysql::prelude::Queryable;
@@ -10,6 +10,7 @@ use bb8_postgres::tokio_postgres::tls::NoTls;
 use bb8_postgres::tokio_postgres::Config;
 use bb8_postgres::PostgresConnectionManager;
 use rust_decimal::Decimal;
+use std::time::Duration;
 
 use crate::common::configuration::DatabaseConfig;
 use crate::common::configuration::DatabaseType;
@@ -55,6 +56,7 @@ impl DbService {
      * 
      * `name`: The name of the monitor.
      * `status`: The status of the monitor.
+     * `duration`: The duration of the check.
      * 
      * Returns: Ok if the status was inserted successfully.
      * 
@@ -64,10 +66,10 @@ impl DbService {
      * - If there is an error committing the transaction.
      * 
      */
-    pub async fn insert_monitor_status(&self, name: &str, status: &Status) -> Result<(), ApplicationError> {
+    pub async fn insert_monitor_status(&self, name: &str, status: &Status, duration: Duration) -> Result<(), ApplicationError> {
         match self {
-            DbService::MariaDb(service) => service.insert_monitor_status(name, status),
-            DbService::PostgresDb(service) => service.insert_monitor_status(name, status).await,
+            DbService::MariaDb(service) => service.insert_monitor_status(name, status, duration),
+            DbService::PostgresDb(service) => service.insert_monitor_status(name, status, duration).await,
         }
     }
 
@@ -109,6 +111,44 @@ impl DbService {
         }
     }
 
+    /**
+     * Store the disk usage in the database. One row is stored for every filesystem.
+     * 
+     * `disks`: The disk usage to store.
+     * 
+     * Returns: Ok if the disk usage was stored successfully.
+     * 
+     * Errors:
+     * - If there is an error storing the disk usage.
+     * - If there is an error starting a transaction.
+     * 
+     */
+    pub async fn store_diskinfo(&self, disks: &[ProcsDisk]) -> Result<(), ApplicationError> {
+        match self {
+            DbService::MariaDb(service) => service.store_diskinfo(disks),
+            DbService::PostgresDb(service) => service.store_diskinfo(disks).await,
+        }
+    }
+
+    /**
+     * Store the cpu usage in the database. One row is stored for all cpus together and one for every core.
+     * 
+     * `usage`: The cpu usage to store.
+     * 
+     * Returns: Ok if the cpu usage was stored successfully.
+     * 
+     * Errors:
+     * - If there is an error storing the cpu usage.
+     * - If there is an error starting a transaction.
+     * 
+     */
+    pub async fn store_cpuusage(&self, usage: &ProcsCpuUsage) -> Result<(), ApplicationError> {
+        match self {
+            DbService::MariaDb(service) => service.store_cpuusage(usage),
+            DbService::PostgresDb(service) => service.store_cpuusage(usage).await,
+        }
+    }
+
     /**
      * Get the database representation of the status.
      * 
@@ -120,6 +160,9 @@ impl DbService {
     fn get_status_db_repr(status: &Status) -> String {
         match &status {
             Status::Error { message: _ } => "ERROR".to_string(),
+            Status::Warning { message: _ } => "WARNING".to_string(),
+            Status::Flapping { message: _ } => "FLAPPING".to_string(),
+            Status::Paused => "PAUSED".to_string(),
             Status::Ok => "OK".to_string(),
             Status::Unknown => "UNKNOWN".to_string(),
         }
@@ -135,11 +178,23 @@ impl DbService {
      */
     fn get_message(status: &Status) -> Option<String> {
         match status {
-            Status::Error { message } => Some(message.clone()),
+            Status::Error { message } | Status::Warning { message } | Status::Flapping { message } => Some(message.clone()),
             _ => None,
         }
     }
 
+    /**
+     * Get the duration of a check in milliseconds.
+     *
+     * `duration`: The duration of the check.
+     *
+     * Returns: The duration in milliseconds.
+     *
+     */
+    fn get_duration_ms(duration: Duration) -> i64 {
+        i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+    }
+
     /**
      * Query long running queries.
      * 
@@ -217,7 +272,7 @@ impl MariaDbService {
      * 
      * `name`: The name of the monitor.
      * `status`: The status of the monitor.
-     * `message`: The message associated with the status.
+     * `duration`: The duration of the check.
      * 
      * Returns: Ok if the status was inserted successfully.
      * 
@@ -228,14 +283,15 @@ impl MariaDbService {
      * 
      */
     #[tracing::instrument(level = "debug")]    
-    pub fn insert_monitor_status(&self, name: &str, status: &Status) -> Result<(), ApplicationError> {
+    pub fn insert_monitor_status(&self, name: &str, status: &Status, duration: Duration) -> Result<(), ApplicationError> {
         let mut conn = self.pool.get().map_err(|err| ApplicationError::new(&err.to_string()))?;
         let mut tx = conn.start_transaction(TxOpts::default()).map_err(|err| ApplicationError::new(&err.to_string()))?;
-        tx.exec_drop("INSERT INTO monitor_status (server_name, monitor_name, status, log_time, message) VALUES (:server_name,:name, :status, now(3), :message)", params! {
+        tx.exec_drop("INSERT INTO monitor_status (server_name, monitor_name, status, log_time, message, duration_ms) VALUES (:server_name,:name, :status, now(3), :message, :duration_ms)", params! {
             "server_name" => self.server_name.to_string(),
             "name" => &name,
             "status" => DbService::get_status_db_repr(status),
             "message" => DbService::get_message(status),
+            "duration_ms" => DbService::get_duration_ms(duration),
         }).map_err(|err| ApplicationError::new(&err.to_string()))?;
         tx.commit().map_err(|err| ApplicationError::new(&err.to_string()))?;
         Ok(())
@@ -293,6 +349,68 @@ impl MariaDbService {
         Ok(())
     }
 
+    /**
+     * Store the disk usage in the database.
+     * 
+     * `disks`: The disk usage to store.
+     * 
+     * Returns: Ok if the disk usage was stored successfully.
+     * 
+     * Errors:
+     * - If there is an error storing the disk usage.
+     * - If there is an error starting a transaction.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub fn store_diskinfo(&self, disks: &[ProcsDisk]) -> Result<(), ApplicationError> {
+        let mut conn = self.pool.get().map_err(|err| ApplicationError::new(&err.to_string()))?;
+        let mut tx = conn.start_transaction(TxOpts::default()).map_err(|err| ApplicationError::new(&err.to_string()))?;
+        for disk in disks {
+            tx.exec_drop("INSERT INTO diskinfo (server_name, mount_point, device, total, available, percent_used, inodes_free, inodes_percent_used, log_time) VALUES (:server_name, :mount_point, :device, :total, :available, :percent_used, :inodes_free, :inodes_percent_used, now(3))", params! {
+                "server_name" => self.server_name.to_string(),
+                "mount_point" => &disk.mount_point,
+                "device" => &disk.device,
+                "total" => disk.total,
+                "available" => disk.available,
+                "percent_used" => disk.get_percent_used(),
+                "inodes_free" => disk.inodes_free,
+                "inodes_percent_used" => disk.get_percent_inodes_used(),
+            }).map_err(|err| ApplicationError::new(&err.to_string()))?;
+        }
+        tx.commit().map_err(|err| ApplicationError::new(&err.to_string()))?;
+        Ok(())
+    }
+
+    /**
+     * Store the cpu usage in the database.
+     * 
+     * `usage`: The cpu usage to store.
+     * 
+     * Returns: Ok if the cpu usage was stored successfully.
+     * 
+     * Errors:
+     * - If there is an error storing the cpu usage.
+     * - If there is an error starting a transaction.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub fn store_cpuusage(&self, usage: &ProcsCpuUsage) -> Result<(), ApplicationError> {
+        let mut conn = self.pool.get().map_err(|err| ApplicationError::new(&err.to_string()))?;
+        let mut tx = conn.start_transaction(TxOpts::default()).map_err(|err| ApplicationError::new(&err.to_string()))?;
+        for cpu in std::iter::once(&usage.total).chain(&usage.cores) {
+            tx.exec_drop("INSERT INTO cpuusage (server_name, cpu, percent_user, percent_system, percent_iowait, percent_steal, percent_idle, percent_used, log_time) VALUES (:server_name, :cpu, :percent_user, :percent_system, :percent_iowait, :percent_steal, :percent_idle, :percent_used, now(3))", params! {
+                "server_name" => self.server_name.to_string(),
+                "cpu" => &cpu.name,
+                "percent_user" => cpu.user,
+                "percent_system" => cpu.system,
+                "percent_iowait" => cpu.iowait,
+                "percent_steal" => cpu.steal,
+                "percent_idle" => cpu.idle,
+                "percent_used" => cpu.get_percent_used(),
+            }).map_err(|err| ApplicationError::new(&err.to_string()))?;
+        }
+        tx.commit().map_err(|err| ApplicationError::new(&err.to_string()))?;
+        Ok(())
+    }
+
     /**
      * Query long running queries.
      * 
@@ -375,7 +493,7 @@ impl PostgresDbService {
      * 
      * `name`: The name of the monitor.
      * `status`: The status of the monitor.
-     * `message`: The message associated with the status.
+     * `duration`: The duration of the check.
      * 
      * Returns: Ok if the status was inserted successfully.
      * 
@@ -386,14 +504,15 @@ impl PostgresDbService {
      * 
      */
     #[tracing::instrument(level = "debug")]
-    pub async fn insert_monitor_status(&self, name: &str, status: &Status) -> Result<(), ApplicationError> {
+    pub async fn insert_monitor_status(&self, name: &str, status: &Status, duration: Duration) -> Result<(), ApplicationError> {
         let mut conn = self.pool.get().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
         let tx = conn.transaction().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
-        tx.execute("INSERT INTO monitor_status (id, server_name, monitor_name, status, log_time, message) VALUES (nextval('seq_monitor_status'), $1, $2, $3, now(), $4)", &[
+        tx.execute("INSERT INTO monitor_status (id, server_name, monitor_name, status, log_time, message, duration_ms) VALUES (nextval('seq_monitor_status'), $1, $2, $3, now(), $4, $5)", &[
             &self.server_name,
             &name,
             &DbService::get_status_db_repr(status),
             &DbService::get_message(status),
+            &DbService::get_duration_ms(duration),
         ]).await.map_err(|err| ApplicationError::new(&err.to_string()))?;
         tx.commit().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
         Ok(())
@@ -452,6 +571,68 @@ impl PostgresDbService {
         Ok(())
     }
 
+    /**
+     * Store the disk usage in the database.
+     * 
+     * `disks`: The disk usage to store.
+     * 
+     * Returns: Ok if the disk usage was stored successfully.
+     * 
+     * Errors:
+     * - If there is an error storing the disk usage.
+     * - If there is an error starting a transaction.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub async fn store_diskinfo(&self, disks: &[ProcsDisk]) -> Result<(), ApplicationError> {
+        let mut conn = self.pool.get().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        let tx = conn.transaction().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        for disk in disks {
+            tx.execute("INSERT INTO diskinfo (id, server_name, mount_point, device, total, available, percent_used, inodes_free, inodes_percent_used, log_time) VALUES (nextval('seq_diskinfo'), $1, $2, $3, $4, $5, $6, $7, $8, now())", &[
+                &self.server_name,
+                &disk.mount_point,
+                &disk.device,
+                &disk.total.and_then(|x| i64::try_from(x).ok()),
+                &disk.available.and_then(|x| i64::try_from(x).ok()),
+                &disk.get_percent_used().and_then(|f| Decimal::try_from(f).ok()),
+                &disk.inodes_free.and_then(|x| i64::try_from(x).ok()),
+                &disk.get_percent_inodes_used().and_then(|f| Decimal::try_from(f).ok()),
+            ]).await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        }
+        tx.commit().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        Ok(())
+    }
+
+    /**
+     * Store the cpu usage in the database.
+     * 
+     * `usage`: The cpu usage to store.
+     * 
+     * Returns: Ok if the cpu usage was stored successfully.
+     * 
+     * Errors:
+     * - If there is an error storing the cpu usage.
+     * - If there is an error starting a transaction.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub async fn store_cpuusage(&self, usage: &ProcsCpuUsage) -> Result<(), ApplicationError> {
+        let mut conn = self.pool.get().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        let tx = conn.transaction().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        for cpu in std::iter::once(&usage.total).chain(&usage.cores) {
+            tx.execute("INSERT INTO cpuusage (id, server_name, cpu, percent_user, percent_system, percent_iowait, percent_steal, percent_idle, percent_used, log_time) VALUES (nextval('seq_cpuusage'), $1, $2, $3, $4, $5, $6, $7, $8, now())", &[
+                &self.server_name,
+                &cpu.name,
+                &Decimal::try_from(cpu.user).ok(),
+                &Decimal::try_from(cpu.system).ok(),
+                &Decimal::try_from(cpu.iowait).ok(),
+                &Decimal::try_from(cpu.steal).ok(),
+                &Decimal::try_from(cpu.idle).ok(),
+                &Decimal::try_from(cpu.get_percent_used()).ok(),
+            ]).await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        }
+        tx.commit().await.map_err(|err| ApplicationError::new(&err.to_string()))?;
+        Ok(())
+    }
+
     /**
      * Query long running queries.
      * 
@@ -5,13 +5,19 @@
  * `monitoringservice`: Handles the web service requests.
  * `schedulingservice`: Handles the scheduling of the monitoring tasks.
  * `databaseservice`: Handles the database operations.
+ * `notificationservice`: Handles notifications when monitors change status.
+ * `sdnotifyservice`: Notifies systemd about readiness, status and watchdog pings.
  */
 mod monitors;
 mod monitoringservice;
 mod schedulingservice;
 mod databaseservice;
+mod notificationservice;
+mod sdnotifyservice;
 
 pub use monitoringservice::MonitoringService;
 pub use schedulingservice::SchedulingService;
 pub use databaseservice::DbService;
+pub use notificationservice::NotificationService;
+pub use sdnotifyservice::SdNotifyService;
 
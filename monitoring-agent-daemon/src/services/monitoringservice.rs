@@ -2,9 +2,12 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use log::error;
-use monitoring_agent_lib::proc::{ProcsCpuinfo, ProcsLoadavg, ProcsMeminfo, ProcsProcess};
+use monitoring_agent_lib::proc::{ProcsCpuUsage, ProcsCpuinfo, ProcsLoadavg, ProcsMeminfo, ProcsProcess};
 
-use crate::common::{ApplicationError, MonitorStatus};
+use chrono::{DateTime, Utc};
+
+use crate::common::{ApplicationError, MonitorHistoryEntry, MonitorStatus, Status};
+use crate::services::monitors::{CheckInProgress, CpuMonitor, RunningChecks, ScheduledMonitor};
 
 /**
  * Monitoring Service.
@@ -21,6 +24,8 @@ use crate::common::{ApplicationError, MonitorStatus};
 pub struct MonitoringService {
     /// The status of the monitors.
     status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+    /// The monitors added to the scheduler.
+    monitors: Arc<Mutex<HashMap<String, ScheduledMonitor>>>,
 }
 
 impl MonitoringService {
@@ -32,6 +37,7 @@ impl MonitoringService {
     pub fn new() -> MonitoringService {
         MonitoringService {
             status: Arc::new(Mutex::new(HashMap::new())),
+            monitors: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -86,6 +92,23 @@ impl MonitoringService {
         }
     }
 
+    /**
+     * Get the current cpu usage, measured over one second.
+     *
+     * result: The result of getting the cpu usage.
+     */
+    #[allow(clippy::unused_self)]
+    pub async fn get_current_cpu_usage(&self) -> Result<ProcsCpuUsage, ApplicationError> {
+        let usage = CpuMonitor::get_cpu_usage().await;
+        match usage {
+            Ok(usage) => Ok(usage),
+            Err(err) => {
+                error!("Error: {}", err.get_message());
+                Err(ApplicationError::new("Error getting cpu usage"))
+            }
+        }
+    }
+
     /**
      * Get the current processes.
      * 
@@ -160,20 +183,110 @@ impl MonitoringService {
     }
 
     /**
-     * Get all monitor statuses.
+     * Get the monitors added to the scheduler.
+     * 
+     * result: The monitors.
+     */
+    pub fn get_monitors(&self) -> Arc<Mutex<HashMap<String, ScheduledMonitor>>> {
+        self.monitors.clone()
+    }
+
+    /**
+     * Get all monitor statuses. The check history is not included, use `get_monitor_history` for it.
      * 
      * result: The result of getting all monitor statuses.
      */
     pub fn get_all_monitorstatuses(&self) -> Vec<MonitorStatus> {
         let status_lock = self.status.lock();
         match status_lock {
-            Ok(lock) => lock.values().cloned().collect(),
+            Ok(lock) => lock.values().map(MonitorStatus::without_history).collect(),
             Err(err) => {
                 error!("Error getting monitor statuses: {:?}", err);
                 Vec::new()
             }
         }
     }
+
+    /**
+     * Get the check history of a monitor.
+     *
+     * `name`: The name of the monitor.
+     * `since`: Only return results after this time.
+     * `limit`: The maximum number of results.
+     *
+     * result: The check history, or None if the monitor does not exist.
+     */
+    pub fn get_monitor_history(&self, name: &str, since: Option<DateTime<Utc>>, limit: Option<usize>) -> Option<Vec<MonitorHistoryEntry>> {
+        match self.status.lock() {
+            Ok(lock) => lock.get(name).map(|monitor_status| monitor_status.get_history(since, limit)),
+            Err(err) => {
+                error!("Error getting monitor history: {:?}", err);
+                None
+            }
+        }
+    }
+
+    /**
+     * Run the check of a monitor outside of its schedule. The check has the timeout of the schedule and is not run
+     * while another check of the monitor is in progress. Paused monitors are not checked.
+     *
+     * `name`: The name of the monitor.
+     * `running_checks`: The running checks. The check is marked as running, so shutdown waits for it.
+     *
+     * result: The status of the monitor after the check, or None if the monitor does not exist.
+     *
+     * throws: `ApplicationError`: If the monitor is paused or a check of the monitor is already in progress.
+     */
+    pub async fn run_monitor(&self, name: &str, running_checks: &RunningChecks) -> Result<Option<MonitorStatus>, ApplicationError> {
+        /*
+         * Clone the monitor to avoid holding the lock during the check. The status is shared with the scheduled job.
+         */
+        let monitor = match self.monitors.lock() {
+            Ok(lock) => lock.get(name).cloned(),
+            Err(err) => {
+                error!("Error getting monitor: {:?}", err);
+                None
+            }
+        };
+        let Some(mut monitor) = monitor else {
+            return Ok(None);
+        };
+        let (paused, timeout) = match self.status.lock() {
+            Ok(lock) => lock.get(name).map_or((false, None), |monitor_status| (monitor_status.status == Status::Paused, monitor_status.timeout)),
+            Err(err) => {
+                error!("Error getting monitor status: {:?}", err);
+                (false, None)
+            }
+        };
+        if paused {
+            return Err(ApplicationError::new(format!("Monitor {name} is paused").as_str()));
+        }
+        let Some(check_in_progress) = CheckInProgress::start(name, &self.status) else {
+            return Err(ApplicationError::new(format!("A check of monitor {name} is already in progress").as_str()));
+        };
+        let running_check = running_checks.start();
+        monitor.check(timeout).await;
+        drop(running_check);
+        drop(check_in_progress);
+        Ok(self.get_monitorstatus(name))
+    }
+
+    /**
+     * Get the status of a monitor.
+     *
+     * `name`: The name of the monitor.
+     *
+     * result: The status of the monitor, or None if the monitor does not exist.
+     */
+    pub fn get_monitorstatus(&self, name: &str) -> Option<MonitorStatus> {
+        match self.status.lock() {
+            Ok(lock) => lock.get(name).cloned(),
+            Err(err) => {
+                error!("Error getting monitor status: {:?}", err);
+                None
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -186,6 +299,76 @@ mod test {
         let monitoring_service = MonitoringService::new();
         let monitor_statuses = monitoring_service.get_all_monitorstatuses();
         assert_eq!(monitor_statuses.len(), 0);
+        let mut monitor_status = MonitorStatus::new("test".to_string(), crate::common::Status::Unknown);
+        monitor_status.set_status(&crate::common::Status::Ok, std::time::Duration::ZERO);
+        monitoring_service.get_status().lock().unwrap().insert("test".to_string(), monitor_status);
+        let monitor_statuses = monitoring_service.get_all_monitorstatuses();
+        assert_eq!(monitor_statuses.len(), 1);
+        assert_eq!(monitor_statuses[0].status, crate::common::Status::Ok);
+        assert!(monitor_statuses[0].history.is_empty());
+    }
+
+    #[test]
+    fn test_get_monitor_history() {
+        let monitoring_service = MonitoringService::new();
+        assert!(monitoring_service.get_monitor_history("test", None, None).is_none());
+        let mut monitor_status = MonitorStatus::new("test".to_string(), crate::common::Status::Unknown);
+        monitor_status.set_status(&crate::common::Status::Ok, std::time::Duration::ZERO);
+        monitoring_service.get_status().lock().unwrap().insert("test".to_string(), monitor_status);
+        let history = monitoring_service.get_monitor_history("test", None, None).unwrap();
+        assert_eq!(history.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_monitor() {
+        let monitoring_service = MonitoringService::new();
+        assert!(monitoring_service.run_monitor("test", &RunningChecks::new()).await.unwrap().is_none());
+        let monitor = crate::services::monitors::TcpMonitor::new("localhost", 65000, None, "test", &monitoring_service.get_status(), &Arc::new(None), &Arc::new(None), &crate::common::configuration::DatabaseStoreLevel::None);
+        monitoring_service.get_monitors().lock().unwrap().insert("test".to_string(), ScheduledMonitor::Tcp(monitor));
+        let monitor_status = monitoring_service.run_monitor("test", &RunningChecks::new()).await.unwrap().unwrap();
+        assert!(matches!(monitor_status.status, crate::common::Status::Error { .. }));
+        assert_eq!(monitor_status.history.len(), 1);
+        assert!(!monitor_status.check_in_progress);
+    }
+
+    /**
+     * Test that a check run on demand is counted as running, and a paused monitor is not checked.
+     */
+    #[tokio::test]
+    async fn test_run_monitor_running_and_paused() {
+        let monitoring_service = MonitoringService::new();
+        let running_checks = RunningChecks::new();
+        let monitor = crate::services::monitors::CommandMonitor::new("sleep", "sleep", Some(vec!["1".to_string()]), None, &monitoring_service.get_status(), &Arc::new(None), &Arc::new(None), &crate::common::configuration::DatabaseStoreLevel::None);
+        monitoring_service.get_monitors().lock().unwrap().insert("sleep".to_string(), ScheduledMonitor::Command(monitor));
+        let run_service = monitoring_service.clone();
+        let run_checks = running_checks.clone();
+        let run = tokio::spawn(async move { run_service.run_monitor("sleep", &run_checks).await });
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert_eq!(running_checks.count(), 1);
+        assert!(run.await.unwrap().unwrap().is_some());
+        assert_eq!(running_checks.count(), 0);
+
+        monitoring_service.get_status().lock().unwrap().get_mut("sleep").unwrap().pause();
+        assert_eq!(monitoring_service.run_monitor("sleep", &running_checks).await.unwrap_err().get_message(), "Monitor sleep is paused");
+        assert_eq!(monitoring_service.get_status().lock().unwrap().get("sleep").unwrap().history.len(), 1);
+    }
+
+    /**
+     * Test that a check run on demand uses the timeout of the monitor, and is rejected while another check of the
+     * monitor is in progress.
+     */
+    #[tokio::test]
+    async fn test_run_monitor_timeout_and_in_progress() {
+        let monitoring_service = MonitoringService::new();
+        let monitor = crate::services::monitors::CommandMonitor::new("sleep", "sleep", Some(vec!["5".to_string()]), None, &monitoring_service.get_status(), &Arc::new(None), &Arc::new(None), &crate::common::configuration::DatabaseStoreLevel::None);
+        monitoring_service.get_monitors().lock().unwrap().insert("sleep".to_string(), ScheduledMonitor::Command(monitor));
+        monitoring_service.get_status().lock().unwrap().get_mut("sleep").unwrap().timeout = Some(std::time::Duration::from_millis(200));
+        let check_in_progress = CheckInProgress::start("sleep", &monitoring_service.get_status()).unwrap();
+        assert_eq!(monitoring_service.run_monitor("sleep", &RunningChecks::new()).await.unwrap_err().get_message(), "A check of monitor sleep is already in progress");
+        drop(check_in_progress);
+        let monitor_status = monitoring_service.run_monitor("sleep", &RunningChecks::new()).await.unwrap().unwrap();
+        assert_eq!(monitor_status.status, crate::common::Status::Error { message: "Check timed out after 200 ms".to_string() });
+        assert_eq!(monitor_status.timed_out_checks, 1);
     }
 
     #[test]
@@ -209,6 +392,13 @@ mod test {
         assert!(cpuinfo.is_ok());
     } 
 
+    #[tokio::test]
+    async fn test_get_current_cpu_usage() {
+        let monitoring_service = MonitoringService::new();
+        let usage = monitoring_service.get_current_cpu_usage().await.unwrap();
+        assert_eq!(usage.total.name, "cpu");
+    }
+
     #[test]
     fn test_get_current_loadavg() {
         let monitoring_service = MonitoringService::new();
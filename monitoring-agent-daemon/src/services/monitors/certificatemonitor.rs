@@ -0,0 +1,665 @@
+use std::{collections::HashMap, fs, net::{TcpStream, ToSocketAddrs}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use log::{debug, error, info};
+use openssl::{asn1::Asn1Time, ssl::{SslConnector, SslMethod, SslVerifyMode}, x509::{X509NameRef, X509Ref, X509VerifyResult, X509}};
+use tokio_cron_scheduler::Job;
+
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService, services::NotificationService};
+
+use super::{HttpMonitor, Monitor};
+use super::{MonitorSchedule, RunningChecks};
+
+/// The maximum time to connect to the server and complete the handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The OpenSSL verification error for an expired certificate, X509_V_ERR_CERT_HAS_EXPIRED.
+const CERT_HAS_EXPIRED: i32 = 10;
+
+/// Seconds in a day.
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Clone)]
+pub struct CertificateMonitor {
+    /// The name of the monitor.
+    pub name: String,
+    /// The host to read the certificate chain from.
+    pub host: Option<String>,
+    /// The port of the host.
+    pub port: u16,
+    /// The server name sent with SNI. The host is used if not given.
+    pub server_name: Option<String>,
+    /// PEM files with certificates to check.
+    pub files: Option<Vec<String>>,
+    /// Root certificate used to verify the chain of the host.
+    pub root_certificate: Option<String>,
+    /// Accept a chain that can not be verified.
+    pub accept_invalid_certs: bool,
+    /// Days before expiry the status becomes warning.
+    pub warning_days: u32,
+    /// Days before expiry the status becomes error.
+    pub error_days: u32,
+    /// The status of the monitor.
+    pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+    /// The database service
+    database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
+    /// The database store level.
+    database_store_level: DatabaseStoreLevel,
+}
+
+/**
+ * The expiry of a certificate.
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct CertificateExpiry {
+    /// Where the certificate was read from, host:port or a file.
+    source: String,
+    /// The subject of the certificate.
+    subject: String,
+    /// The issuer of the certificate.
+    issuer: String,
+    /// The expiry time of the certificate.
+    not_after: String,
+    /// Seconds until the certificate expires, negative if it has expired.
+    seconds_remaining: i64,
+}
+
+impl CertificateMonitor {
+
+    /**
+     * Create a new `CertificateMonitor`.
+     *
+     * `name`: The name of the monitor.
+     * `host`: The host to read the certificate chain from.
+     * `port`: The port of the host.
+     * `server_name`: The server name sent with SNI. The host is used if not given.
+     * `files`: PEM files with certificates to check.
+     * `root_certificate`: Root certificate used to verify the chain of the host.
+     * `accept_invalid_certs`: Accept a chain that can not be verified.
+     * `warning_days`: Days before expiry the status becomes warning.
+     * `error_days`: Days before expiry the status becomes error.
+     * `status`: The status of the monitor.
+     * `database_service`: The database service.
+     * `notification_service`: The notification service.
+     * `database_store_level`: The database store level.
+     *
+     * Returns: A new `CertificateMonitor`.
+     *
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        host: Option<String>,
+        port: u16,
+        server_name: Option<String>,
+        files: Option<Vec<String>>,
+        root_certificate: Option<String>,
+        accept_invalid_certs: bool,
+        warning_days: u32,
+        error_days: u32,
+        status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
+        database_store_level: &DatabaseStoreLevel,
+    ) -> CertificateMonitor {
+
+        let status_lock = status.lock();
+        match status_lock {
+            Ok(mut lock) => {
+                lock.insert(name.to_string(), MonitorStatus::new(name.to_string(), Status::Unknown));
+            }
+            Err(err) => {
+                error!("Error creating certificate monitor: {:?}", err);
+            }
+        }
+
+        CertificateMonitor {
+            name: name.to_string(),
+            host,
+            port,
+            server_name,
+            files,
+            root_certificate,
+            accept_invalid_certs,
+            warning_days,
+            error_days,
+            status: status.clone(),
+            database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
+            database_store_level: database_store_level.clone(),
+        }
+    }
+
+    /**
+     * Read the certificates of the host and the files.
+     *
+     * Returns: The expiry of the certificates and the problems reading or verifying them.
+     */
+    fn read_certificates(&self) -> (Vec<CertificateExpiry>, Vec<String>) {
+        let mut certificates = Vec::new();
+        let mut errors = Vec::new();
+        if let Some(host) = &self.host {
+            let source = format!("{host}:{}", self.port);
+            match self.read_server_certificates(host, &source) {
+                Ok((server_certificates, verify_error)) => {
+                    certificates.extend(server_certificates);
+                    errors.extend(verify_error.map(|verify_error| format!("{source}: Certificate verification failed: {verify_error}")));
+                }
+                Err(err) => errors.push(format!("{source}: {}", err.get_message())),
+            }
+        }
+        for file in self.files.iter().flatten() {
+            match CertificateMonitor::read_file_certificates(file) {
+                Ok(file_certificates) => certificates.extend(file_certificates),
+                Err(err) => errors.push(format!("{file}: {}", err.get_message())),
+            }
+        }
+        (certificates, errors)
+    }
+
+    /**
+     * Read the certificate chain of the host. The chain is not verified during the handshake, so the dates of an
+     * expired or untrusted certificate can still be read.
+     *
+     * `host`: The host.
+     * `source`: The host and port, used in the results.
+     *
+     * Returns: The expiry of the certificates and the verification error, if the chain could not be verified.
+     *
+     * throws: `ApplicationError`: If the connection or handshake fails.
+     */
+    fn read_server_certificates(&self, host: &str, source: &str) -> Result<(Vec<CertificateExpiry>, Option<String>), ApplicationError> {
+        let mut builder = SslConnector::builder(SslMethod::tls_client())
+            .map_err(|err| ApplicationError::new(&format!("Error creating TLS connector: {err}")))?;
+        builder.set_verify(SslVerifyMode::NONE);
+        if let Some(root_certificate) = &self.root_certificate {
+            let data = HttpMonitor::read_root_certificate(root_certificate)?;
+            let root_certificates = X509::stack_from_pem(&data)
+                .map_err(|err| ApplicationError::new(&format!("Error reading root_certificate: {err}")))?;
+            for root_certificate in root_certificates {
+                builder.cert_store_mut().add_cert(root_certificate)
+                    .map_err(|err| ApplicationError::new(&format!("Error adding root_certificate: {err}")))?;
+            }
+        }
+        let connector = builder.build();
+
+        let stream = CertificateMonitor::connect(host, self.port)?;
+        let server_name = self.server_name.as_deref().unwrap_or(host);
+        let tls_stream = connector.configure()
+            .map_err(|err| ApplicationError::new(&format!("Error creating TLS connector: {err}")))?
+            .connect(server_name, stream)
+            .map_err(|err| ApplicationError::new(&format!("TLS handshake failed: {err}")))?;
+
+        let ssl = tls_stream.ssl();
+        let certificates = match ssl.peer_cert_chain() {
+            Some(chain) => chain.iter()
+                .map(|certificate| CertificateMonitor::get_expiry(source, certificate))
+                .collect::<Result<Vec<CertificateExpiry>, ApplicationError>>()?,
+            None => ssl.peer_certificate()
+                .map(|certificate| CertificateMonitor::get_expiry(source, &certificate))
+                .transpose()?
+                .into_iter()
+                .collect(),
+        };
+        /*
+         * An expired certificate is reported from its date, so it is not reported again as a verification error.
+         */
+        let verify_result = ssl.verify_result();
+        let verify_error = if self.accept_invalid_certs || verify_result == X509VerifyResult::OK || verify_result.as_raw() == CERT_HAS_EXPIRED {
+            None
+        } else {
+            Some(verify_result.error_string().to_string())
+        };
+        Ok((certificates, verify_error))
+    }
+
+    /**
+     * Connect to the host.
+     *
+     * `host`: The host.
+     * `port`: The port.
+     *
+     * Returns: The connection.
+     *
+     * throws: `ApplicationError`: If the host can not be resolved or none of its addresses accept the connection.
+     */
+    fn connect(host: &str, port: u16) -> Result<TcpStream, ApplicationError> {
+        let addresses = (host, port).to_socket_addrs()
+            .map_err(|err| ApplicationError::new(&format!("Could not resolve host: {err}")))?;
+        let mut last_error = None;
+        for address in addresses {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(CONNECT_TIMEOUT))
+                        .and_then(|()| stream.set_write_timeout(Some(CONNECT_TIMEOUT)))
+                        .map_err(|err| ApplicationError::new(&format!("Could not connect: {err}")))?;
+                    return Ok(stream);
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(ApplicationError::new(&match last_error {
+            Some(err) => format!("Could not connect: {err}"),
+            None => "Could not resolve host: no addresses".to_string(),
+        }))
+    }
+
+    /**
+     * Read the certificates of a PEM file.
+     *
+     * `file`: The file.
+     *
+     * Returns: The expiry of the certificates.
+     *
+     * throws: `ApplicationError`: If the file can not be read or has no certificates.
+     */
+    fn read_file_certificates(file: &str) -> Result<Vec<CertificateExpiry>, ApplicationError> {
+        let data = fs::read(file).map_err(|err| ApplicationError::new(&format!("Error reading certificate file: {err}")))?;
+        let certificates = X509::stack_from_pem(&data)
+            .map_err(|err| ApplicationError::new(&format!("Error reading certificates: {err}")))?;
+        if certificates.is_empty() {
+            return Err(ApplicationError::new("No certificates in file"));
+        }
+        certificates.iter().map(|certificate| CertificateMonitor::get_expiry(file, certificate)).collect()
+    }
+
+    /**
+     * Get the expiry of a certificate.
+     *
+     * `source`: Where the certificate was read from.
+     * `certificate`: The certificate.
+     *
+     * Returns: The expiry of the certificate.
+     *
+     * throws: `ApplicationError`: If the time until expiry can not be calculated.
+     */
+    fn get_expiry(source: &str, certificate: &X509Ref) -> Result<CertificateExpiry, ApplicationError> {
+        let now = Asn1Time::days_from_now(0)
+            .map_err(|err| ApplicationError::new(&format!("Error getting current time: {err}")))?;
+        let remaining = now.diff(certificate.not_after())
+            .map_err(|err| ApplicationError::new(&format!("Error reading certificate expiry: {err}")))?;
+        Ok(CertificateExpiry {
+            source: source.to_string(),
+            subject: CertificateMonitor::format_name(certificate.subject_name()),
+            issuer: CertificateMonitor::format_name(certificate.issuer_name()),
+            not_after: certificate.not_after().to_string(),
+            seconds_remaining: i64::from(remaining.days) * SECONDS_PER_DAY + i64::from(remaining.secs),
+        })
+    }
+
+    /**
+     * Format a certificate name, e.g. CN=example.com, O=Example.
+     *
+     * `name`: The name.
+     *
+     * Returns: The formatted name.
+     */
+    fn format_name(name: &X509NameRef) -> String {
+        name.entries()
+            .map(|entry| {
+                let key = entry.object().nid().short_name().unwrap_or("?");
+                let value = entry.data().to_string().unwrap_or_default();
+                format!("{key}={value}")
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /**
+     * Check the expiry of the certificates.
+     *
+     * `certificates`: The expiry of the certificates.
+     * `read_errors`: The problems reading or verifying the certificates.
+     * `duration`: The duration of the check.
+     *
+     */
+    async fn check_certificates(&mut self, certificates: &[CertificateExpiry], read_errors: &[String], duration: Duration) {
+        let mut errors: Vec<String> = read_errors.to_vec();
+        let mut warnings: Vec<String> = Vec::new();
+        for certificate in certificates {
+            match self.check_expiry(certificate) {
+                Status::Error { message } => errors.push(message),
+                Status::Warning { message } => warnings.push(message),
+                _ => {}
+            }
+        }
+
+        if !errors.is_empty() {
+            self.set_status(&Status::Error {
+                message: format!("Certificate check failed: {}", errors.join(", ")),
+            }, duration).await;
+        } else if !warnings.is_empty() {
+            self.set_status(&Status::Warning {
+                message: format!("Certificate check warning: {}", warnings.join(", ")),
+            }, duration).await;
+        } else {
+            self.set_status(&Status::Ok, duration).await;
+        }
+    }
+
+    /**
+     * Check the expiry of a certificate against the error and warning days.
+     *
+     * `certificate`: The expiry of the certificate.
+     *
+     * Returns: Error if expired or expiring within the error days, warning if expiring within the warning days,
+     * otherwise ok.
+     *
+     */
+    fn check_expiry(&self, certificate: &CertificateExpiry) -> Status {
+        let remaining = certificate.seconds_remaining;
+        let description = format!("{}: {} issued by {}", certificate.source, certificate.subject, certificate.issuer);
+        if remaining < 0 {
+            return Status::Error {
+                message: format!("{description} expired {} days ago ({})", -remaining / SECONDS_PER_DAY, certificate.not_after),
+            };
+        }
+        let message = format!("{description} expires in {} days ({})", remaining / SECONDS_PER_DAY, certificate.not_after);
+        if remaining < i64::from(self.error_days) * SECONDS_PER_DAY {
+            Status::Error { message }
+        } else if remaining < i64::from(self.warning_days) * SECONDS_PER_DAY {
+            Status::Warning { message }
+        } else {
+            Status::Ok
+        }
+    }
+
+    /**
+     * Get certificate monitor job.
+     *
+     * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
+     *
+     * Returns: The certificate monitor job.
+     *
+     */
+    pub fn get_certificate_monitor_job(
+        &mut self,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
+    ) -> Result<Job, ApplicationError> {
+        info!("Creating certificate monitor: {}", &self.name);
+        let certificate_monitor = self.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut certificate_monitor = certificate_monitor.clone();
+            Box::pin(async move {
+                let mut checked_monitor = certificate_monitor.clone();
+                certificate_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
+    }
+
+    /**
+     * Check the monitor.
+     */
+    pub async fn check(&mut self) {
+        debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
+        /*
+         * The TLS handshake and the file reads block, so the certificates are read outside of the async workers.
+         */
+        let monitor = self.clone();
+        let certificates = tokio::task::spawn_blocking(move || monitor.read_certificates()).await;
+        let duration = start.elapsed();
+        match certificates {
+            Ok((certificates, read_errors)) => {
+                self.check_certificates(&certificates, &read_errors, duration).await;
+            }
+            Err(err) => {
+                error!("Error reading certificates: {:?}", err);
+            }
+        }
+    }
+
+}
+
+/**
+ * Implement the `Monitor` trait for `CertificateMonitor`.
+ */
+impl super::Monitor for CertificateMonitor {
+    /**
+     * Get the name of the monitor.
+     *
+     * Returns: The name of the monitor.
+     */
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Get the status of the monitor.
+     *
+     * Returns: The status of the monitor.
+     */
+    fn get_status(&self) -> Arc<Mutex<HashMap<String, MonitorStatus>>> {
+        self.status.clone()
+    }
+
+    /**
+     * Get the database service.
+     *
+     * Returns: The database service.
+     */
+    fn get_database_service(&self) -> Arc<Option<DbService>> {
+        self.database_service.clone()
+    }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
+    /**
+     * Get the database store level.
+     *
+     * Returns: The database store level.
+     */
+    fn get_database_store_level(&self) -> DatabaseStoreLevel {
+        self.database_store_level.clone()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, net::TcpListener, sync::{Arc, Mutex}, time::{SystemTime, UNIX_EPOCH}};
+
+    use openssl::{
+        asn1::Asn1Time, bn::BigNum, ec::{EcGroup, EcKey}, hash::MessageDigest, nid::Nid,
+        pkey::{PKey, PKeyRef, Private}, ssl::{SslAcceptor, SslMethod},
+        x509::{extension::{BasicConstraints, SubjectAlternativeName}, X509NameBuilder, X509Ref, X509},
+    };
+
+    use crate::common::{configuration::DatabaseStoreLevel, MonitorStatus, Status};
+
+    use super::{CertificateMonitor, Monitor, MonitorSchedule, RunningChecks, SECONDS_PER_DAY};
+
+    /**
+     * Create a certificate for localhost expiring at a time relative to now.
+     *
+     * `common_name`: The common name of the subject.
+     * `seconds_remaining`: Seconds until the certificate expires.
+     * `issuer`: The issuing certificate and key. The certificate is a self-signed CA if not given.
+     */
+    fn create_certificate(common_name: &str, seconds_remaining: i64, issuer: Option<(&X509Ref, &PKeyRef<Private>)>) -> (X509, PKey<Private>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, common_name).unwrap();
+        let name = name.build();
+        let now = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()).unwrap();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap()).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(issuer.map_or(&*name, |(issuer, _)| issuer.subject_name())).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::from_unix(now - 30 * SECONDS_PER_DAY).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::from_unix(now + seconds_remaining).unwrap()).unwrap();
+        if issuer.is_none() {
+            builder.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap();
+        } else {
+            let subject_alternative_name = SubjectAlternativeName::new().dns("localhost")
+                .build(&builder.x509v3_context(issuer.map(|(issuer, _)| issuer), None)).unwrap();
+            builder.append_extension(subject_alternative_name).unwrap();
+        }
+        builder.sign(issuer.map_or(&*key, |(_, issuer_key)| issuer_key), MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
+
+    /**
+     * Write certificates to a PEM file.
+     */
+    fn write_pem(name: &str, certificates: &[&X509]) -> String {
+        let path = format!("/tmp/monitoring-agent-{name}-{}.pem", std::process::id());
+        let pem: Vec<u8> = certificates.iter().flat_map(|certificate| certificate.to_pem().unwrap()).collect();
+        std::fs::write(&path, pem).unwrap();
+        path
+    }
+
+    /**
+     * Start a TLS server on localhost serving a certificate chain.
+     *
+     * Returns: The port of the server.
+     */
+    fn start_server(certificate: &X509, key: &PKey<Private>, chain: &X509) -> u16 {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(certificate).unwrap();
+        acceptor.set_private_key(key).unwrap();
+        acceptor.add_extra_chain_cert(chain.clone()).unwrap();
+        let acceptor = acceptor.build();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = acceptor.accept(stream);
+            }
+        });
+        port
+    }
+
+    /**
+     * Test the check function with certificate files.
+     *
+     * Test the following scenarios:
+     * - The certificate expires within the warning days.
+     * - One file is missing and the certificate of the other file has expired.
+     * - The certificate expires after the warning days.
+     */
+    #[tokio::test]
+    async fn test_check_files() {
+        let (ca, ca_key) = create_certificate("Test CA", 365 * SECONDS_PER_DAY, None);
+        let (leaf, _) = create_certificate("localhost", 10 * SECONDS_PER_DAY + 3600, Some((&ca, &ca_key)));
+        let file = write_pem("files", &[&leaf, &ca]);
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+
+        let mut monitor = CertificateMonitor::new("test", None, 443, Some("localhost".to_string()), Some(vec![file.clone()]), None, false, 30, 7, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        let Status::Warning { message } = status.lock().unwrap().get("test").unwrap().status.clone() else {
+            panic!("Expected a warning");
+        };
+        assert!(message.starts_with(&format!("Certificate check warning: {file}: CN=localhost issued by CN=Test CA expires in 10 days (")), "{message}");
+
+        let (expired, _) = create_certificate("localhost", -2 * SECONDS_PER_DAY - 3600, Some((&ca, &ca_key)));
+        let expired_file = write_pem("expired", &[&expired]);
+        let mut monitor = CertificateMonitor::new("test", None, 443, Some("localhost".to_string()), Some(vec![expired_file.clone(), "/tmp/monitoring-agent-missing.pem".to_string()]), None, false, 30, 7, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        let Status::Error { message } = status.lock().unwrap().get("test").unwrap().status.clone() else {
+            panic!("Expected an error");
+        };
+        assert!(message.starts_with("Certificate check failed: /tmp/monitoring-agent-missing.pem: Error reading certificate file: "), "{message}");
+        assert!(message.contains(&format!("{expired_file}: CN=localhost issued by CN=Test CA expired 2 days ago (")), "{message}");
+
+        let mut monitor = CertificateMonitor::new("test", None, 443, Some("localhost".to_string()), Some(vec![write_pem("ca", &[&ca])]), None, false, 30, 7, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+
+        let _ = std::fs::remove_file(file);
+        let _ = std::fs::remove_file(expired_file);
+    }
+
+    /**
+     * Test the check function against a TLS server.
+     *
+     * Test the following scenarios:
+     * - The certificate of the server expires within the error days. Only the server certificate is reported.
+     * - The certificate of the server can not be verified without the root certificate.
+     */
+    #[tokio::test]
+    async fn test_check_server() {
+        let (ca, ca_key) = create_certificate("Test CA", 365 * SECONDS_PER_DAY, None);
+        let (leaf, leaf_key) = create_certificate("localhost", 5 * SECONDS_PER_DAY + 3600, Some((&ca, &ca_key)));
+        let port = start_server(&leaf, &leaf_key, &ca);
+        let ca_file = write_pem("server-ca", &[&ca]);
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+
+        let mut monitor = CertificateMonitor::new("test", Some("127.0.0.1".to_string()), port, Some("localhost".to_string()), None, Some(ca_file.clone()), false, 30, 7, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        let Status::Error { message } = status.lock().unwrap().get("test").unwrap().status.clone() else {
+            panic!("Expected an error");
+        };
+        assert!(message.starts_with(&format!("Certificate check failed: 127.0.0.1:{port}: CN=localhost issued by CN=Test CA expires in 5 days (")), "{message}");
+        assert_eq!(message.matches("issued by").count(), 1, "{message}");
+
+        let mut monitor = CertificateMonitor::new("test", Some("127.0.0.1".to_string()), port, Some("localhost".to_string()), None, None, false, 30, 7, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        let Status::Error { message } = status.lock().unwrap().get("test").unwrap().status.clone() else {
+            panic!("Expected an error");
+        };
+        assert!(message.contains(&format!("127.0.0.1:{port}: Certificate verification failed: ")), "{message}");
+
+        let _ = std::fs::remove_file(ca_file);
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - Nothing is listening on the port.
+     */
+    #[tokio::test]
+    async fn test_check_connection_refused() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut monitor = CertificateMonitor::new(
+            "test",
+            Some("127.0.0.1".to_string()),
+            port,
+            Some("localhost".to_string()),
+            None,
+            None,
+            false,
+            30,
+            7,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        let Status::Error { message } = &status.get("test").unwrap().status else {
+            panic!("Expected an error");
+        };
+        assert!(message.starts_with(&format!("Certificate check failed: 127.0.0.1:{port}: Could not connect: ")), "{message}");
+    }
+
+    #[test]
+    fn test_get_certificate_monitor_job() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = CertificateMonitor::new("test", Some("localhost".to_string()), 443, None, None, None, false, 30, 7, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let job = monitor.get_certificate_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
+        assert!(job.is_ok());
+    }
+}
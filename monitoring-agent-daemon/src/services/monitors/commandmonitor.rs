@@ -1,12 +1,14 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
+    time::Instant,
 };
 
 use log::{debug, error, info};
 use tokio_cron_scheduler::Job;
 
-use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, services::{monitors::Monitor, DbService}};
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, services::{monitors::Monitor, DbService, NotificationService}};
+use super::{MonitorSchedule, RunningChecks};
 
 /**
  * Command Monitor.
@@ -28,6 +30,8 @@ pub struct CommandMonitor {
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,   
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel, 
 }
@@ -42,11 +46,13 @@ impl CommandMonitor {
      * expected: The expected output of the command.
      * status: The status of the monitor.
      * `database_service`: The database service.
+     * `notification_service`: The notification service.
      * `database_store_level`: The database store level.
      *
      * Returns: A new command monitor.
      * 
      */
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: &str,
         command: &str,
@@ -54,6 +60,7 @@ impl CommandMonitor {
         expected: Option<String>,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel
     ) -> CommandMonitor {
         let status_lock = status.lock();
@@ -73,6 +80,7 @@ impl CommandMonitor {
             expected,
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
         }
     } 
@@ -100,6 +108,7 @@ impl CommandMonitor {
      * Get a command monitor job.
      * 
      * `schedule`: The schedule.
+     * `running_checks`: The running checks. The job marks its check as running.
      * `name`: The name of the monitor.
      * `command`: The command to monitor.
      * `args`: The arguments.
@@ -114,24 +123,22 @@ impl CommandMonitor {
     #[allow(clippy::too_many_arguments)]
     pub fn get_command_monitor_job(
         &mut self,
-        schedule: &str,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating Command monitor: {}", &self.name);
         let command_monitor: CommandMonitor = self.clone();       
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
             let mut command_monitor = command_monitor.clone();
             Box::pin(async move {
-                let _ = command_monitor.check().await.map_err(|err| {
-                    error!("Error checking monitor: {:?}", err);
-                });
+                let mut checked_monitor = command_monitor.clone();
+                command_monitor.check_with_timeout(async move {
+                    let _ = checked_monitor.check().await.map_err(|err| {
+                        error!("Error checking monitor: {:?}", err);
+                    });
+                }, timeout).await;
             })
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            ))
-        }
+        })
     }
 
     /**
@@ -142,26 +149,32 @@ impl CommandMonitor {
      * Returns: Ok if the monitor ran successfully, an error otherwise.
      *
      */
-    async fn check(&mut self) -> Result<(), ApplicationError> {
+    pub async fn check(&mut self) -> Result<(), ApplicationError> {
         debug!("Checking monitor: {}", &self.name);
-        let mut command = std::process::Command::new(&self.command);
+        let mut command = tokio::process::Command::new(&self.command);
         let command = match &self.args {
             Some(args) => command.args(args),
             None => &mut command,
         };
-        let command_result = command.output();
+        /*
+         * The command is killed if the check is cancelled by the timeout of the monitor.
+         */
+        command.kill_on_drop(true);
+        let start = Instant::now();
+        let command_result = command.output().await;
+        let duration = start.elapsed();
         match command_result {
             Ok(output) => {
                 let output_resp = String::from_utf8_lossy(&output.stdout);
                 debug!("Command output: {}", output_resp);
                 if self.is_command_success(&output, &output_resp)
                 {
-                    self.set_status(&Status::Ok).await;
+                    self.set_status(&Status::Ok, duration).await;
                 } else {
                     info!("Monitor status error: {} - {:?}", &self.name, output);
                     self.set_status(&Status::Error {
                         message: format!("Error running command: {output:?}"),
-                    }).await;
+                    }, duration).await;
                 }
                 Ok(())
             }
@@ -169,7 +182,7 @@ impl CommandMonitor {
                 info!("Monitor status error: {} - {:?}", &self.name, err);
                 self.set_status(&Status::Error {
                     message: format!("Error running command: {err:?}"),
-                }).await;
+                }, duration).await;
                 Err(ApplicationError::new(&format!(
                     "Error running command: {err:?}"
                 )))
@@ -209,6 +222,15 @@ impl super::Monitor for CommandMonitor {
     fn get_database_service(&self) -> Arc<Option<DbService>> {
         self.database_service.clone()
     }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
  
     /**
      * Get the database store level.
@@ -222,8 +244,9 @@ impl super::Monitor for CommandMonitor {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test {
-    use std::os::unix::process::ExitStatusExt;
+    use std::{os::unix::process::ExitStatusExt, time::Duration};
 
     use super::*;
 
@@ -234,7 +257,7 @@ mod test {
     async fn test_check_ls() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
         monitor.check().await.unwrap();
         assert_eq!(
             status.lock().unwrap().get("test").unwrap().status,
@@ -256,6 +279,7 @@ mod test {
             None,
             &status,
             &Arc::new(None), 
+            &Arc::new(None), 
             &DatabaseStoreLevel::None
         );
         monitor.check().await.unwrap();
@@ -265,6 +289,23 @@ mod test {
         );
     }
 
+    /**
+     * Test that a command running longer than the timeout is cancelled and gives a timed out error.
+     */
+    #[tokio::test]
+    async fn test_check_with_timeout() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = CommandMonitor::new("test", "sleep", Some(vec!["10".to_string()]), None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut checked_monitor = monitor.clone();
+        let start = Instant::now();
+        monitor.check_with_timeout(async move { let _ = checked_monitor.check().await; }, Some(Duration::from_millis(200))).await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let monitor_status = status.lock().unwrap().get("test").unwrap().clone();
+        assert_eq!(monitor_status.status, Status::Error { message: "Check timed out after 200 ms".to_string() });
+        assert_eq!(monitor_status.timed_out_checks, 1);
+    }
+
     /**
      * Test the check method. Testing non existing command.
      */
@@ -272,7 +313,7 @@ mod test {
     async fn test_check_non_existing_command() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = CommandMonitor::new("test", "grumpy", None, None, &status, &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut monitor = CommandMonitor::new("test", "grumpy", None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
         let _ = monitor.check().await;
         assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error { message: "Error running command: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }".to_string() });
     }
@@ -295,6 +336,7 @@ mod test {
             Some("ActiveState=active\n".to_string()),
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None
         );
         let _ = monitor.check().await;
@@ -308,7 +350,7 @@ mod test {
     fn test_is_command_success_exitstatus_0() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
         let output = std::process::Output {
             status: std::process::ExitStatus::from_raw(0),
             stdout: Vec::new(),
@@ -321,7 +363,7 @@ mod test {
     fn test_is_command_success_exitstatus_1() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
         let output = std::process::Output {
             status: std::process::ExitStatus::from_raw(1),
             stdout: Vec::new(),
@@ -334,8 +376,8 @@ mod test {
     fn test_get_command_monitor_job() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &DatabaseStoreLevel::None);
-        let job = monitor.get_command_monitor_job("0 * * * * *");
+        let mut monitor = CommandMonitor::new("test", "ls", None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let job = monitor.get_command_monitor_job(&MonitorSchedule::new("0 * * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }
 }
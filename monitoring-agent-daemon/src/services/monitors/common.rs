@@ -1,8 +1,8 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}};
+use std::{collections::HashMap, future::Future, sync::{Arc, Mutex}, time::Duration};
 
-use log::{debug, error};
+use log::{debug, error, info};
 
-use crate::{common::{configuration::DatabaseStoreLevel, MonitorStatus, Status}, services::DbService};
+use crate::{common::{configuration::DatabaseStoreLevel, MonitorStatus, Status}, services::{DbService, NotificationService}};
 
 pub trait Monitor {
     
@@ -27,6 +27,13 @@ pub trait Monitor {
      */
     fn get_database_service(&self) -> Arc<Option<DbService>>;
 
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>>;
+
     /**
      * Get the database store level.
      *
@@ -35,15 +42,64 @@ pub trait Monitor {
     fn get_database_store_level(&self) -> DatabaseStoreLevel;
 
     /**
-     * Set the status of the monitor.
+     * Set the status of the monitor. The status only changes when the thresholds of the monitor are reached.
+     * Notifies the notification service if the status changed.
+     *
+     * `new_status`: The new status.
+     * `duration`: The duration of the check.
+     *
+     */
+    async fn set_status(&mut self, new_status: &Status, duration: Duration) {
+        let Some((old_status, current_status)) = self.update_monitor_status(new_status, duration) else {
+            return;
+        };
+        /*
+         * Only the first flapping status is stored to avoid flooding the database. Checks of paused monitors are not stored.
+         */
+        let still_flapping = matches!(old_status, Status::Flapping { .. }) && matches!(current_status, Status::Flapping { .. });
+        if !still_flapping && current_status != Status::Paused {
+            self.insert_monitor_status(&current_status, duration).await;
+        }
+        self.notify_status_transition(&old_status, &current_status);
+    }
+
+    /**
+     * Run a check of the monitor with a timeout. A check running longer than the timeout is cancelled, and the
+     * monitor gets a timed out error.
+     *
+     * `check`: The check.
+     * `timeout`: The maximum duration of the check. If None, the check runs until it finishes.
+     *
+     */
+    async fn check_with_timeout<F: Future<Output = ()>>(&mut self, check: F, timeout: Option<Duration>) {
+        let Some(timeout) = timeout else {
+            check.await;
+            return;
+        };
+        if tokio::time::timeout(timeout, check).await.is_ok() {
+            return;
+        }
+        let message = format!("Check timed out after {} ms", timeout.as_millis());
+        info!("Monitor status error: {} - {}", self.get_name(), message);
+        if let Ok(mut monitor_lock) = self.get_status().lock() {
+            if let Some(monitor_status) = monitor_lock.get_mut(self.get_name()) {
+                monitor_status.timed_out_checks += 1;
+            }
+        }
+        self.set_status(&Status::Error { message }, timeout).await;
+    }
+
+    /**
+     * Update the status of the monitor in the status map.
      *
      * `new_status`: The new status.
+     * `duration`: The duration of the check.
      *
+     * Returns: The previous and current status, or None if the status could not be updated.
      */
-    async fn set_status(&mut self, new_status: &Status) {
-        self.insert_monitor_status(new_status).await;
+    fn update_monitor_status(&self, new_status: &Status, duration: Duration) -> Option<(Status, Status)> {
         let status = self.get_status();
-        match status.lock() {
+        let statuses = match status.lock() {
             Ok(mut monitor_lock) => {
                 debug!(
                     "Setting monitor status for {} to: {:?}",
@@ -51,23 +107,44 @@ pub trait Monitor {
                 );
                 let Some(monitor_status) = monitor_lock.get_mut(self.get_name()) else {
                     error!("Monitor status not found for: {}", &self.get_name());
-                    return;
+                    return None;
                 };
-                monitor_status.set_status(new_status);
+                let old_status = monitor_status.status.clone();
+                let current_status = monitor_status.set_status(new_status, duration);
+                Some((old_status, current_status))
             }
             Err(err) => {
                 error!("Error updating monitor status: {:?}", err);
+                None
             }
         };
+        statuses
+    }
+
+    /**
+     * Notify the notification service if the status has changed.
+     *
+     * `old_status`: The previous status.
+     * `new_status`: The new status.
+     *
+     */
+    fn notify_status_transition(&self, old_status: &Status, new_status: &Status) {
+        if !NotificationService::is_transition(old_status, new_status) {
+            return;
+        }
+        if let Some(notification_service) = self.get_notification_service().as_ref() {
+            notification_service.notify(self.get_name(), old_status, new_status);
+        }
     }
 
    /**
      * Insert the monitor status into the database.
      *
      * status: The status to insert.
+     * duration: The duration of the check.
      *
      */
-    async fn insert_monitor_status(&mut self, status: &Status) {
+    async fn insert_monitor_status(&mut self, status: &Status, duration: Duration) {
         match self.get_database_store_level() {
             DatabaseStoreLevel::None => {
                 return;
@@ -89,6 +166,7 @@ pub trait Monitor {
                 match database_service.insert_monitor_status(
                     self.get_name(),
                     &status.clone(),
+                    duration,
                 ).await {
                     Ok(()) => {}
                     Err(err) => {
@@ -100,6 +178,28 @@ pub trait Monitor {
     }  
 }
 
+/**
+ * Check the response time of a successful check against the max response time.
+ *
+ * `status`: The status returned by the check.
+ * `duration`: The duration of the check.
+ * `max_response_time`: The max response time in milliseconds.
+ *
+ * Returns: An error status if the response time exceeded the max response time, otherwise the given status.
+ */
+pub fn check_response_time(status: Status, duration: Duration, max_response_time: Option<u64>) -> Status {
+    let Some(max_response_time) = max_response_time else {
+        return status;
+    };
+    let response_time = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+    if status == Status::Ok && response_time > max_response_time {
+        return Status::Error {
+            message: format!("Response time {response_time} ms exceeded max response time {max_response_time} ms"),
+        };
+    }
+    status
+}
+
 #[cfg(test)]
 mod test {
 
@@ -122,7 +222,7 @@ mod test {
         let name = "test_monitor";
         let status = Status::Ok;
         let mut monitorstatus = MonitorStatus::new(name.to_string(), status.clone());
-        monitorstatus.set_status(&status);
+        monitorstatus.set_status(&status, std::time::Duration::ZERO);
         assert_eq!(monitorstatus.status, status);
         assert!(monitorstatus.last_successful_time.is_some());
         assert_eq!(monitorstatus.last_error, None);
@@ -131,10 +231,21 @@ mod test {
         let status = Status::Error {
             message: "test error".to_string(),
         };
-        monitorstatus.set_status(&status);
+        monitorstatus.set_status(&status, std::time::Duration::ZERO);
         assert_eq!(monitorstatus.status, status);
         assert!(monitorstatus.last_successful_time.is_some());
         assert_eq!(monitorstatus.last_error, Some("test error".to_string()));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_check_response_time() {
+        assert_eq!(check_response_time(Status::Ok, Duration::from_millis(500), None), Status::Ok);
+        assert_eq!(check_response_time(Status::Ok, Duration::from_millis(500), Some(500)), Status::Ok);
+        assert_eq!(
+            check_response_time(Status::Ok, Duration::from_millis(501), Some(500)),
+            Status::Error { message: "Response time 501 ms exceeded max response time 500 ms".to_string() }
+        );
+        let error = Status::Error { message: "Connection refused".to_string() };
+        assert_eq!(check_response_time(error.clone(), Duration::from_millis(501), Some(500)), error);
+    }
+}
@@ -0,0 +1,429 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use log::{debug, error, info};
+use monitoring_agent_lib::proc::{stat::CpuUsage, ProcsCpuStat, ProcsCpuUsage};
+use tokio_cron_scheduler::Job;
+
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService, services::NotificationService};
+
+use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
+
+/// The time between the two samples of /proc/stat the cpu usage is calculated from.
+const SAMPLE_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct CpuMonitor {
+    /// The name of the monitor.
+    pub name: String,
+    /// Max percentage cpu used.
+    pub max_percentage_used: Option<f64>,
+    /// Warning percentage cpu used.
+    pub warning_percentage_used: Option<f64>,
+    /// Max percentage waiting for I/O.
+    pub max_percentage_iowait: Option<f64>,
+    /// Warning percentage waiting for I/O.
+    pub warning_percentage_iowait: Option<f64>,
+    /// Max percentage stolen by the hypervisor.
+    pub max_percentage_steal: Option<f64>,
+    /// Warning percentage stolen by the hypervisor.
+    pub warning_percentage_steal: Option<f64>,
+    /// Check the thresholds for each core as well.
+    pub per_core: bool,
+    /// The status of the monitor.
+    pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+    /// The database service
+    database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
+    /// The database store level.
+    database_store_level: DatabaseStoreLevel,
+    /// Store the current cpu usage.
+    store_current_cpuusage: bool,
+}
+
+impl CpuMonitor {
+
+    /**
+     * Create a new `CpuMonitor`.
+     *
+     * `name`: The name of the monitor.
+     * `max_percentage_used`: The maximum percentage cpu used.
+     * `warning_percentage_used`: The warning percentage cpu used.
+     * `max_percentage_iowait`: The maximum percentage waiting for I/O.
+     * `warning_percentage_iowait`: The warning percentage waiting for I/O.
+     * `max_percentage_steal`: The maximum percentage stolen by the hypervisor.
+     * `warning_percentage_steal`: The warning percentage stolen by the hypervisor.
+     * `per_core`: Check the thresholds for each core as well as for all cores together.
+     * `status`: The status of the monitor.
+     * `database_service`: The database service.
+     * `notification_service`: The notification service.
+     * `database_store_level`: The database store level.
+     * `store_current_cpuusage`: Store the current cpu usage.
+     *
+     * Returns: A new `CpuMonitor`.
+     *
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        max_percentage_used: Option<f64>,
+        warning_percentage_used: Option<f64>,
+        max_percentage_iowait: Option<f64>,
+        warning_percentage_iowait: Option<f64>,
+        max_percentage_steal: Option<f64>,
+        warning_percentage_steal: Option<f64>,
+        per_core: bool,
+        status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
+        database_store_level: &DatabaseStoreLevel,
+        store_current_cpuusage: bool,
+    ) -> CpuMonitor {
+
+        let status_lock = status.lock();
+        match status_lock {
+            Ok(mut lock) => {
+                lock.insert(name.to_string(), MonitorStatus::new(name.to_string(), Status::Unknown));
+            }
+            Err(err) => {
+                error!("Error creating cpu monitor: {:?}", err);
+            }
+        }
+
+        CpuMonitor {
+            name: name.to_string(),
+            max_percentage_used,
+            warning_percentage_used,
+            max_percentage_iowait,
+            warning_percentage_iowait,
+            max_percentage_steal,
+            warning_percentage_steal,
+            per_core,
+            status: status.clone(),
+            database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
+            database_store_level: database_store_level.clone(),
+            store_current_cpuusage,
+        }
+    }
+
+    /**
+     * Get the current cpu usage. Two samples of /proc/stat are taken one second apart.
+     *
+     * Returns: The cpu usage between the samples.
+     *
+     * throws: `ApplicationError`: If /proc/stat can not be read.
+     */
+    pub async fn get_cpu_usage() -> Result<ProcsCpuUsage, ApplicationError> {
+        let previous = ProcsCpuStat::get_cpu_stat().map_err(|err| ApplicationError::new(&err.message))?;
+        tokio::time::sleep(SAMPLE_TIME).await;
+        let current = ProcsCpuStat::get_cpu_stat().map_err(|err| ApplicationError::new(&err.message))?;
+        Ok(current.get_usage(&previous))
+    }
+
+    /**
+     * Check the cpu usage.
+     *
+     * `usage`: The cpu usage.
+     * `duration`: The duration of the check.
+     *
+     */
+    async fn check_usage(&mut self, usage: &ProcsCpuUsage, duration: Duration) {
+        let mut errors: Vec<String> = Vec::new();
+        let mut warnings: Vec<String> = Vec::new();
+        let cores: &[CpuUsage] = if self.per_core { &usage.cores } else { &[] };
+        for cpu in std::iter::once(&usage.total).chain(cores) {
+            let prefix = if cpu.name == usage.total.name { String::new() } else { format!("{}: ", cpu.name) };
+            let checks = [
+                ("CPU use", self.max_percentage_used, self.warning_percentage_used, cpu.get_percent_used()),
+                ("Iowait", self.max_percentage_iowait, self.warning_percentage_iowait, cpu.iowait),
+                ("Steal", self.max_percentage_steal, self.warning_percentage_steal, cpu.steal),
+            ];
+            for (kind, max, warning, current) in checks {
+                match CpuMonitor::check_cpu_thresholds(kind, max, warning, current) {
+                    Status::Error { message } => errors.push(format!("{prefix}{message}")),
+                    Status::Warning { message } => warnings.push(format!("{prefix}{message}")),
+                    _ => {}
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            self.set_status(&Status::Error {
+                message: format!("CPU check failed: {}", errors.join(", ")),
+            }, duration).await;
+        } else if !warnings.is_empty() {
+            self.set_status(&Status::Warning {
+                message: format!("CPU check warning: {}", warnings.join(", ")),
+            }, duration).await;
+        } else {
+            self.set_status(&Status::Ok, duration).await;
+        }
+    }
+
+    /**
+     * Check a cpu percentage against both the max and the warning threshold.
+     *
+     * `kind`: What is checked, e.g. CPU use or Iowait.
+     * `max`: The max percentage.
+     * `warning`: The warning percentage.
+     * `current`: The current percentage.
+     *
+     * Returns: Error if above max, warning if above the warning threshold, otherwise ok.
+     *
+     */
+    fn check_cpu_thresholds(kind: &str, max: Option<f64>, warning: Option<f64>, current: f64) -> Status {
+        if let Some(max) = max {
+            if current > max {
+                return Status::Error {
+                    message: format!("{kind} {current:0.3}% is more than {max:0.3}%"),
+                };
+            }
+        }
+        if let Some(warning) = warning {
+            if current > warning {
+                return Status::Warning {
+                    message: format!("{kind} {current:0.3}% is more than warning {warning:0.3}%"),
+                };
+            }
+        }
+        Status::Ok
+    }
+
+    /**
+     * Check and store the current cpu usage.
+     *
+     * `usage`: The current cpu usage.
+     *
+     */
+    async fn check_store_current_cpuusage(&self, usage: &ProcsCpuUsage) {
+        if self.store_current_cpuusage {
+            self.store_current_cpuusage(usage).await;
+        }
+    }
+
+    /**
+     * Store the current cpu usage.
+     *
+     * `usage`: The current cpu usage.
+     */
+    async fn store_current_cpuusage(&self, usage: &ProcsCpuUsage) {
+        if let Some(database_service) = self.database_service.as_ref() {
+            match database_service.store_cpuusage(usage).await {
+                Ok(()) => {}
+                Err(err) => {
+                    error!("Error storing cpu usage: {:?}", err);
+                }
+            }
+        }
+    }
+
+    /**
+     * Get cpu monitor job.
+     *
+     * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
+     *
+     * Returns: The cpu monitor job.
+     *
+     */
+    pub fn get_cpu_monitor_job(
+        &mut self,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
+    ) -> Result<Job, ApplicationError> {
+        info!("Creating cpu monitor: {}", &self.name);
+        let cpu_monitor = self.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut cpu_monitor = cpu_monitor.clone();
+            Box::pin(async move {
+                let mut checked_monitor = cpu_monitor.clone();
+                cpu_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
+    }
+
+    /**
+     * Check the monitor.
+     */
+    pub async fn check(&mut self) {
+        debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
+        let usage = CpuMonitor::get_cpu_usage().await;
+        let duration = start.elapsed();
+        match usage {
+            Ok(usage) => {
+                self.check_store_current_cpuusage(&usage).await;
+                self.check_usage(&usage, duration).await;
+            }
+            Err(err) => {
+                self.set_status(&Status::Error {
+                    message: format!("CPU check failed: {}", err.get_message()),
+                }, duration).await;
+            }
+        }
+    }
+
+}
+
+/**
+ * Implement the `Monitor` trait for `CpuMonitor`.
+ */
+impl super::Monitor for CpuMonitor {
+    /**
+     * Get the name of the monitor.
+     *
+     * Returns: The name of the monitor.
+     */
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Get the status of the monitor.
+     *
+     * Returns: The status of the monitor.
+     */
+    fn get_status(&self) -> Arc<Mutex<HashMap<String, MonitorStatus>>> {
+        self.status.clone()
+    }
+
+    /**
+     * Get the database service.
+     *
+     * Returns: The database service.
+     */
+    fn get_database_service(&self) -> Arc<Option<DbService>> {
+        self.database_service.clone()
+    }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
+    /**
+     * Get the database store level.
+     *
+     * Returns: The database store level.
+     */
+    fn get_database_store_level(&self) -> DatabaseStoreLevel {
+        self.database_store_level.clone()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+
+    use monitoring_agent_lib::proc::{stat::CpuUsage, ProcsCpuUsage};
+
+    use crate::common::{configuration::DatabaseStoreLevel, MonitorStatus, Status};
+
+    use super::{CpuMonitor, Monitor, MonitorSchedule, RunningChecks};
+
+    /**
+     * Get cpu usage with the given user, iowait and steal percentages.
+     */
+    fn get_cpu(name: &str, user: f64, iowait: f64, steal: f64) -> CpuUsage {
+        CpuUsage { name: name.to_string(), user, system: 0.0, iowait, steal, idle: 100.0 - user - iowait - steal }
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - The cpu use of the system and each core is below the max percentage used.
+     */
+    #[tokio::test]
+    async fn test_check() {
+        let mut monitor = CpuMonitor::new(
+            "test",
+            Some(100.0),
+            None,
+            None,
+            None,
+            None,
+            None,
+            true,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+            false,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, Status::Ok);
+    }
+
+    /**
+     * Test the check_usage function.
+     *
+     * Test the following scenarios:
+     * - Total cpu use is higher than the warning percentage, and one core is higher than the max percentage, but
+     *   the cores are not checked.
+     * - One core is higher than the max percentage and the cores are checked.
+     * - Iowait is higher than the max percentage.
+     * - Steal is higher than the warning percentage.
+     * - Cpu use, iowait and steal are lower than all percentages.
+     */
+    #[tokio::test]
+    async fn test_check_usage() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let usage = ProcsCpuUsage {
+            total: get_cpu("cpu", 60.0, 10.0, 0.0),
+            cores: vec![get_cpu("cpu0", 95.0, 0.0, 0.0), get_cpu("cpu1", 25.0, 20.0, 0.0)],
+        };
+
+        let mut monitor = CpuMonitor::new("test", Some(90.0), Some(50.0), Some(20.0), None, None, Some(5.0), false, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_usage(&usage, Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Warning {
+            message: "CPU check warning: CPU use 60.000% is more than warning 50.000%".to_string(),
+        });
+
+        let mut monitor = CpuMonitor::new("test", Some(90.0), Some(50.0), Some(20.0), None, None, Some(5.0), true, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_usage(&usage, Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "CPU check failed: cpu0: CPU use 95.000% is more than 90.000%".to_string(),
+        });
+
+        let usage = ProcsCpuUsage { total: get_cpu("cpu", 10.0, 30.0, 6.0), cores: Vec::new() };
+        let mut monitor = CpuMonitor::new("test", Some(90.0), None, Some(20.0), None, None, Some(5.0), true, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_usage(&usage, Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "CPU check failed: Iowait 30.000% is more than 20.000%".to_string(),
+        });
+
+        let usage = ProcsCpuUsage { total: get_cpu("cpu", 10.0, 0.0, 6.0), cores: Vec::new() };
+        let mut monitor = CpuMonitor::new("test", Some(90.0), None, Some(20.0), None, None, Some(5.0), false, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_usage(&usage, Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Warning {
+            message: "CPU check warning: Steal 6.000% is more than warning 5.000%".to_string(),
+        });
+
+        let usage = ProcsCpuUsage { total: get_cpu("cpu", 10.0, 0.0, 0.0), cores: Vec::new() };
+        let mut monitor = CpuMonitor::new("test", Some(90.0), Some(50.0), Some(20.0), None, None, Some(5.0), true, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_usage(&usage, Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+    }
+
+    #[test]
+    fn test_get_cpu_monitor_job() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = CpuMonitor::new("test", Some(90.0), None, None, None, None, None, false, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        let job = monitor.get_cpu_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
+        assert!(job.is_ok());
+    }
+}
@@ -1,9 +1,10 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}};
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::Instant};
 
 use log::{debug, error, info};
 use tokio_cron_scheduler::Job;
 
-use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, services::{monitors::Monitor, DbService}};
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, services::{monitors::Monitor, DbService, NotificationService}};
+use super::{MonitorSchedule, RunningChecks};
 
 /**
  * Database monitor.
@@ -14,6 +15,7 @@ use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, Monito
  * `query_max_time`: The max query time.
  * `status`: The status of the monitor.
  * `database_service`: The database service.
+ * `notification_service`: The notification service.
  * `database_store_level`: The database store level.
  */
 #[derive(Debug, Clone)]
@@ -26,6 +28,8 @@ pub struct DatabaseMonitor {
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,   
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel,        
 }
@@ -38,6 +42,7 @@ impl DatabaseMonitor {
      * `query_max_time`: The max query time.
      * `status`: The status of the monitor.
      * `database_service`: The database service.
+     * `notification_service`: The notification service.
      * `database_store_level`: The database store level.
      * 
      * Returns a new `DatabaseMonitor`.
@@ -47,6 +52,7 @@ impl DatabaseMonitor {
         query_max_time: Option<u32>,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel,
     ) -> DatabaseMonitor {
 
@@ -65,6 +71,7 @@ impl DatabaseMonitor {
             query_max_time,
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
         }
     }
@@ -73,6 +80,7 @@ impl DatabaseMonitor {
      * Get meminfo monitor job.
      * 
      * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
      * 
      * Returns: The meminfo monitor job.
      * 
@@ -81,36 +89,33 @@ impl DatabaseMonitor {
     #[allow(clippy::similar_names)]    
     pub fn get_database_monitor_job(
         &mut self,
-        schedule: &str,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating database monitor: {}", &self.name);
         let database_monitor = self.clone();       
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {                
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
             let mut database_monitor = database_monitor.clone();
             Box::pin(async move {
-                database_monitor.check().await;
-            })  
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            )), 
-        }
+                let mut checked_monitor = database_monitor.clone();
+                database_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
     }   
 
     /**
      * Check the status of the database.
      */
-    async fn check(&mut self) {
+    pub async fn check(&mut self) {
         debug!("Checking monitor: {}", &self.name);
         let Some(database_service) = &*self.database_service else {
             error!("Database service not found.");
                 return;
         };
+        let start = Instant::now();
         let mut status = Status::Ok;
-        if self.query_max_time.is_some() {
-            let overtimed_query = match database_service.query_long_running_queries(self.query_max_time.unwrap()).await {
+        if let Some(query_max_time) = self.query_max_time {
+            let overtimed_query = match database_service.query_long_running_queries(query_max_time).await {
                 Ok(query) => query,
                 Err(err) => {
                     error!("Error checking query time: {:?}", err);
@@ -123,7 +128,7 @@ impl DatabaseMonitor {
                 };
             }
         }
-        self.set_status(&status).await;
+        self.set_status(&status, start.elapsed()).await;
     
     } 
 
@@ -159,6 +164,15 @@ impl super::Monitor for DatabaseMonitor {
     fn get_database_service(&self) -> Arc<Option<DbService>> {
         self.database_service.clone()
     }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
  
     /**
      * Get the database store level.
@@ -184,7 +198,7 @@ mod test {
         let status = Arc::new(Mutex::new(HashMap::new()));
         let database_service = Arc::new(None);
         let database_store_level = DatabaseStoreLevel::None;
-        let database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &database_store_level);
+        let database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &Arc::new(None), &database_store_level);
         assert_eq!(database_monitor.name, name);
     }
 
@@ -194,8 +208,8 @@ mod test {
         let status = Arc::new(Mutex::new(HashMap::new()));
         let database_service = Arc::new(None);
         let database_store_level = DatabaseStoreLevel::None;
-        let mut database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &database_store_level);
-        let job = database_monitor.get_database_monitor_job("* * * * * *");
+        let mut database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &Arc::new(None), &database_store_level);
+        let job = database_monitor.get_database_monitor_job(&MonitorSchedule::new("* * * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }
 
@@ -205,7 +219,7 @@ mod test {
         let status = Arc::new(Mutex::new(HashMap::new()));
         let database_service = Arc::new(None);
         let database_store_level = DatabaseStoreLevel::None;
-        let mut database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &database_store_level);
+        let mut database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &Arc::new(None), &database_store_level);
         let check = database_monitor.check().await;
         assert_eq!(check, ());
     }
@@ -216,7 +230,7 @@ mod test {
         let status = Arc::new(Mutex::new(HashMap::new()));
         let database_service = Arc::new(None);
         let database_store_level = DatabaseStoreLevel::None;
-        let database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &database_store_level);
+        let database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &Arc::new(None), &database_store_level);
         assert_eq!(database_monitor.get_name(), name);
     }
 
@@ -226,7 +240,7 @@ mod test {
         let status = Arc::new(Mutex::new(HashMap::new()));
         let database_service = Arc::new(None);
         let database_store_level = DatabaseStoreLevel::None;
-        let database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &database_store_level);
+        let database_monitor = DatabaseMonitor::new(name, None, &status, &database_service, &Arc::new(None), &database_store_level);
         assert_eq!(database_monitor.get_status().lock().unwrap().get("test").unwrap().status, Status::Unknown);
     }
 }
\ No newline at end of file
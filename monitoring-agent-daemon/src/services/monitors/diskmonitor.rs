@@ -0,0 +1,430 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use log::{debug, error, info};
+use monitoring_agent_lib::proc::ProcsDisk;
+use tokio_cron_scheduler::Job;
+
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService, services::NotificationService};
+
+use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
+
+#[derive(Debug, Clone)]
+pub struct DiskMonitor {
+    /// The name of the monitor.
+    pub name: String,
+    /// The mount points to check. If None, then all real filesystems are checked.
+    pub mount_points: Option<Vec<String>>,
+    /// Max percentage disk space used.
+    pub max_percentage_used: Option<f64>,
+    /// Max percentage inodes used.
+    pub max_percentage_inodes_used: Option<f64>,
+    /// Warning percentage disk space used.
+    pub warning_percentage_used: Option<f64>,
+    /// Warning percentage inodes used.
+    pub warning_percentage_inodes_used: Option<f64>,
+    /// The status of the monitor.
+    pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+    /// The database service
+    database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
+    /// The database store level.
+    database_store_level: DatabaseStoreLevel,
+    /// Store the current disk usage.
+    store_current_diskinfo: bool,
+}
+
+impl DiskMonitor {
+
+    /**
+     * Create a new `DiskMonitor`.
+     *
+     * `name`: The name of the monitor.
+     * `mount_points`: The mount points to check. If None, then all real filesystems are checked.
+     * `max_percentage_used`: The maximum percentage disk space used.
+     * `max_percentage_inodes_used`: The maximum percentage inodes used.
+     * `warning_percentage_used`: The warning percentage disk space used.
+     * `warning_percentage_inodes_used`: The warning percentage inodes used.
+     * `status`: The status of the monitor.
+     * `database_service`: The database service.
+     * `notification_service`: The notification service.
+     * `database_store_level`: The database store level.
+     * `store_current_diskinfo`: Store the current disk usage.
+     *
+     * Returns: A new `DiskMonitor`.
+     *
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        mount_points: Option<Vec<String>>,
+        max_percentage_used: Option<f64>,
+        max_percentage_inodes_used: Option<f64>,
+        warning_percentage_used: Option<f64>,
+        warning_percentage_inodes_used: Option<f64>,
+        status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
+        database_store_level: &DatabaseStoreLevel,
+        store_current_diskinfo: bool,
+    ) -> DiskMonitor {
+
+        let status_lock = status.lock();
+        match status_lock {
+            Ok(mut lock) => {
+                lock.insert(name.to_string(), MonitorStatus::new(name.to_string(), Status::Unknown));
+            }
+            Err(err) => {
+                error!("Error creating disk monitor: {:?}", err);
+            }
+        }
+
+        DiskMonitor {
+            name: name.to_string(),
+            mount_points,
+            max_percentage_used,
+            max_percentage_inodes_used,
+            warning_percentage_used,
+            warning_percentage_inodes_used,
+            status: status.clone(),
+            database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
+            database_store_level: database_store_level.clone(),
+            store_current_diskinfo,
+        }
+    }
+
+    /**
+     * Read the disk usage of the mount points, or of all real filesystems if no mount points are given.
+     *
+     * `mount_points`: The mount points.
+     *
+     * Returns: The disk usage and the mount points that could not be read.
+     */
+    fn read_disks(mount_points: Option<&[String]>) -> (Vec<ProcsDisk>, Vec<String>) {
+        let Some(mount_points) = mount_points else {
+            return match ProcsDisk::get_disks() {
+                Ok(disks) => (disks, Vec::new()),
+                Err(err) => (Vec::new(), vec![err.message]),
+            };
+        };
+        let mut disks = Vec::new();
+        let mut errors = Vec::new();
+        for mount_point in mount_points {
+            match ProcsDisk::get_disk(mount_point) {
+                Ok(disk) => disks.push(disk),
+                Err(err) => errors.push(err.message),
+            }
+        }
+        (disks, errors)
+    }
+
+    /**
+     * Check the disk usage.
+     *
+     * `disks`: The disk usage.
+     * `read_errors`: The mount points that could not be read.
+     * `duration`: The duration of the check.
+     *
+     */
+    async fn check_disks(&mut self, disks: &[ProcsDisk], read_errors: &[String], duration: Duration) {
+        let mut errors: Vec<String> = read_errors.to_vec();
+        let mut warnings: Vec<String> = Vec::new();
+        for disk in disks {
+            let checks = [
+                ("Disk", self.max_percentage_used, self.warning_percentage_used, disk.get_percent_used()),
+                ("Inode", self.max_percentage_inodes_used, self.warning_percentage_inodes_used, disk.get_percent_inodes_used()),
+            ];
+            for (kind, max, warning, current) in checks {
+                match DiskMonitor::check_disk_thresholds(kind, max, warning, current) {
+                    Status::Error { message } => errors.push(format!("{}: {message}", disk.mount_point)),
+                    Status::Warning { message } => warnings.push(format!("{}: {message}", disk.mount_point)),
+                    _ => {}
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            self.set_status(&Status::Error {
+                message: format!("Disk check failed: {}", errors.join(", ")),
+            }, duration).await;
+        } else if !warnings.is_empty() {
+            self.set_status(&Status::Warning {
+                message: format!("Disk check warning: {}", warnings.join(", ")),
+            }, duration).await;
+        } else {
+            self.set_status(&Status::Ok, duration).await;
+        }
+    }
+
+    /**
+     * Check the disk usage against both the max and the warning threshold.
+     *
+     * `kind`: What is used, Disk or Inode.
+     * `max`: The max percentage used.
+     * `warning`: The warning percentage used.
+     * `current`: The current percentage used.
+     *
+     * Returns: Error if above max, warning if above the warning threshold, otherwise ok.
+     *
+     */
+    fn check_disk_thresholds(kind: &str, max: Option<f64>, warning: Option<f64>, current: Option<f64>) -> Status {
+        let Some(current) = current else { return Status::Ok };
+        if let Some(max) = max {
+            if current > max {
+                return Status::Error {
+                    message: format!("{kind} use {current:0.3}% is more than {max:0.3}%"),
+                };
+            }
+        }
+        if let Some(warning) = warning {
+            if current > warning {
+                return Status::Warning {
+                    message: format!("{kind} use {current:0.3}% is more than warning {warning:0.3}%"),
+                };
+            }
+        }
+        Status::Ok
+    }
+
+    /**
+     * Check and store the current disk usage.
+     *
+     * `disks`: The current disk usage.
+     *
+     */
+    async fn check_store_current_diskinfo(&self, disks: &[ProcsDisk]) {
+        if self.store_current_diskinfo && !disks.is_empty() {
+            self.store_current_diskinfo(disks).await;
+        }
+    }
+
+    /**
+     * Store the current disk usage.
+     *
+     * `disks`: The current disk usage.
+     */
+    async fn store_current_diskinfo(&self, disks: &[ProcsDisk]) {
+        if let Some(database_service) = self.database_service.as_ref() {
+            match database_service.store_diskinfo(disks).await {
+                Ok(()) => {}
+                Err(err) => {
+                    error!("Error storing disk usage: {:?}", err);
+                }
+            }
+        }
+    }
+
+    /**
+     * Get disk monitor job.
+     *
+     * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
+     *
+     * Returns: The disk monitor job.
+     *
+     */
+    pub fn get_disk_monitor_job(
+        &mut self,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
+    ) -> Result<Job, ApplicationError> {
+        info!("Creating disk monitor: {}", &self.name);
+        let disk_monitor = self.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut disk_monitor = disk_monitor.clone();
+            Box::pin(async move {
+                let mut checked_monitor = disk_monitor.clone();
+                disk_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
+    }
+
+    /**
+     * Check the monitor.
+     */
+    pub async fn check(&mut self) {
+        debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
+        /*
+         * Reading the usage of a hung network filesystem blocks, so it is read outside of the async workers.
+         */
+        let mount_points = self.mount_points.clone();
+        let disks = tokio::task::spawn_blocking(move || DiskMonitor::read_disks(mount_points.as_deref())).await;
+        let duration = start.elapsed();
+        match disks {
+            Ok((disks, read_errors)) => {
+                self.check_store_current_diskinfo(&disks).await;
+                self.check_disks(&disks, &read_errors, duration).await;
+            }
+            Err(err) => {
+                error!("Error getting disk usage: {:?}", err);
+            }
+        }
+    }
+
+}
+
+/**
+ * Implement the `Monitor` trait for `DiskMonitor`.
+ */
+impl super::Monitor for DiskMonitor {
+    /**
+     * Get the name of the monitor.
+     *
+     * Returns: The name of the monitor.
+     */
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Get the status of the monitor.
+     *
+     * Returns: The status of the monitor.
+     */
+    fn get_status(&self) -> Arc<Mutex<HashMap<String, MonitorStatus>>> {
+        self.status.clone()
+    }
+
+    /**
+     * Get the database service.
+     *
+     * Returns: The database service.
+     */
+    fn get_database_service(&self) -> Arc<Option<DbService>> {
+        self.database_service.clone()
+    }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
+    /**
+     * Get the database store level.
+     *
+     * Returns: The database store level.
+     */
+    fn get_database_store_level(&self) -> DatabaseStoreLevel {
+        self.database_store_level.clone()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+
+    use monitoring_agent_lib::proc::ProcsDisk;
+
+    use crate::common::{configuration::DatabaseStoreLevel, MonitorStatus, Status};
+
+    use super::{DiskMonitor, Monitor, MonitorSchedule, RunningChecks};
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - The root filesystem is below the max percentage used.
+     */
+    #[tokio::test]
+    async fn test_check() {
+        let mut monitor = DiskMonitor::new(
+            "test",
+            Some(vec!["/".to_string()]),
+            Some(100.0),
+            Some(90.0),
+            None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+            false,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, Status::Ok);
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - One of the configured mount points has nothing mounted.
+     */
+    #[tokio::test]
+    async fn test_check_missing_mount_point() {
+        let mut monitor = DiskMonitor::new(
+            "test",
+            Some(vec!["/".to_string(), "/no/such/mount".to_string()]),
+            Some(100.0),
+            Some(90.0),
+            None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+            false,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, Status::Error { message: "Disk check failed: Nothing is mounted on /no/such/mount".to_string() });
+    }
+
+    /**
+     * Test the check_disks function.
+     *
+     * Test the following scenarios:
+     * - Disk use and inode use are higher than the max percentages.
+     * - Disk use is higher than the warning percentage, but lower than the max percentage.
+     * - Disk use is lower than the max percentage and no warning percentage is set.
+     */
+    #[tokio::test]
+    async fn test_check_disks() {
+        let disks = vec![
+            ProcsDisk::new("/dev/sda1", "/", "ext4", Some(100_000), Some(20_000), Some(20_000), Some(1000), Some(50)),
+            ProcsDisk::new("/dev/sda2", "/home", "ext4", Some(100_000), Some(40_000), Some(40_000), Some(1000), Some(900)),
+        ];
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+
+        let mut monitor = DiskMonitor::new("test", None, Some(70.0), Some(90.0), Some(50.0), None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_disks(&disks, &[], Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Disk check failed: /: Disk use 80.000% is more than 70.000%, /: Inode use 95.000% is more than 90.000%".to_string(),
+        });
+
+        let mut monitor = DiskMonitor::new("test", None, Some(90.0), Some(90.0), Some(50.0), None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_disks(&disks[1..], &[], Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Warning {
+            message: "Disk check warning: /home: Disk use 60.000% is more than warning 50.000%".to_string(),
+        });
+
+        let mut monitor = DiskMonitor::new("test", None, Some(90.0), Some(90.0), None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        monitor.check_disks(&disks[1..], &[], Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+    }
+
+    #[test]
+    fn test_get_disk_monitor_job() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = DiskMonitor::new("test", None, Some(90.0), None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None, false);
+        let job = monitor.get_disk_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
+        assert!(job.is_ok());
+    }
+}
@@ -0,0 +1,566 @@
+use std::{collections::HashMap, net::IpAddr, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    error::{ResolveError, ResolveErrorKind},
+    proto::{op::ResponseCode, rr::{RData, RecordType}},
+    TokioAsyncResolver,
+};
+use log::{debug, error, info};
+use tokio_cron_scheduler::Job;
+
+use crate::{common::{configuration::{parse_nameserver, DatabaseStoreLevel}, ApplicationError, DnsRecordType, MonitorStatus, Status}, DbService, services::NotificationService};
+
+use super::common::check_response_time;
+use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
+
+/**
+ * DNS Monitor.
+ *
+ * This struct represents a DNS monitor. It resolves a name against a nameserver, or the system resolver, and
+ * optionally checks the answers and the resolution time.
+ *
+ */
+#[derive(Debug, Clone)]
+pub struct DnsMonitor {
+    /// The name of the monitor.
+    pub name: String,
+    /// The name to resolve.
+    pub dns_name: String,
+    /// The record type to resolve.
+    pub record_type: DnsRecordType,
+    /// The nameserver to ask. If None, then the system resolver is used.
+    pub nameserver: Option<String>,
+    /// Answers that must be among the answers returned.
+    pub expected: Option<Vec<String>>,
+    /// Max resolution time in milliseconds.
+    pub max_response_time: Option<u64>,
+    /// The status of the monitor.
+    pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+    /// The database service
+    database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
+    /// The database store level.
+    database_store_level: DatabaseStoreLevel,
+}
+
+impl DnsMonitor {
+
+    /**
+     * Create a new `DnsMonitor`.
+     *
+     * `name`: The name of the monitor.
+     * `dns_name`: The name to resolve.
+     * `record_type`: The record type to resolve.
+     * `nameserver`: The nameserver to ask. If None, then the system resolver is used.
+     * `expected`: Answers that must be among the answers returned.
+     * `max_response_time`: Max resolution time in milliseconds.
+     * `status`: The status of the monitor.
+     * `database_service`: The database service.
+     * `notification_service`: The notification service.
+     * `database_store_level`: The database store level.
+     *
+     * Returns: A new `DnsMonitor`.
+     *
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        dns_name: &str,
+        record_type: DnsRecordType,
+        nameserver: Option<String>,
+        expected: Option<Vec<String>>,
+        max_response_time: Option<u64>,
+        status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
+        database_store_level: &DatabaseStoreLevel,
+    ) -> DnsMonitor {
+
+        let status_lock = status.lock();
+        match status_lock {
+            Ok(mut lock) => {
+                lock.insert(name.to_string(), MonitorStatus::new(name.to_string(), Status::Unknown));
+            }
+            Err(err) => {
+                error!("Error creating dns monitor: {:?}", err);
+            }
+        }
+
+        DnsMonitor {
+            name: name.to_string(),
+            dns_name: dns_name.to_string(),
+            record_type,
+            nameserver,
+            expected,
+            max_response_time,
+            status: status.clone(),
+            database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
+            database_store_level: database_store_level.clone(),
+        }
+    }
+
+    /**
+     * Get the record type to resolve.
+     *
+     * Returns: The record type.
+     */
+    fn get_record_type(&self) -> RecordType {
+        match self.record_type {
+            DnsRecordType::A => RecordType::A,
+            DnsRecordType::Aaaa => RecordType::AAAA,
+            DnsRecordType::Cname => RecordType::CNAME,
+            DnsRecordType::Mx => RecordType::MX,
+            DnsRecordType::Txt => RecordType::TXT,
+        }
+    }
+
+    /**
+     * Create a resolver for the nameserver, or from the system configuration if no nameserver is given. A new
+     * resolver is created for each check, so answers are never cached between checks.
+     *
+     * Returns: The resolver or an error.
+     *
+     * throws: `ApplicationError`: If the nameserver is invalid or the system configuration can not be read.
+     */
+    fn get_resolver(&self) -> Result<TokioAsyncResolver, ApplicationError> {
+        let (config, mut opts) = match &self.nameserver {
+            Some(nameserver) => {
+                let address = parse_nameserver(nameserver).ok_or_else(|| {
+                    ApplicationError::new(format!("Invalid nameserver {nameserver}").as_str())
+                })?;
+                let name_servers = NameServerConfigGroup::from_ips_clear(&[address.ip()], address.port(), true);
+                (ResolverConfig::from_parts(None, vec![], name_servers), ResolverOpts::default())
+            }
+            None => hickory_resolver::system_conf::read_system_conf().map_err(|err| {
+                ApplicationError::new(format!("Error reading system resolver configuration: {err}").as_str())
+            })?,
+        };
+        opts.cache_size = 0;
+        Ok(TokioAsyncResolver::tokio(config, opts))
+    }
+
+    /**
+     * Resolve the name.
+     *
+     * Returns: The answers of the requested record type or an error message.
+     */
+    async fn resolve(&self) -> Result<Vec<String>, String> {
+        let resolver = self.get_resolver().map_err(|err| err.message)?;
+        let record_type = self.get_record_type();
+        let lookup = resolver.lookup(self.dns_name.as_str(), record_type).await
+            .map_err(|err| DnsMonitor::get_error_message(&err))?;
+        Ok(lookup.record_iter()
+            .filter(|record| record.record_type() == record_type)
+            .filter_map(|record| record.data().and_then(DnsMonitor::get_answer))
+            .collect())
+    }
+
+    /**
+     * Get the message of a failed lookup.
+     *
+     * `err`: The error of the lookup.
+     *
+     * Returns: The message.
+     */
+    fn get_error_message(err: &ResolveError) -> String {
+        match err.kind() {
+            ResolveErrorKind::NoRecordsFound { response_code, .. } if *response_code == ResponseCode::NXDomain => {
+                "name does not exist".to_string()
+            }
+            ResolveErrorKind::NoRecordsFound { response_code, .. } => {
+                format!("no records found, response code {response_code}")
+            }
+            _ => err.to_string(),
+        }
+    }
+
+    /**
+     * Convert the data of a record to an answer. Names are written without the trailing dot.
+     *
+     * `data`: The data of the record.
+     *
+     * Returns: The answer, or None for record types that are not monitored.
+     */
+    fn get_answer(data: &RData) -> Option<String> {
+        match data {
+            RData::A(address) => Some(address.to_string()),
+            RData::AAAA(address) => Some(address.to_string()),
+            RData::CNAME(name) => Some(name.to_string().trim_end_matches('.').to_string()),
+            RData::MX(mx) => Some(format!("{} {}", mx.preference(), mx.exchange().to_string().trim_end_matches('.'))),
+            RData::TXT(txt) => Some(txt.to_string()),
+            _ => None,
+        }
+    }
+
+    /**
+     * Normalize an answer for comparison. Addresses are compared as addresses, names without case and trailing dot,
+     * and text exactly.
+     *
+     * `answer`: The answer.
+     *
+     * Returns: The normalized answer.
+     */
+    fn normalize_answer(&self, answer: &str) -> String {
+        match self.record_type {
+            DnsRecordType::A | DnsRecordType::Aaaa => answer.trim().parse::<IpAddr>()
+                .map_or_else(|_| answer.to_string(), |address| address.to_string()),
+            DnsRecordType::Cname | DnsRecordType::Mx => answer.trim().trim_end_matches('.').to_lowercase(),
+            DnsRecordType::Txt => answer.to_string(),
+        }
+    }
+
+    /**
+     * Check the answers of a lookup.
+     *
+     * `answers`: The answers or the error message of the lookup.
+     * `duration`: The duration of the lookup.
+     *
+     * Returns: The status.
+     */
+    fn check_answers(&self, answers: Result<Vec<String>, String>, duration: Duration) -> Status {
+        let lookup = format!("DNS lookup of {} {}", self.dns_name, self.get_record_type());
+        let answers = match answers {
+            Ok(answers) if answers.is_empty() => {
+                return Status::Error { message: format!("{lookup} failed: no records found") };
+            }
+            Ok(answers) => answers,
+            Err(message) => {
+                return Status::Error { message: format!("{lookup} failed: {message}") };
+            }
+        };
+        if let Some(expected) = &self.expected {
+            let normalized: Vec<String> = answers.iter().map(|answer| self.normalize_answer(answer)).collect();
+            let missing: Vec<&str> = expected.iter()
+                .filter(|answer| !normalized.contains(&self.normalize_answer(answer)))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                return Status::Error {
+                    message: format!("{lookup} returned {}, expected {}", answers.join(", "), missing.join(", ")),
+                };
+            }
+        }
+        check_response_time(Status::Ok, duration, self.max_response_time)
+    }
+
+    /**
+     * Get dns monitor job.
+     *
+     * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
+     *
+     * Returns: The dns monitor job.
+     *
+     */
+    pub fn get_dns_monitor_job(
+        &mut self,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
+    ) -> Result<Job, ApplicationError> {
+        info!("Creating dns monitor: {}", &self.name);
+        let dns_monitor = self.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut dns_monitor = dns_monitor.clone();
+            Box::pin(async move {
+                let mut checked_monitor = dns_monitor.clone();
+                dns_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
+    }
+
+    /**
+     * Check the monitor.
+     */
+    pub async fn check(&mut self) {
+        debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
+        let answers = self.resolve().await;
+        let duration = start.elapsed();
+        let status = self.check_answers(answers, duration);
+        self.set_status(&status, duration).await;
+    }
+
+}
+
+/**
+ * Implement the `Monitor` trait for `DnsMonitor`.
+ */
+impl super::Monitor for DnsMonitor {
+    /**
+     * Get the name of the monitor.
+     *
+     * Returns: The name of the monitor.
+     */
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Get the status of the monitor.
+     *
+     * Returns: The status of the monitor.
+     */
+    fn get_status(&self) -> Arc<Mutex<HashMap<String, MonitorStatus>>> {
+        self.status.clone()
+    }
+
+    /**
+     * Get the database service.
+     *
+     * Returns: The database service.
+     */
+    fn get_database_service(&self) -> Arc<Option<DbService>> {
+        self.database_service.clone()
+    }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
+    /**
+     * Get the database store level.
+     *
+     * Returns: The database store level.
+     */
+    fn get_database_store_level(&self) -> DatabaseStoreLevel {
+        self.database_store_level.clone()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, net::{Ipv4Addr, Ipv6Addr}, sync::{Arc, Mutex}, time::Duration};
+
+    use hickory_resolver::proto::{
+        op::{Message, MessageType, OpCode, ResponseCode},
+        rr::{rdata::{A, AAAA, CNAME, MX, TXT}, Name, RData, Record, RecordType},
+    };
+    use tokio::net::UdpSocket;
+
+    use crate::common::{configuration::DatabaseStoreLevel, DnsRecordType, MonitorStatus, Status};
+
+    use super::{DnsMonitor, Monitor, MonitorSchedule, RunningChecks};
+
+    /**
+     * Build the answer of the stand-in server. Names under example.test have one record of each type, slow.test is
+     * answered late and everything else does not exist.
+     */
+    fn get_response(request: &Message) -> Option<Message> {
+        let query = request.queries().first()?;
+        let name = query.name().clone();
+        let mut response = Message::new();
+        response.set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(request.recursion_desired())
+            .set_recursion_available(true)
+            .add_query(query.clone());
+        let known = name == Name::from_ascii("example.test.").ok()? || name == Name::from_ascii("slow.test.").ok()?;
+        if !known {
+            response.set_response_code(ResponseCode::NXDomain);
+            return Some(response);
+        }
+        let data = match query.query_type() {
+            RecordType::A => RData::A(A(Ipv4Addr::new(192, 0, 2, 1))),
+            RecordType::AAAA => RData::AAAA(AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+            RecordType::CNAME => RData::CNAME(CNAME(Name::from_ascii("target.example.test.").ok()?)),
+            RecordType::MX => RData::MX(MX::new(10, Name::from_ascii("mail.example.test.").ok()?)),
+            RecordType::TXT => RData::TXT(TXT::new(vec!["v=spf1 -all".to_string()])),
+            _ => return Some(response),
+        };
+        response.add_answer(Record::from_rdata(name, 60, data));
+        Some(response)
+    }
+
+    /**
+     * Start a stand-in dns server on a random local port.
+     *
+     * Returns: The address of the server, to be used as nameserver.
+     */
+    async fn start_server() -> String {
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let address = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 512];
+            loop {
+                let Ok((length, peer)) = socket.recv_from(&mut buffer).await else { return };
+                let Ok(request) = Message::from_vec(&buffer[..length]) else { continue };
+                let Some(response) = get_response(&request) else { continue };
+                let slow = request.queries().first().is_some_and(|query| query.name().to_ascii() == "slow.test.");
+                let socket = socket.clone();
+                tokio::spawn(async move {
+                    if slow {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                    let _ = socket.send_to(&response.to_vec().unwrap(), peer).await;
+                });
+            }
+        });
+        address.to_string()
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - A, AAAA, CNAME, MX and TXT records are resolved and match the expected answers, written in another case or
+     *   notation than the answers.
+     */
+    #[tokio::test]
+    async fn test_check_record_types() {
+        let nameserver = start_server().await;
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let checks = [
+            (DnsRecordType::A, "192.0.2.1"),
+            (DnsRecordType::Aaaa, "2001:0db8::0001"),
+            (DnsRecordType::Cname, "Target.Example.Test."),
+            (DnsRecordType::Mx, "10 mail.example.test"),
+            (DnsRecordType::Txt, "v=spf1 -all"),
+        ];
+        for (record_type, expected) in checks {
+            let mut monitor = DnsMonitor::new("test", "example.test", record_type, Some(nameserver.clone()), Some(vec![expected.to_string()]), Some(5000), &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+            monitor.check().await;
+            assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok, "{record_type:?}");
+        }
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - The name resolves and no answers are expected.
+     */
+    #[tokio::test]
+    async fn test_check_without_expected() {
+        let nameserver = start_server().await;
+        let mut monitor = DnsMonitor::new(
+            "test",
+            "example.test",
+            DnsRecordType::A,
+            Some(nameserver),
+            None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, Status::Ok);
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - One of the expected answers is missing from the answers.
+     */
+    #[tokio::test]
+    async fn test_check_unexpected_answer() {
+        let nameserver = start_server().await;
+        let mut monitor = DnsMonitor::new(
+            "test",
+            "example.test",
+            DnsRecordType::Mx,
+            Some(nameserver),
+            Some(vec!["10 mail.example.test".to_string(), "20 backup.example.test".to_string()]),
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, Status::Error {
+            message: "DNS lookup of example.test MX returned 10 mail.example.test, expected 20 backup.example.test".to_string(),
+        });
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - The name does not exist.
+     */
+    #[tokio::test]
+    async fn test_check_missing_name() {
+        let nameserver = start_server().await;
+        let mut monitor = DnsMonitor::new(
+            "test",
+            "missing.test",
+            DnsRecordType::A,
+            Some(nameserver),
+            None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, Status::Error {
+            message: "DNS lookup of missing.test A failed: name does not exist".to_string(),
+        });
+    }
+
+    /**
+     * Test the check function.
+     *
+     * Test the following scenarios:
+     * - The answer is as expected, but arrives after the max response time.
+     */
+    #[tokio::test]
+    async fn test_check_slow_response() {
+        let nameserver = start_server().await;
+        let mut monitor = DnsMonitor::new(
+            "test",
+            "slow.test",
+            DnsRecordType::A,
+            Some(nameserver),
+            Some(vec!["192.0.2.1".to_string()]),
+            Some(10),
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &DatabaseStoreLevel::None,
+        );
+
+        monitor.check().await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert!(matches!(&status.get("test").unwrap().status, Status::Error { message } if message.contains("exceeded max response time 10 ms")));
+    }
+
+    #[test]
+    fn test_get_dns_monitor_job() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = DnsMonitor::new("test", "example.com", DnsRecordType::A, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let job = monitor.get_dns_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
+        assert!(job.is_ok());
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use log::info;
 use log::{debug, error};
@@ -13,10 +14,16 @@ use tokio_cron_scheduler::Job;
 
 use crate::common::configuration::DatabaseStoreLevel;
 use crate::common::ApplicationError;
+use crate::common::secrets;
 use crate::common::{MonitorStatus, Status};
 use crate::common::HttpMethod;
 use crate::services::monitors::Monitor;
-use crate::services::DbService;
+use super::common::check_response_time;
+use crate::services::{DbService, NotificationService};
+use super::{MonitorSchedule, RunningChecks};
+
+/// The timeout of each request if the monitor has no check timeout.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
 /**
  * HTTP Monitor.
@@ -28,9 +35,11 @@ use crate::services::DbService;
  * method: The HTTP method to use.
  * body: The body of the request.
  * headers: The headers of the request.
+ * max_response_time: The max response time in milliseconds.
+ * request_timeout: The timeout of each request.
  * status: The status of the monitor.
  */
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct HttpMonitor {
     /// The name of the monitor.
     pub name: String,
@@ -42,12 +51,18 @@ pub struct HttpMonitor {
     pub body: Option<String>,
     /// The headers of the request.
     pub headers: Option<HashMap<String, String>>,
+    /// The max response time in milliseconds.
+    pub max_response_time: Option<u64>,
+    /// The timeout of each request.
+    request_timeout: Duration,
     /// The HTTP client.
     client: reqwest::Client,
     /// The status of the monitor.
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel,         
 }
@@ -60,6 +75,7 @@ impl HttpMonitor {
      * `method`: The HTTP method to use.
      * `body`: The body of the request.
      * `headers`: The headers of the request.
+     * `max_response_time`: The max response time in milliseconds.
      * `name`: The name of the monitor.
      * `use_builtin_root_certs`: Use the built-in root certificates.
      * `accept_invalid_certs`: Accept invalid certificates.
@@ -69,6 +85,7 @@ impl HttpMonitor {
      * `identity_password`: The password for the identity.
      * `status`: The status of the monitor.
      * `database_service`: The database service.
+     * `notification_service`: The notification service.
      * 
      * Returns: A new HTTP monitor.
      *
@@ -79,6 +96,7 @@ impl HttpMonitor {
         method: HttpMethod,
         body: &Option<String>,
         headers: &Option<HashMap<String, String>>,
+        max_response_time: Option<u64>,
         name: &str,
         use_builtin_root_certs: bool,
         accept_invalid_certs: bool,
@@ -88,6 +106,7 @@ impl HttpMonitor {
         identity_password: Option<String>,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel,
     ) -> Result<HttpMonitor, ApplicationError> {
         debug!("Creating HTTP monitor: {}", &name);
@@ -156,13 +175,26 @@ impl HttpMonitor {
             method,
             body: body.clone(),
             headers: headers.clone(),
+            max_response_time,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
             status: status.clone(),
             client,
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
         })
     }
 
+    /**
+     * Set the timeout of each request from the timeout of the checks.
+     *
+     * `timeout`: The maximum duration of each check. If None, the default request timeout of 5 seconds is used.
+     *
+     */
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.request_timeout = timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+    }
+
     /**
      * This method converts a `HashMap` to a `HeaderMap`.
      *
@@ -228,7 +260,7 @@ impl HttpMonitor {
      * Returns a `HeaderMap`.
      *
      */
-    fn get_headers(
+    pub fn get_headers(
         headers: &Option<HashMap<String, String>>,
     ) -> Result<reqwest::header::HeaderMap, ApplicationError> {
         match headers {
@@ -241,16 +273,19 @@ impl HttpMonitor {
      * Check the response and set the status of the monitor.
      *
      * `response`: The response from the request.
+     * `duration`: The duration of the request.
      *
      */
     async fn check_response_and_set_status(
         &mut self,
         response: Result<reqwest::Response, reqwest::Error>,
+        duration: Duration,
     ) {
         match response {
             Ok(response) => {
                 if response.status().is_success() {
-                    self.set_status(&Status::Ok).await;
+                    let status = check_response_time(Status::Ok, duration, self.max_response_time);
+                    self.set_status(&status, duration).await;
                 } else {
                     info!("Monitor status error: {} - {:?}", &self.name, response);
                     self.set_status(&Status::Error {
@@ -259,13 +294,13 @@ impl HttpMonitor {
                             &self.url,
                             response.status()
                         ),
-                    }).await;
+                    }, duration).await;
                 }
             }
             Err(err) => {
                 self.set_status(&Status::Error {
                     message: format!("Error connecting to {} with error: {err}", &self.url),
-                }).await;
+                }, duration).await;
             }
         }
     }
@@ -314,6 +349,20 @@ impl HttpMonitor {
         Ok(identity)
     }
 
+    /**
+     * Read a root certificate file.
+     *
+     * `root_certificate`: The root certificate file path
+     *
+     * Returns the PEM data of the certificate.
+     *
+     */
+    pub fn read_root_certificate(root_certificate: &str) -> Result<Vec<u8>, ApplicationError> {
+        fs::read(root_certificate).map_err(|err| ApplicationError::new(&format!(
+            "Error reading root_certificate: {err}"
+        )))
+    }
+
     /**
      * Get root certificate.
      *
@@ -323,17 +372,7 @@ impl HttpMonitor {
      *
      */
     fn get_root_certificate(root_certificate: &str) -> Result<Certificate, ApplicationError> {
-        /*
-         * Read root certificate.
-         */
-        let data = match fs::read(root_certificate) {
-            Ok(data) => data,
-            Err(err) => {
-                return Err(ApplicationError::new(&format!(
-                    "Error reading root_certificate: {err}"
-                )));
-            }
-        };
+        let data = HttpMonitor::read_root_certificate(root_certificate)?;
         /*
          * Create root certificate.
          */
@@ -352,6 +391,7 @@ impl HttpMonitor {
      * Get an HTTP monitor job.
      *
      * `schedule`: The schedule.
+     * `running_checks`: The running checks. The job marks its check as running.
      * `name`: The name of the monitor.
      * `url`: The URL to monitor.
      * `method`: The HTTP method.
@@ -364,30 +404,28 @@ impl HttpMonitor {
      */
     pub fn get_http_monitor_job(
         &mut self,
-        schedule: &str,        
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating http monitor: {}", &self.name);
         let http_monitor = self.clone();
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
             let mut http_monitor = http_monitor.clone();
             Box::pin(async move {
-                let _ = http_monitor.check().await.map_err(|err| {
-                    error!("Error checking monitor: {:?}", err);
-                });
+                let mut checked_monitor = http_monitor.clone();
+                http_monitor.check_with_timeout(async move {
+                    let _ = checked_monitor.check().await.map_err(|err| {
+                        error!("Error checking monitor: {:?}", err);
+                    });
+                }, timeout).await;
             })
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            )),
-        }
+        })
     }
 
     /**
      * Check the monitor.
      */
-    async fn check(&mut self) -> Result<(), ApplicationError> {
+    pub async fn check(&mut self) -> Result<(), ApplicationError> {
         debug!("Checking monitor: {}", &self.name);
         /*
          * Set http method.
@@ -414,21 +452,40 @@ impl HttpMonitor {
         /*
          * Set timeout.
          */
-        let request_builder = request_builder.timeout(Duration::from_secs(5));
+        let request_builder = request_builder.timeout(self.request_timeout);
         /*
          * Send request.
          */
+        let start = Instant::now();
         let req_response = request_builder.send().await;
+        let duration = start.elapsed();
         /*
          * Check response and set status in the monitor.
          */
-        self.check_response_and_set_status(req_response).await;
+        self.check_response_and_set_status(req_response, duration).await;
         debug!("Monitor checked: {}", &self.name);
         Ok(())
     }    
 
 }
 
+/**
+ * The debug output only shows the header names, as the header values and the body may hold secrets.
+ */
+impl fmt::Debug for HttpMonitor {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header_names: Option<Vec<&String>> = self.headers.as_ref().map(|headers| headers.keys().collect());
+        formatter.debug_struct("HttpMonitor")
+            .field("name", &self.name)
+            .field("url", &secrets::redact(&self.url))
+            .field("method", &self.method)
+            .field("headers", &header_names)
+            .field("max_response_time", &self.max_response_time)
+            .field("request_timeout", &self.request_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
 /**
  * Implement the `Monitor` trait for `HttpMonitor`.
  */
@@ -460,6 +517,15 @@ impl super::Monitor for HttpMonitor {
         self.database_service.clone()
     }
 
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
     /**
      * Get the database store level.
      *
@@ -494,6 +560,7 @@ mod test {
             HttpMethod::Get,
             &None,
             &None,
+            None,
             "localhost",
             true,
             true,
@@ -503,6 +570,7 @@ mod test {
             Some("test".to_string()),
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None
         )
         .unwrap();
@@ -538,6 +606,7 @@ mod test {
             HttpMethod::Get,
             &None,
             &None,
+            None,
             "Google",
             true,
             true,
@@ -547,10 +616,11 @@ mod test {
             None,
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None
         )
         .unwrap();
-        monitor.set_status(&Status::Ok).await;
+        monitor.set_status(&Status::Ok, Duration::ZERO).await;
         assert_eq!(
             status.lock().unwrap().get("Google").unwrap().status,
             Status::Ok
@@ -566,6 +636,7 @@ mod test {
             HttpMethod::Get,
             &None,
             &None,
+            None,
             "Google",
             true,
             true,
@@ -575,9 +646,25 @@ mod test {
             None,
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None
         ).unwrap();
-        let job = monitor.get_http_monitor_job("0 0 * * * *");
+        let job = monitor.get_http_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }
+
+    /**
+     * Test that the request timeout follows the check timeout, and falls back to the default without one.
+     */
+    #[test]
+    fn test_set_request_timeout() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = HttpMonitor::new("http://localhost:65000", HttpMethod::Get, &None, &None, None, "localhost", true, false, false, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None).unwrap();
+        assert_eq!(monitor.request_timeout, DEFAULT_REQUEST_TIMEOUT);
+        monitor.set_request_timeout(Some(Duration::from_secs(30)));
+        assert_eq!(monitor.request_timeout, Duration::from_secs(30));
+        monitor.set_request_timeout(None);
+        assert_eq!(monitor.request_timeout, DEFAULT_REQUEST_TIMEOUT);
+    }
 }
@@ -1,12 +1,13 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}};
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
 
 use log::{debug, error, info};
 use monitoring_agent_lib::proc::ProcsLoadavg;
 use tokio_cron_scheduler::Job;
 
-use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService};
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService, services::NotificationService};
 
 use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
 
 #[derive(Debug, Clone)]
 pub struct LoadAvgMonitor {
@@ -18,10 +19,18 @@ pub struct LoadAvgMonitor {
     pub loadavg5min_max: Option<f32>,
     /// Max load average for 10 minutes.
     pub loadavg10min_max: Option<f32>,
+    /// Warning load average for 1 minute.
+    pub loadavg1min_warning: Option<f32>,
+    /// Warning load average for 5 minutes.
+    pub loadavg5min_warning: Option<f32>,
+    /// Warning load average for 10 minutes.
+    pub loadavg10min_warning: Option<f32>,
     /// The status of the monitor.
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel,
     /// The current load average.
@@ -37,8 +46,12 @@ impl LoadAvgMonitor {
      * `loadavg1min_max`: The max load average for 1 minute.
      * `loadavg5min_max`: The max load average for 5 minutes.
      * `loadavg10min_max`: The max load average for 10 minutes.
+     * `loadavg1min_warning`: The warning load average for 1 minute.
+     * `loadavg5min_warning`: The warning load average for 5 minutes.
+     * `loadavg10min_warning`: The warning load average for 10 minutes.
      * `status`: The status of the monitor.
      * `database_service`: The database service.
+     * `notification_service`: The notification service.
      * `database_store_level`: The database store level.
      * `store_current_loadavg`: Store the current load average.
      * 
@@ -52,8 +65,12 @@ impl LoadAvgMonitor {
         loadavg1min_max: Option<f32>,
         loadavg5min_max: Option<f32>,
         loadavg10min_max: Option<f32>,
+        loadavg1min_warning: Option<f32>,
+        loadavg5min_warning: Option<f32>,
+        loadavg10min_warning: Option<f32>,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel,
         store_current_loadavg: bool,
     ) -> LoadAvgMonitor {
@@ -73,8 +90,12 @@ impl LoadAvgMonitor {
             loadavg1min_max,
             loadavg5min_max,
             loadavg10min_max,
+            loadavg1min_warning,
+            loadavg5min_warning,
+            loadavg10min_warning,
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
             store_current_loadavg,
         }
@@ -84,23 +105,59 @@ impl LoadAvgMonitor {
      * Check the load average.
      * 
      * `loadavg`: The current load average.
+     * `duration`: The duration of the check.
      * 
      */
     #[allow(clippy::similar_names)]         
-    async fn check_loadavg(&mut self, loadavg: &ProcsLoadavg) {    
-        let status_1min = LoadAvgMonitor::check_loadavg_values(self.loadavg1min_max, loadavg.loadavg1min);
-        let status_5min = LoadAvgMonitor::check_loadavg_values(self.loadavg5min_max, loadavg.loadavg5min);
-        let status_10min = LoadAvgMonitor::check_loadavg_values(self.loadavg10min_max, loadavg.loadavg10min);
-        
-        if status_1min != Status::Ok || status_5min != Status::Ok || status_10min != Status::Ok {
+    async fn check_loadavg(&mut self, loadavg: &ProcsLoadavg, duration: Duration) {    
+        let status_1min = LoadAvgMonitor::check_loadavg_thresholds(self.loadavg1min_max, self.loadavg1min_warning, loadavg.loadavg1min);
+        let status_5min = LoadAvgMonitor::check_loadavg_thresholds(self.loadavg5min_max, self.loadavg5min_warning, loadavg.loadavg5min);
+        let status_10min = LoadAvgMonitor::check_loadavg_thresholds(self.loadavg10min_max, self.loadavg10min_warning, loadavg.loadavg10min);
+        let statuses = [&status_1min, &status_5min, &status_10min];
+
+        if statuses.iter().any(|status| matches!(status, Status::Error { .. })) {
             self.set_status(&Status::Error {
                 message: format!(
                     "Load average check failed: 1min: {status_1min:?}, 5min: {status_5min:?}, 10min: {status_10min:?}"
                 ),
-            }).await;
+            }, duration).await;
+        } else if statuses.iter().any(|status| matches!(status, Status::Warning { .. })) {
+            self.set_status(&Status::Warning {
+                message: format!(
+                    "Load average check warning: 1min: {status_1min:?}, 5min: {status_5min:?}, 10min: {status_10min:?}"
+                ),
+            }, duration).await;
         } else {
-            self.set_status(&Status::Ok).await;
+            self.set_status(&Status::Ok, duration).await;
+        }
+    }
+
+    /**
+     * Check the load average against both the max and the warning threshold.
+     * 
+     * `max`: The max load average.
+     * `warning`: The warning load average.
+     * `current`: The current load average.
+     * 
+     * Returns: Error if above max, warning if above the warning threshold, otherwise ok.
+     * 
+     */
+    fn check_loadavg_thresholds(max: Option<f32>, warning: Option<f32>, current: Option<f32>) -> Status {
+        let status = LoadAvgMonitor::check_loadavg_values(max, current);
+        if status != Status::Ok {
+            return status;
+        }
+        let Some(current) = current else { return Status::Ok };
+        let Some(warning) = warning else { return Status::Ok };
+
+        if current > warning {
+            return Status::Warning {
+                message: format!(
+                    "Load average {current} is greater than warning load average {warning}"
+                ),
+            };
         }
+        Status::Ok
     }
 
     /**
@@ -155,6 +212,7 @@ impl LoadAvgMonitor {
      * Get a loadavg monitor job.
      * 
      * `schedule`: The schedule.
+     * `running_checks`: The running checks. The job marks its check as running.
      * `name`: The name of the monitor.
      * `threshold_1min`: The threshold for the 1 minute load average.
      * `threshold_5min`: The threshold for the 5 minute load average.
@@ -172,34 +230,32 @@ impl LoadAvgMonitor {
     #[allow(clippy::similar_names)]    
     pub fn get_loadavg_monitor_job(
         &mut self,
-        schedule: &str,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating Loadavg monitor: {}", &self.name);
         let loadavg_monitor = self.clone();       
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {                
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
             let mut loadavg_monitor = loadavg_monitor.clone();
             Box::pin(async move {
-                loadavg_monitor.check().await;
-            })        
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            )), 
-        }
+                let mut checked_monitor = loadavg_monitor.clone();
+                loadavg_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
     }    
 
     /**
      * Check the monitor.
      */
-    async fn check(&mut self) {
+    pub async fn check(&mut self) {
         debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
         let loadavg = ProcsLoadavg::get_loadavg();
+        let duration = start.elapsed();
         match loadavg {
             Ok(loadavg) => {
                 self.check_store_current_loadavg(&loadavg).await;
-                self.check_loadavg(&loadavg).await;
+                self.check_loadavg(&loadavg, duration).await;
             }
             Err(err) => {
                 error!("Error getting load average: {:?}", err);
@@ -240,6 +296,15 @@ impl super::Monitor for LoadAvgMonitor {
         self.database_service.clone()
     }
 
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
     /**
      * Get the database store level.
      *
@@ -253,10 +318,10 @@ impl super::Monitor for LoadAvgMonitor {
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashMap, sync::{Arc, Mutex}};
+    use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
     use crate::{common::{configuration::DatabaseStoreLevel, MonitorStatus}, services::monitors::LoadAvgMonitor};
 
-    use super::Monitor;
+    use super::{Monitor, MonitorSchedule, RunningChecks};
 
     /**
      * Test the check_loadavg_values function.'
@@ -288,6 +353,72 @@ mod test {
         assert_eq!(status, super::Status::Ok);
     }
 
+    /**
+     * Test the check_loadavg_thresholds function.
+     * 
+     * Test the following scenarios:
+     * - Load average is higher than max load average.
+     * - Load average is higher than warning load average.
+     * - Load average is equal to warning load average.
+     * - Warning load average is None.
+     */
+    #[test]
+    fn test_check_loadavg_thresholds() {
+        let status = LoadAvgMonitor::check_loadavg_thresholds(Some(2.0), Some(1.0), Some(3.0));
+        assert_eq!(status, super::Status::Error {
+            message: "Load average 3 is greater than max load average 2".to_string()
+        });
+
+        let status = LoadAvgMonitor::check_loadavg_thresholds(Some(2.0), Some(1.0), Some(1.5));
+        assert_eq!(status, super::Status::Warning {
+            message: "Load average 1.5 is greater than warning load average 1".to_string()
+        });
+
+        let status = LoadAvgMonitor::check_loadavg_thresholds(Some(2.0), Some(1.0), Some(1.0));
+        assert_eq!(status, super::Status::Ok);
+
+        let status = LoadAvgMonitor::check_loadavg_thresholds(Some(2.0), None, Some(1.5));
+        assert_eq!(status, super::Status::Ok);
+    }
+
+    /**
+     * Test the check_loadavg function.
+     * 
+     * Test the following scenarios:
+     * - Load average is higher than the warning on 5 minutes, but lower than max on all.
+     */
+    #[tokio::test]
+    async fn test_check_loadavg_5min_warning() {
+        let mut monitor = super::LoadAvgMonitor::new(
+            "test",
+            Some(1.0),
+            Some(2.0),
+            Some(3.0),
+            None,
+            Some(1.5),
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &super::DatabaseStoreLevel::None,
+            false,
+        );
+
+        let loadavg = monitoring_agent_lib::proc::ProcsLoadavg {
+            loadavg1min: Some(1.0),
+            loadavg5min: Some(1.9),
+            loadavg10min: Some(3.0),
+            current_running_processes: Some(1),
+            total_number_of_processes: Some(10)
+        };
+
+        monitor.check_loadavg(&loadavg, Duration::ZERO).await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, super::Status::Warning { message: "Load average check warning: 1min: Ok, 5min: Warning { message: \"Load average 1.9 is greater than warning load average 1.5\" }, 10min: Ok".to_string() } );
+    }
+
     /**
      * Test the check_loadavg function.
      * 
@@ -302,8 +433,12 @@ mod test {
             Some(1.0),
             Some(2.0),
             Some(3.0),
+            None,
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -316,7 +451,7 @@ mod test {
             total_number_of_processes: Some(10)
         };
 
-        monitor.check_loadavg(&loadavg).await;
+        monitor.check_loadavg(&loadavg, Duration::ZERO).await;
 
         let status = monitor.get_status();
         let status = status.lock().unwrap();
@@ -337,8 +472,12 @@ mod test {
             Some(1.0),
             Some(2.0),
             Some(3.0),
+            None,
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -351,7 +490,7 @@ mod test {
             total_number_of_processes: Some(10)
         };
 
-        monitor.check_loadavg(&loadavg).await;
+        monitor.check_loadavg(&loadavg, Duration::ZERO).await;
 
         let status = monitor.get_status();
         let status = status.lock().unwrap();
@@ -372,8 +511,12 @@ mod test {
             Some(1.0),
             Some(2.0),
             Some(3.0),
+            None,
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -386,7 +529,7 @@ mod test {
             total_number_of_processes: Some(10)
         };
 
-        monitor.check_loadavg(&loadavg).await;
+        monitor.check_loadavg(&loadavg, Duration::ZERO).await;
 
         let status = monitor.get_status();
         let status = status.lock().unwrap();
@@ -407,8 +550,12 @@ mod test {
             Some(1.0),
             Some(2.0),
             Some(3.0),
+            None,
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -421,7 +568,7 @@ mod test {
             total_number_of_processes: Some(10)
         };
 
-        monitor.check_loadavg(&loadavg).await;
+        monitor.check_loadavg(&loadavg, Duration::ZERO).await;
 
         let status = monitor.get_status();
         let status = status.lock().unwrap();
@@ -437,12 +584,16 @@ mod test {
             Some(1.0),
             Some(2.0),
             Some(3.0),
+            None,
+            None,
+            None,
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None,
             false,    
         );
-        let job = monitor.get_loadavg_monitor_job("0 0 * * * *");
+        let job = monitor.get_loadavg_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }   
 }
\ No newline at end of file
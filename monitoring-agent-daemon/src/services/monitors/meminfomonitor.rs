@@ -1,12 +1,13 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}};
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
 
 use log::{debug, error, info};
 use monitoring_agent_lib::proc::ProcsMeminfo;
 use tokio_cron_scheduler::Job;
 
-use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService};
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, DbService, services::NotificationService};
 
 use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
 
 #[derive(Debug, Clone)]
 pub struct MeminfoMonitor {
@@ -16,10 +17,16 @@ pub struct MeminfoMonitor {
     pub max_percentage_mem: Option<f64>,
     /// Minimum free percentage swap memory.
     pub max_percentage_swap: Option<f64>,
+    /// Warning percentage memory used.
+    pub warning_percentage_mem: Option<f64>,
+    /// Warning percentage swap memory used.
+    pub warning_percentage_swap: Option<f64>,
     /// The status of the monitor.
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,    
     /// The database service
     database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel,
     /// The current load average.
@@ -34,8 +41,11 @@ impl MeminfoMonitor {
      * `name`: The name of the monitor.
      * `max_percentage_mem`: The maximum percentage memory.
      * `max_percentage_swap`: The maximum percentage swap.
+     * `warning_percentage_mem`: The warning percentage memory.
+     * `warning_percentage_swap`: The warning percentage swap.
      * `status`: The status of the monitor.
      * `database_service`: The database service.
+     * `notification_service`: The notification service.
      * `database_store_level`: The database store level.
      * `store_current_meminfo`: Store the current load average.
      * 
@@ -48,8 +58,11 @@ impl MeminfoMonitor {
         name: &str,
         max_percentage_mem: Option<f64>,
         max_percentage_swap: Option<f64>,
+        warning_percentage_mem: Option<f64>,
+        warning_percentage_swap: Option<f64>,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel,
         store_current_meminfo: bool,
     ) -> MeminfoMonitor {
@@ -68,8 +81,11 @@ impl MeminfoMonitor {
             name: name.to_string(),
             max_percentage_mem,
             max_percentage_swap,
+            warning_percentage_mem,
+            warning_percentage_swap,
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
             store_current_meminfo,
         }
@@ -79,25 +95,61 @@ impl MeminfoMonitor {
      * Check the memory use.
      * 
      * `meminfo`: The memory use.
+     * `duration`: The duration of the check.
      * 
      */
     #[allow(clippy::similar_names)]         
-    async fn check_meminfo(&mut self, meminfo: &ProcsMeminfo) {    
+    async fn check_meminfo(&mut self, meminfo: &ProcsMeminfo, duration: Duration) {    
         let percentage_mem_used = ProcsMeminfo::get_percent_used(meminfo.memfree, meminfo.memtotal);
         let percentage_swap_used = ProcsMeminfo::get_percent_used(meminfo.swapfree, meminfo.swaptotal);
 
-        let free_percentage_mem_status = MeminfoMonitor::check_meminfo_values(self.max_percentage_mem, percentage_mem_used);
-        let free_percentage_swap_status = MeminfoMonitor::check_meminfo_values(self.max_percentage_swap, percentage_swap_used);
-        
-        if free_percentage_mem_status != Status::Ok || free_percentage_swap_status != Status::Ok{
+        let free_percentage_mem_status = MeminfoMonitor::check_meminfo_thresholds(self.max_percentage_mem, self.warning_percentage_mem, percentage_mem_used);
+        let free_percentage_swap_status = MeminfoMonitor::check_meminfo_thresholds(self.max_percentage_swap, self.warning_percentage_swap, percentage_swap_used);
+        let statuses = [&free_percentage_mem_status, &free_percentage_swap_status];
+
+        if statuses.iter().any(|status| matches!(status, Status::Error { .. })) {
             self.set_status(&Status::Error {
                 message: format!(
                     "Meminfo check failed: mem: {free_percentage_mem_status:?}, swap: {free_percentage_swap_status:?}"
                 ),
-            }).await;
+            }, duration).await;
+        } else if statuses.iter().any(|status| matches!(status, Status::Warning { .. })) {
+            self.set_status(&Status::Warning {
+                message: format!(
+                    "Meminfo check warning: mem: {free_percentage_mem_status:?}, swap: {free_percentage_swap_status:?}"
+                ),
+            }, duration).await;
         } else {
-            self.set_status(&Status::Ok).await;
+            self.set_status(&Status::Ok, duration).await;
+        }
+    }
+
+    /**
+     * Check the memory use against both the max and the warning threshold.
+     * 
+     * `max`: The max percentage used.
+     * `warning`: The warning percentage used.
+     * `current`: The current percentage used.
+     * 
+     * Returns: Error if above max, warning if above the warning threshold, otherwise ok.
+     * 
+     */
+    fn check_meminfo_thresholds(max: Option<f64>, warning: Option<f64>, current: Option<f64>) -> Status {
+        let status = MeminfoMonitor::check_meminfo_values(max, current);
+        if status != Status::Ok {
+            return status;
+        }
+        let Some(current) = current else { return Status::Ok };
+        let Some(warning) = warning else { return Status::Ok };
+
+        if current > warning {
+            return Status::Warning {
+                message: format!(
+                    "Memory use {current:0.3}% is more than warning {warning:0.3}%"
+                ),
+            };
         }
+        Status::Ok
     }
 
     /**
@@ -142,16 +194,13 @@ impl MeminfoMonitor {
      * `meminfo`: The current load average.
      */
     async fn store_current_meminfo(&self, meminfo: &ProcsMeminfo) {
-        match self.database_service.as_ref() {            
-            Some(database_service) => {
-                match database_service.store_meminfo(meminfo).await {
-                    Ok(()) => {}
-                    Err(err) => {
-                        error!("Error storing memory use: {:?}", err);
-                    }
+        if let Some(database_service) = self.database_service.as_ref() {
+            match database_service.store_meminfo(meminfo).await {
+                Ok(()) => {}
+                Err(err) => {
+                    error!("Error storing memory use: {:?}", err);
                 }
             }
-            None => {}
         }        
     }
 
@@ -159,6 +208,7 @@ impl MeminfoMonitor {
      * Get meminfo monitor job.
      * 
      * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
      * 
      * Returns: The meminfo monitor job.
      * 
@@ -167,34 +217,32 @@ impl MeminfoMonitor {
     #[allow(clippy::similar_names)]    
     pub fn get_meminfo_monitor_job(
         &mut self,
-        schedule: &str,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating meminfo monitor: {}", &self.name);
         let meminfo_monitor = self.clone();       
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {                
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
             let mut meminfo_monitor = meminfo_monitor.clone();
             Box::pin(async move {
-                meminfo_monitor.check().await;
-            })  
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            )), 
-        }
+                let mut checked_monitor = meminfo_monitor.clone();
+                meminfo_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
     }    
 
     /**
      * Check the monitor.
      */
-    async fn check(&mut self) {
+    pub async fn check(&mut self) {
         debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
         let meminfo = ProcsMeminfo::get_meminfo();
+        let duration = start.elapsed();
         match meminfo {
             Ok(meminfo) => {
                 self.check_store_current_meminfo(&meminfo).await;
-                self.check_meminfo(&meminfo).await;
+                self.check_meminfo(&meminfo, duration).await;
             }
             Err(err) => {
                 error!("Error getting meminfo: {:?}", err);
@@ -235,6 +283,15 @@ impl super::Monitor for MeminfoMonitor {
         self.database_service.clone()
     }
 
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
     /**
      * Get the database store level.
      *
@@ -248,11 +305,11 @@ impl super::Monitor for MeminfoMonitor {
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashMap, sync::{Arc, Mutex}};
+    use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
 
     use crate::{common::MonitorStatus, services::monitors::MeminfoMonitor};
 
-    use super::Monitor;
+    use super::{Monitor, MonitorSchedule, RunningChecks};
 
     #[tokio::test]
     async fn test_check() {
@@ -260,8 +317,11 @@ mod test {
             "test",
             Some(100.0),
             Some(100.0),
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -284,8 +344,11 @@ mod test {
             "test",
             Some(80.0),
             Some(80.0),
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -298,7 +361,7 @@ mod test {
             swapfree: Some(5000),
         };
 
-        monitor.check_meminfo(&meminfo).await;
+        monitor.check_meminfo(&meminfo, Duration::ZERO).await;
 
         let status = monitor.get_status();
         let status = status.lock().unwrap();
@@ -319,8 +382,11 @@ mod test {
             "test",
             Some(70.0),
             Some(15.0),
+            None,
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
@@ -333,13 +399,49 @@ mod test {
             swapfree: Some(5000),
         };
 
-        monitor.check_meminfo(&meminfo).await;
+        monitor.check_meminfo(&meminfo, Duration::ZERO).await;
 
         let status = monitor.get_status();
         let status = status.lock().unwrap();
         assert_eq!(status.get("test").unwrap().status, super::Status::Error { message: "Meminfo check failed: mem: Error { message: \"Memory use 75.000% is more than 70.000%\" }, swap: Error { message: \"Memory use 50.000% is more than 15.000%\" }".to_string() });
     }
 
+    /**
+     * Test the check_max_meminfo function.
+     * 
+     * Test the following scenarios:
+     * - Memory is above the warning threshold, but below the max.
+     */
+    #[tokio::test]
+    async fn test_check_max_meminfo_warning() {
+        let mut monitor = super::MeminfoMonitor::new(
+            "test",
+            Some(80.0),
+            Some(80.0),
+            Some(70.0),
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+            &Arc::new(None),
+            &Arc::new(None),
+            &super::DatabaseStoreLevel::None,
+            false,
+        );
+
+        let meminfo = monitoring_agent_lib::proc::ProcsMeminfo {
+            memtotal: Some(20000),
+            memfree: Some(5000),
+            memavailable: Some(16000),
+            swaptotal: Some(10000),
+            swapfree: Some(5000),
+        };
+
+        monitor.check_meminfo(&meminfo, Duration::ZERO).await;
+
+        let status = monitor.get_status();
+        let status = status.lock().unwrap();
+        assert_eq!(status.get("test").unwrap().status, super::Status::Warning { message: "Meminfo check warning: mem: Warning { message: \"Memory use 75.000% is more than warning 70.000%\" }, swap: Ok".to_string() });
+    }
+
     #[test]
     fn test_get_meminfo_monitor_job() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
@@ -348,12 +450,15 @@ mod test {
             "test",
             Some(100.0),
             Some(100.0),
+            None,
+            None,
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &super::DatabaseStoreLevel::None,
             false,
         );
-        let job = monitor.get_meminfo_monitor_job("0 0 * * * *");
+        let job = monitor.get_meminfo_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }  
 }
\ No newline at end of file
@@ -7,8 +7,16 @@
  * `tcpmonitor`: Monitor that checks the status of a TCP service. 
  * `loadavgmonitor`: Monitor that checks the load average of the system.
  * `meminfomonitor`: Monitor that checks the memory information of the system.
+ * `diskmonitor`: Monitor that checks the disk space and inode usage of the filesystems.
+ * `cpumonitor`: Monitor that checks the cpu usage of the system.
+ * `certificatemonitor`: Monitor that checks the expiry of TLS certificates.
+ * `dnsmonitor`: Monitor that checks the resolution of a name.
+ * `processmonitor`: Monitor that checks the processes matching a name or command line.
  * `systemctlmonitor`: Monitor that checks the status of a systemd service.
  * `databasemonitor`: Monitor that checks the status of a database service.
+ * `scheduledmonitor`: Monitor added to the scheduler, used to run checks on demand.
+ * `runningchecks`: Counts the running checks.
+ * `monitorschedule`: The cron or interval schedule of a monitor, and the jobs running its checks.
  */
 mod common;
 mod commandmonitor;
@@ -16,8 +24,16 @@ mod httpmonitor;
 mod tcpmonitor;
 mod loadavgmonitor;
 mod meminfomonitor;
+mod diskmonitor;
+mod cpumonitor;
+mod certificatemonitor;
+mod dnsmonitor;
+mod processmonitor;
 mod systemctlmonitor;
 mod databasemonitor;
+mod scheduledmonitor;
+mod runningchecks;
+mod monitorschedule;
 
 pub use common::Monitor;
 pub use commandmonitor::CommandMonitor;
@@ -25,5 +41,13 @@ pub use httpmonitor::HttpMonitor;
 pub use tcpmonitor::TcpMonitor;
 pub use loadavgmonitor::LoadAvgMonitor;
 pub use meminfomonitor::MeminfoMonitor;
+pub use diskmonitor::DiskMonitor;
+pub use cpumonitor::CpuMonitor;
+pub use certificatemonitor::CertificateMonitor;
+pub use dnsmonitor::DnsMonitor;
+pub use processmonitor::ProcessMonitor;
 pub use systemctlmonitor::SystemctlMonitor;
-pub use databasemonitor::DatabaseMonitor;
\ No newline at end of file
+pub use databasemonitor::DatabaseMonitor;
+pub use scheduledmonitor::ScheduledMonitor;
+pub use runningchecks::RunningChecks;
+pub use monitorschedule::{CheckInProgress, MonitorSchedule};
@@ -0,0 +1,435 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{debug, error, warn};
+use tokio_cron_scheduler::Job;
+
+use crate::common::configuration::parse_duration;
+use crate::common::{ApplicationError, MonitorStatus, Status};
+
+use super::RunningChecks;
+
+/**
+ * Monitor schedule.
+ *
+ * When the checks of a monitor run. Either from a cron expression or at a fixed interval, with each run optionally
+ * delayed by a random jitter so monitors on many hosts do not check shared backends at the same second. A run is
+ * skipped if the previous run of the monitor is still in progress, and each check can be given a timeout.
+ *
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorSchedule {
+    /// The cron expression or interval.
+    timing: Timing,
+    /// The maximum random delay of each run.
+    jitter: Option<Duration>,
+    /// The maximum duration of each check.
+    timeout: Option<Duration>,
+}
+
+/**
+ * Check in progress.
+ *
+ * Marks the check of a monitor as in progress in the status of the monitor until it is dropped. The mark is shared
+ * by the scheduled job and the checks run on demand, so only one check of a monitor runs at a time.
+ */
+pub struct CheckInProgress {
+    /// The name of the monitor.
+    name: String,
+    /// The status of the monitors.
+    status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+}
+
+impl CheckInProgress {
+    /**
+     * Mark the check of a monitor as in progress.
+     *
+     * `name`: The name of the monitor.
+     * `status`: The status of the monitors.
+     *
+     * Returns: The mark, or None if a check of the monitor is already in progress.
+     */
+    pub fn start(name: &str, status: &Arc<Mutex<HashMap<String, MonitorStatus>>>) -> Option<CheckInProgress> {
+        match status.lock() {
+            Ok(mut status_lock) => {
+                if let Some(monitor_status) = status_lock.get_mut(name) {
+                    if monitor_status.check_in_progress {
+                        return None;
+                    }
+                    monitor_status.check_in_progress = true;
+                }
+            }
+            Err(err) => {
+                error!("Error updating monitor status: {:?}", err);
+            }
+        }
+        Some(CheckInProgress { name: name.to_string(), status: status.clone() })
+    }
+}
+
+impl Drop for CheckInProgress {
+    fn drop(&mut self) {
+        if let Ok(mut status_lock) = self.status.lock() {
+            if let Some(monitor_status) = status_lock.get_mut(&self.name) {
+                monitor_status.check_in_progress = false;
+            }
+        }
+    }
+}
+
+/**
+ * The timing of a monitor schedule.
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Timing {
+    /// Run at the times of a cron expression.
+    Cron(String),
+    /// Run at a fixed interval.
+    Interval(Duration),
+}
+
+impl MonitorSchedule {
+
+    /**
+     * Create a new monitor schedule.
+     *
+     * `schedule`: A cron expression, e.g. "0 * * * * *", or an interval, e.g. "30s" or "5m".
+     * `jitter`: The maximum random delay of each run, e.g. "10s".
+     * `timeout`: The maximum duration of each check, e.g. "10s".
+     *
+     * Returns: The monitor schedule.
+     *
+     * throws: `ApplicationError`: If the jitter or timeout is not a valid duration.
+     */
+    pub fn new(schedule: &str, jitter: Option<&str>, timeout: Option<&str>) -> Result<MonitorSchedule, ApplicationError> {
+        let timing = match parse_duration(schedule) {
+            Some(interval) => Timing::Interval(interval),
+            None => Timing::Cron(schedule.to_string()),
+        };
+        let jitter = match jitter {
+            Some(jitter) => Some(parse_duration(jitter).ok_or_else(|| ApplicationError::new(
+                format!("Invalid jitter: {jitter}").as_str(),
+            ))?),
+            None => None,
+        };
+        let timeout = match timeout {
+            Some(timeout) => Some(parse_duration(timeout).ok_or_else(|| ApplicationError::new(
+                format!("Invalid timeout: {timeout}").as_str(),
+            ))?),
+            None => None,
+        };
+        Ok(MonitorSchedule { timing, jitter, timeout })
+    }
+
+    /**
+     * Get the maximum duration of each check.
+     *
+     * Returns: The timeout, or None if the checks run until they finish.
+     */
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /**
+     * Get a job running a check on the schedule. The check is marked as running while it runs. A run is skipped and
+     * counted in the status of the monitor if the previous run is still in progress.
+     *
+     * `name`: The name of the monitor.
+     * `status`: The status of the monitors.
+     * `running_checks`: The running checks.
+     * `check`: Creates the check to run, given the timeout of the check.
+     *
+     * Returns: The job.
+     *
+     * throws: `ApplicationError`: If the cron expression is invalid.
+     */
+    pub fn get_job<F>(
+        &self,
+        name: &str,
+        status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        running_checks: &RunningChecks,
+        check: F,
+    ) -> Result<Job, ApplicationError>
+    where
+        F: Fn(Option<Duration>) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync + 'static,
+    {
+        let jitter = self.jitter;
+        let timeout = self.timeout;
+        let name = name.to_string();
+        let status = status.clone();
+        let running_checks = running_checks.clone();
+        let run = move |_uuid, _locked| -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            let check = check(timeout);
+            let name = name.clone();
+            let status = status.clone();
+            let running_checks = running_checks.clone();
+            let delay = jitter.map(MonitorSchedule::get_random_delay);
+            Box::pin(MonitorSchedule::run(name, status, running_checks, delay, check))
+        };
+        let job_result = match &self.timing {
+            Timing::Cron(schedule) => Job::new_async(schedule.as_str(), run),
+            Timing::Interval(interval) => Job::new_repeated_async(*interval, run),
+        };
+        job_result.map_err(|err| ApplicationError::new(
+            format!("Could not create job: {err}").as_str(),
+        ))
+    }
+
+    /**
+     * Run a check. The check is marked as in progress and running before the delay, so a delayed run is seen by the
+     * overlap guard and by the shutdown. After the delay the check is not run if the checks are stopped or the
+     * monitor is paused or removed.
+     *
+     * `name`: The name of the monitor.
+     * `status`: The status of the monitors.
+     * `running_checks`: The running checks.
+     * `delay`: The random delay before the check.
+     * `check`: The check to run.
+     */
+    async fn run(
+        name: String,
+        status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        running_checks: RunningChecks,
+        delay: Option<Duration>,
+        check: Pin<Box<dyn Future<Output = ()> + Send>>,
+    ) {
+        let Some(_check_in_progress) = CheckInProgress::start(&name, &status) else {
+            MonitorSchedule::skip_check(&name, &status);
+            return;
+        };
+        let _running_check = running_checks.start();
+        if let Some(delay) = delay {
+            debug!("Delaying check by {} ms", delay.as_millis());
+            if !running_checks.sleep(delay).await || !MonitorSchedule::is_active(&name, &status) {
+                debug!("Dropping delayed check of monitor {name}");
+                return;
+            }
+        }
+        check.await;
+    }
+
+    /**
+     * Check if a monitor is active, i.e. not paused or removed.
+     *
+     * `name`: The name of the monitor.
+     * `status`: The status of the monitors.
+     *
+     * Returns: True if the monitor is active.
+     */
+    fn is_active(name: &str, status: &Arc<Mutex<HashMap<String, MonitorStatus>>>) -> bool {
+        match status.lock() {
+            Ok(status_lock) => status_lock.get(name).is_some_and(|monitor_status| monitor_status.status != Status::Paused),
+            Err(err) => {
+                error!("Error getting monitor status: {:?}", err);
+                false
+            }
+        }
+    }
+
+    /**
+     * Skip a run of a monitor, because the previous run is still in progress.
+     *
+     * `name`: The name of the monitor.
+     * `status`: The status of the monitors.
+     */
+    fn skip_check(name: &str, status: &Arc<Mutex<HashMap<String, MonitorStatus>>>) {
+        warn!("Skipping check of monitor {name}, the previous check is still in progress");
+        match status.lock() {
+            Ok(mut monitor_lock) => {
+                if let Some(monitor_status) = monitor_lock.get_mut(name) {
+                    monitor_status.skipped_checks += 1;
+                }
+            }
+            Err(err) => {
+                error!("Error updating monitor status: {:?}", err);
+            }
+        }
+    }
+
+    /**
+     * Get a random delay.
+     *
+     * `jitter`: The maximum delay.
+     *
+     * Returns: A delay between zero and the jitter, in whole milliseconds.
+     */
+    fn get_random_delay(jitter: Duration) -> Duration {
+        /*
+         * Each RandomState is seeded with new random keys, which is random enough for spreading checks.
+         */
+        let random = RandomState::new().build_hasher().finish();
+        let max_millis = u64::try_from(jitter.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(random % max_millis.saturating_add(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio_cron_scheduler::JobScheduler;
+
+    use super::*;
+
+    /**
+     * Get a status map with a single monitor.
+     */
+    fn get_status(name: &str) -> Arc<Mutex<HashMap<String, MonitorStatus>>> {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        status.lock().unwrap().insert(name.to_string(), MonitorStatus::new(name.to_string(), Status::Unknown));
+        status
+    }
+
+    #[test]
+    fn test_new() {
+        let schedule = MonitorSchedule::new("0 * * * * *", None, None).unwrap();
+        assert_eq!(schedule.timing, Timing::Cron("0 * * * * *".to_string()));
+        assert_eq!(schedule.jitter, None);
+        assert_eq!(schedule.timeout, None);
+        let schedule = MonitorSchedule::new("5m", Some("10s"), Some("1m")).unwrap();
+        assert_eq!(schedule.timing, Timing::Interval(Duration::from_secs(300)));
+        assert_eq!(schedule.jitter, Some(Duration::from_secs(10)));
+        assert_eq!(schedule.timeout, Some(Duration::from_secs(60)));
+        assert_eq!(MonitorSchedule::new("30s", Some("soon"), None).unwrap_err().get_message(), "Invalid jitter: soon");
+        assert_eq!(MonitorSchedule::new("30s", None, Some("0s")).unwrap_err().get_message(), "Invalid timeout: 0s");
+    }
+
+    #[test]
+    fn test_get_job() {
+        let running_checks = RunningChecks::new();
+        let status = get_status("test");
+        let cron_job = MonitorSchedule::new("0 * * * * *", None, None).unwrap().get_job("test", &status, &running_checks, |_| Box::pin(async {}));
+        assert!(cron_job.is_ok());
+        let interval_job = MonitorSchedule::new("30s", Some("5s"), Some("10s")).unwrap().get_job("test", &status, &running_checks, |_| Box::pin(async {}));
+        assert!(interval_job.is_ok());
+        let invalid_job = MonitorSchedule::new("0 0 0 0 0 0 0", None, None).unwrap().get_job("test", &status, &running_checks, |_| Box::pin(async {}));
+        assert!(invalid_job.is_err());
+    }
+
+    #[test]
+    fn test_get_random_delay() {
+        let jitter = Duration::from_secs(10);
+        let delays: Vec<Duration> = (0..20).map(|_| MonitorSchedule::get_random_delay(jitter)).collect();
+        assert!(delays.iter().all(|delay| *delay <= jitter));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+        assert_eq!(MonitorSchedule::get_random_delay(Duration::ZERO), Duration::ZERO);
+    }
+
+    /**
+     * Test that an interval job runs the check repeatedly, with the timeout of the schedule.
+     */
+    #[tokio::test]
+    async fn test_interval_job_runs() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let job_runs = runs.clone();
+        let schedule = MonitorSchedule { timing: Timing::Interval(Duration::from_secs(1)), jitter: None, timeout: Some(Duration::from_secs(5)) };
+        let job = schedule.get_job("test", &get_status("test"), &RunningChecks::new(), move |timeout| {
+            assert_eq!(timeout, Some(Duration::from_secs(5)));
+            let job_runs = job_runs.clone();
+            Box::pin(async move {
+                job_runs.fetch_add(1, Ordering::SeqCst);
+            })
+        }).unwrap();
+        let mut scheduler = JobScheduler::new().await.unwrap();
+        scheduler.add(job).await.unwrap();
+        scheduler.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2600)).await;
+        scheduler.shutdown().await.unwrap();
+        assert!(runs.load(Ordering::SeqCst) >= 2);
+    }
+
+    /**
+     * Test that a run is skipped and counted while the previous run of the monitor is still in progress.
+     */
+    #[tokio::test]
+    async fn test_overlapping_runs_are_skipped() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let job_runs = runs.clone();
+        let status = get_status("slow");
+        let schedule = MonitorSchedule { timing: Timing::Interval(Duration::from_secs(1)), jitter: None, timeout: None };
+        let job = schedule.get_job("slow", &status, &RunningChecks::new(), move |_| {
+            let job_runs = job_runs.clone();
+            Box::pin(async move {
+                job_runs.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            })
+        }).unwrap();
+        let mut scheduler = JobScheduler::new().await.unwrap();
+        scheduler.add(job).await.unwrap();
+        scheduler.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(3600)).await;
+        scheduler.shutdown().await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(status.lock().unwrap().get("slow").unwrap().skipped_checks >= 2);
+    }
+
+    /**
+     * Test a delayed run.
+     *
+     * Test the following scenarios:
+     * - The run is in progress and running while it waits, and the check runs after the delay.
+     * - The check is not run if the monitor is paused while the run waits.
+     * - The check is not run if the monitor is removed while the run waits.
+     * - The check is not run if the checks are stopped while the run waits.
+     */
+    #[tokio::test]
+    async fn test_delayed_run() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let get_check = || -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            let runs = runs.clone();
+            Box::pin(async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let status = get_status("test");
+        let running_checks = RunningChecks::new();
+        let run = tokio::spawn(MonitorSchedule::run("test".to_string(), status.clone(), running_checks.clone(), Some(Duration::from_millis(300)), get_check()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(status.lock().unwrap().get("test").unwrap().check_in_progress);
+        assert_eq!(running_checks.count(), 1);
+        run.await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(!status.lock().unwrap().get("test").unwrap().check_in_progress);
+        assert_eq!(running_checks.count(), 0);
+
+        let run = tokio::spawn(MonitorSchedule::run("test".to_string(), status.clone(), running_checks.clone(), Some(Duration::from_millis(300)), get_check()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        status.lock().unwrap().get_mut("test").unwrap().pause();
+        run.await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let status = get_status("test");
+        let run = tokio::spawn(MonitorSchedule::run("test".to_string(), status.clone(), running_checks.clone(), Some(Duration::from_millis(300)), get_check()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        status.lock().unwrap().remove("test");
+        run.await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let status = get_status("test");
+        let run = tokio::spawn(MonitorSchedule::run("test".to_string(), status.clone(), running_checks.clone(), Some(Duration::from_secs(10)), get_check()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        running_checks.stop();
+        assert!(running_checks.wait(Duration::from_secs(5)).await);
+        run.await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    /**
+     * Test that a check can not be started while another check of the same monitor is in progress.
+     */
+    #[test]
+    fn test_check_in_progress() {
+        let status = get_status("test");
+        let check_in_progress = CheckInProgress::start("test", &status).unwrap();
+        assert!(status.lock().unwrap().get("test").unwrap().check_in_progress);
+        assert!(CheckInProgress::start("test", &status).is_none());
+        drop(check_in_progress);
+        assert!(!status.lock().unwrap().get("test").unwrap().check_in_progress);
+        assert!(CheckInProgress::start("test", &status).is_some());
+    }
+}
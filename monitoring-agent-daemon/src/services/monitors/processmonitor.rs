@@ -0,0 +1,475 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use log::{debug, error, info};
+use monitoring_agent_lib::proc::{process::ProcessState, ProcsProcess};
+use regex::Regex;
+use tokio_cron_scheduler::Job;
+
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, ProcessStateType, Status}, DbService, services::NotificationService};
+
+use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
+
+/**
+ * Process Monitor.
+ *
+ * This struct represents a process monitor. It finds the processes matching a name and/or a command line regular
+ * expression, and checks their number, threads and states.
+ *
+ */
+#[derive(Debug, Clone)]
+pub struct ProcessMonitor {
+    /// The name of the monitor.
+    pub name: String,
+    /// The name of the processes.
+    pub process_name: Option<String>,
+    /// Regular expression searched for in the command line of the processes.
+    pub command_line: Option<String>,
+    /// Min number of matching processes.
+    pub min_processes: u32,
+    /// Max number of matching processes.
+    pub max_processes: Option<u32>,
+    /// Max number of threads of each matching process.
+    pub max_threads: Option<u32>,
+    /// States no matching process may be in.
+    pub forbidden_states: Option<Vec<ProcessStateType>>,
+    /// The status of the monitor.
+    pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
+    /// The database service
+    database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
+    /// The database store level.
+    database_store_level: DatabaseStoreLevel,
+}
+
+impl ProcessMonitor {
+
+    /**
+     * Create a new `ProcessMonitor`.
+     *
+     * `name`: The name of the monitor.
+     * `process_name`: The name of the processes.
+     * `command_line`: Regular expression searched for in the command line of the processes.
+     * `min_processes`: Min number of matching processes.
+     * `max_processes`: Max number of matching processes.
+     * `max_threads`: Max number of threads of each matching process.
+     * `forbidden_states`: States no matching process may be in.
+     * `status`: The status of the monitor.
+     * `database_service`: The database service.
+     * `notification_service`: The notification service.
+     * `database_store_level`: The database store level.
+     *
+     * Returns: A new `ProcessMonitor`.
+     *
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        process_name: Option<String>,
+        command_line: Option<String>,
+        min_processes: u32,
+        max_processes: Option<u32>,
+        max_threads: Option<u32>,
+        forbidden_states: Option<Vec<ProcessStateType>>,
+        status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
+        database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
+        database_store_level: &DatabaseStoreLevel,
+    ) -> ProcessMonitor {
+
+        let status_lock = status.lock();
+        match status_lock {
+            Ok(mut lock) => {
+                lock.insert(name.to_string(), MonitorStatus::new(name.to_string(), Status::Unknown));
+            }
+            Err(err) => {
+                error!("Error creating process monitor: {:?}", err);
+            }
+        }
+
+        ProcessMonitor {
+            name: name.to_string(),
+            process_name,
+            command_line,
+            min_processes,
+            max_processes,
+            max_threads,
+            forbidden_states,
+            status: status.clone(),
+            database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
+            database_store_level: database_store_level.clone(),
+        }
+    }
+
+    /**
+     * Describe which processes are matched, for the status message.
+     *
+     * Returns: The description.
+     */
+    fn get_description(&self) -> String {
+        match (&self.process_name, &self.command_line) {
+            (Some(process_name), Some(command_line)) => format!("name {process_name} and command line {command_line}"),
+            (Some(process_name), None) => format!("name {process_name}"),
+            (None, Some(command_line)) => format!("command line {command_line}"),
+            (None, None) => "nothing".to_string(),
+        }
+    }
+
+    /**
+     * Check if a process matches the name and the command line. Processes without a command line, like kernel
+     * threads, never match a command line.
+     *
+     * `process`: The process.
+     * `command_line`: The command line regular expression.
+     *
+     * Returns: True if the process matches.
+     */
+    fn is_match(&self, process: &ProcsProcess, command_line: Option<&Regex>) -> bool {
+        let name_matches = self.process_name.as_ref().is_none_or(|process_name| process.name.as_ref() == Some(process_name));
+        let command_line_matches = command_line.is_none_or(|command_line| {
+            process.command_line.as_ref().is_some_and(|process_command_line| command_line.is_match(process_command_line))
+        });
+        (self.process_name.is_some() || command_line.is_some()) && name_matches && command_line_matches
+    }
+
+    /**
+     * Check if a process state is a forbidden state.
+     *
+     * `forbidden_state`: The forbidden state.
+     * `state`: The state of the process.
+     *
+     * Returns: True if the process is in the forbidden state.
+     */
+    fn is_state(forbidden_state: ProcessStateType, state: &ProcessState) -> bool {
+        match forbidden_state {
+            ProcessStateType::Running => *state == ProcessState::Running,
+            ProcessStateType::InterruptableSleep => *state == ProcessState::InterruptableSleep,
+            ProcessStateType::DiskSleep => *state == ProcessState::DiskSleep,
+            ProcessStateType::Stopped => *state == ProcessState::Stopped,
+            ProcessStateType::TracingStop => *state == ProcessState::TracingStop,
+            ProcessStateType::Zombie => *state == ProcessState::Zombie,
+            ProcessStateType::Idle => *state == ProcessState::Idle,
+            ProcessStateType::Dead => *state == ProcessState::Dead,
+        }
+    }
+
+    /**
+     * Check the matching processes.
+     *
+     * `processes`: All processes.
+     * `duration`: The duration of the check.
+     *
+     */
+    async fn check_processes(&mut self, processes: &[ProcsProcess], duration: Duration) {
+        let command_line = match self.command_line.as_deref().map(Regex::new).transpose() {
+            Ok(command_line) => command_line,
+            Err(err) => {
+                self.set_status(&Status::Error {
+                    message: format!("Process check failed: invalid commandLine: {err}"),
+                }, duration).await;
+                return;
+            }
+        };
+        let matching: Vec<&ProcsProcess> = processes.iter()
+            .filter(|process| self.is_match(process, command_line.as_ref()))
+            .collect();
+        let status = self.check_matching_processes(&matching);
+        self.set_status(&status, duration).await;
+    }
+
+    /**
+     * Check the number, threads and states of the matching processes.
+     *
+     * `matching`: The matching processes.
+     *
+     * Returns: Error with the pids of the processes if a check fails, otherwise ok.
+     *
+     */
+    fn check_matching_processes(&self, matching: &[&ProcsProcess]) -> Status {
+        let mut errors: Vec<String> = Vec::new();
+        let count = u32::try_from(matching.len()).unwrap_or(u32::MAX);
+        let found = if matching.is_empty() {
+            format!("found 0 matching {}", self.get_description())
+        } else {
+            let pids: Vec<String> = matching.iter().filter_map(|process| process.pid).map(|pid| pid.to_string()).collect();
+            format!("found {count} matching {} (pids {})", self.get_description(), pids.join(", "))
+        };
+        if count < self.min_processes {
+            errors.push(format!("{found}, expected at least {}", self.min_processes));
+        }
+        if let Some(max_processes) = self.max_processes {
+            if count > max_processes {
+                errors.push(format!("{found}, expected at most {max_processes}"));
+            }
+        }
+        for process in matching {
+            let pid = process.pid.map_or_else(|| "unknown".to_string(), |pid| pid.to_string());
+            if let (Some(max_threads), Some(threads)) = (self.max_threads, process.threads) {
+                if threads > max_threads {
+                    errors.push(format!("pid {pid} has {threads} threads, more than {max_threads}"));
+                }
+            }
+            if let Some(state) = &process.state {
+                for forbidden_state in self.forbidden_states.iter().flatten() {
+                    if ProcessMonitor::is_state(*forbidden_state, state) {
+                        errors.push(format!("pid {pid} is {forbidden_state:?}"));
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Status::Ok
+        } else {
+            Status::Error {
+                message: format!("Process check failed: {}", errors.join(", ")),
+            }
+        }
+    }
+
+    /**
+     * Get process monitor job.
+     *
+     * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
+     *
+     * Returns: The process monitor job.
+     *
+     */
+    pub fn get_process_monitor_job(
+        &mut self,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
+    ) -> Result<Job, ApplicationError> {
+        info!("Creating process monitor: {}", &self.name);
+        let process_monitor = self.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut process_monitor = process_monitor.clone();
+            Box::pin(async move {
+                let mut checked_monitor = process_monitor.clone();
+                process_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
+    }
+
+    /**
+     * Check the monitor.
+     */
+    pub async fn check(&mut self) {
+        debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
+        let processes = ProcsProcess::get_all_processes();
+        let duration = start.elapsed();
+        match processes {
+            Ok(processes) => {
+                self.check_processes(&processes, duration).await;
+            }
+            Err(err) => {
+                error!("Error getting processes: {:?}", err);
+                self.set_status(&Status::Error {
+                    message: format!("Process check failed: {}", err.message),
+                }, duration).await;
+            }
+        }
+    }
+
+}
+
+/**
+ * Implement the `Monitor` trait for `ProcessMonitor`.
+ */
+impl super::Monitor for ProcessMonitor {
+    /**
+     * Get the name of the monitor.
+     *
+     * Returns: The name of the monitor.
+     */
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Get the status of the monitor.
+     *
+     * Returns: The status of the monitor.
+     */
+    fn get_status(&self) -> Arc<Mutex<HashMap<String, MonitorStatus>>> {
+        self.status.clone()
+    }
+
+    /**
+     * Get the database service.
+     *
+     * Returns: The database service.
+     */
+    fn get_database_service(&self) -> Arc<Option<DbService>> {
+        self.database_service.clone()
+    }
+
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
+    /**
+     * Get the database store level.
+     *
+     * Returns: The database store level.
+     */
+    fn get_database_store_level(&self) -> DatabaseStoreLevel {
+        self.database_store_level.clone()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+
+    use monitoring_agent_lib::proc::{process::ProcessState, ProcsProcess};
+
+    use crate::common::{configuration::DatabaseStoreLevel, MonitorStatus, ProcessStateType, Status};
+
+    use super::{MonitorSchedule, ProcessMonitor, RunningChecks};
+
+    /**
+     * Get a process.
+     */
+    fn get_process(pid: u32, name: &str, state: ProcessState, threads: u32, command_line: Option<&str>) -> ProcsProcess {
+        ProcsProcess::new(Some(pid), Some(1), Some(name.to_string()), None, Some(state), Some(threads), None, command_line.map(ToString::to_string))
+    }
+
+    /**
+     * Get test processes: two nginx workers, a java service and a kernel thread.
+     */
+    fn get_processes() -> Vec<ProcsProcess> {
+        vec![
+            get_process(100, "nginx", ProcessState::InterruptableSleep, 1, Some("nginx: worker process")),
+            get_process(101, "nginx", ProcessState::Zombie, 1, None),
+            get_process(200, "java", ProcessState::Running, 250, Some("/usr/bin/java -Xmx1g -jar /opt/service/service.jar")),
+            get_process(300, "kworker/0:1", ProcessState::Idle, 1, None),
+        ]
+    }
+
+    /**
+     * Test the check function with the processes of the system.
+     *
+     * Test the following scenarios:
+     * - The test process is found by its command line and is not a zombie.
+     * - No process matches the name.
+     */
+    #[tokio::test]
+    async fn test_check() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = ProcessMonitor::new("test", None, Some("monitoring_agent_daemon".to_string()), 1, None, None, Some(vec![ProcessStateType::Zombie]), &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+
+        let mut monitor = ProcessMonitor::new("test", Some("no-such-process".to_string()), None, 1, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check().await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Process check failed: found 0 matching name no-such-process, expected at least 1".to_string(),
+        });
+    }
+
+    /**
+     * Test the check_processes function.
+     *
+     * Test the following scenarios:
+     * - The number of processes matching the name is within the limits.
+     * - Fewer processes than the minimum match the name.
+     * - More processes than the maximum match the name.
+     */
+    #[tokio::test]
+    async fn test_check_process_count() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = ProcessMonitor::new("test", Some("nginx".to_string()), None, 1, Some(2), None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+
+        let mut monitor = ProcessMonitor::new("test", Some("nginx".to_string()), None, 3, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Process check failed: found 2 matching name nginx (pids 100, 101), expected at least 3".to_string(),
+        });
+
+        let mut monitor = ProcessMonitor::new("test", Some("nginx".to_string()), None, 0, Some(1), None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Process check failed: found 2 matching name nginx (pids 100, 101), expected at most 1".to_string(),
+        });
+    }
+
+    /**
+     * Test the check_processes function with a command line.
+     *
+     * Test the following scenarios:
+     * - The process matching the name and command line has more threads than the maximum.
+     * - A process matches the command line.
+     * - No process matches the command line.
+     * - The command line is an invalid regular expression.
+     */
+    #[tokio::test]
+    async fn test_check_command_line() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = ProcessMonitor::new("test", Some("java".to_string()), Some(r"-jar /opt/service/service\.jar".to_string()), 1, Some(1), Some(200), None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Process check failed: pid 200 has 250 threads, more than 200".to_string(),
+        });
+
+        let mut monitor = ProcessMonitor::new("test", None, Some("^nginx".to_string()), 1, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+
+        let mut monitor = ProcessMonitor::new("test", None, Some("other.jar".to_string()), 1, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Process check failed: found 0 matching command line other.jar, expected at least 1".to_string(),
+        });
+
+        let mut monitor = ProcessMonitor::new("test", None, Some("java (".to_string()), 1, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert!(matches!(&status.lock().unwrap().get("test").unwrap().status, Status::Error { message } if message.starts_with("Process check failed: invalid commandLine")));
+    }
+
+    /**
+     * Test the check_processes function with forbidden states.
+     *
+     * Test the following scenarios:
+     * - One of the processes matching the name is in a forbidden state.
+     * - No process matching the name is in a forbidden state.
+     */
+    #[tokio::test]
+    async fn test_check_forbidden_states() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = ProcessMonitor::new("test", Some("nginx".to_string()), None, 1, None, None, Some(vec![ProcessStateType::Zombie, ProcessStateType::Stopped]), &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Error {
+            message: "Process check failed: pid 101 is Zombie".to_string(),
+        });
+
+        let mut monitor = ProcessMonitor::new("test", Some("java".to_string()), None, 1, None, None, Some(vec![ProcessStateType::Zombie]), &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.check_processes(&get_processes(), Duration::ZERO).await;
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, Status::Ok);
+    }
+
+    #[test]
+    fn test_get_process_monitor_job() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let mut monitor = ProcessMonitor::new("test", Some("nginx".to_string()), None, 1, None, None, None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let job = monitor.get_process_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
+        assert!(job.is_ok());
+    }
+}
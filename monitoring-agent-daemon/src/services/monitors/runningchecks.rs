@@ -0,0 +1,152 @@
+use std::{sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc}, time::Duration};
+
+use tokio::time::Instant;
+
+/**
+ * Running checks.
+ *
+ * Counts the running checks, scheduled or run on demand. Used on shutdown to let the running checks finish, and to
+ * stop the checks still waiting to start.
+ *
+ */
+#[derive(Debug, Clone, Default)]
+pub struct RunningChecks {
+    /// The number of running checks.
+    count: Arc<AtomicUsize>,
+    /// True when the checks are stopped on shutdown.
+    stopped: Arc<AtomicBool>,
+}
+
+/**
+ * Running check.
+ *
+ * Marks a check as running until it is dropped.
+ */
+pub struct RunningCheck {
+    /// The number of running checks.
+    count: Arc<AtomicUsize>,
+}
+
+impl RunningChecks {
+
+    /**
+     * Create a new running checks counter.
+     *
+     * Returns: The running checks counter.
+     */
+    pub fn new() -> RunningChecks {
+        RunningChecks::default()
+    }
+
+    /**
+     * Mark a check as started. The check is running until the returned value is dropped.
+     *
+     * Returns: The running check.
+     */
+    pub fn start(&self) -> RunningCheck {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        RunningCheck {
+            count: self.count.clone(),
+        }
+    }
+
+    /**
+     * Get the number of running checks.
+     *
+     * Returns: The number of running checks.
+     */
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Stop the checks. Checks waiting to start with `sleep` are not started.
+     */
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /**
+     * Wait before starting a check. The wait ends early if the checks are stopped.
+     *
+     * `delay`: The time to wait.
+     *
+     * Returns: True if the check can start, false if the checks are stopped.
+     */
+    pub async fn sleep(&self, delay: Duration) -> bool {
+        let end = Instant::now() + delay;
+        while !self.stopped.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now >= end {
+                return true;
+            }
+            tokio::time::sleep((end - now).min(Duration::from_millis(50))).await;
+        }
+        false
+    }
+
+    /**
+     * Wait for the running checks to finish.
+     *
+     * `deadline`: The maximum time to wait.
+     *
+     * Returns: True if all checks finished before the deadline.
+     */
+    pub async fn wait(&self, deadline: Duration) -> bool {
+        let end = Instant::now() + deadline;
+        while self.count() > 0 {
+            if Instant::now() >= end {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        true
+    }
+}
+
+impl Drop for RunningCheck {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /**
+     * Test that waiting returns when the running checks are finished, and times out otherwise.
+     */
+    #[tokio::test]
+    async fn test_wait() {
+        let running_checks = RunningChecks::new();
+        assert!(running_checks.wait(Duration::ZERO).await);
+        let running_check = running_checks.start();
+        assert_eq!(running_checks.count(), 1);
+        assert!(!running_checks.wait(Duration::from_millis(100)).await);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            drop(running_check);
+        });
+        assert!(running_checks.wait(Duration::from_secs(5)).await);
+        assert_eq!(running_checks.count(), 0);
+    }
+
+    /**
+     * Test that sleeping ends early when the checks are stopped.
+     */
+    #[tokio::test]
+    async fn test_sleep() {
+        let running_checks = RunningChecks::new();
+        assert!(running_checks.sleep(Duration::from_millis(10)).await);
+        let stopping = running_checks.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            stopping.stop();
+        });
+        let start = Instant::now();
+        assert!(!running_checks.sleep(Duration::from_secs(10)).await);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!running_checks.sleep(Duration::ZERO).await);
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use log::error;
+use tokio_cron_scheduler::Job;
+
+use crate::common::ApplicationError;
+use crate::services::monitors::Monitor;
+
+use super::{CertificateMonitor, CommandMonitor, CpuMonitor, DatabaseMonitor, DiskMonitor, DnsMonitor, HttpMonitor, LoadAvgMonitor, MeminfoMonitor, MonitorSchedule, ProcessMonitor, RunningChecks, SystemctlMonitor, TcpMonitor};
+
+/**
+ * Scheduled monitor.
+ *
+ * This enum holds a monitor added to the scheduler. It is used to run the check of a monitor outside of its schedule
+ * and to create the job of the monitor again when it is resumed.
+ *
+ */
+#[derive(Debug, Clone)]
+pub enum ScheduledMonitor {
+    Tcp(TcpMonitor),
+    Http(HttpMonitor),
+    Command(CommandMonitor),
+    LoadAvg(LoadAvgMonitor),
+    Mem(MeminfoMonitor),
+    Disk(DiskMonitor),
+    Cpu(CpuMonitor),
+    Certificate(CertificateMonitor),
+    Dns(DnsMonitor),
+    Process(ProcessMonitor),
+    Systemctl(SystemctlMonitor),
+    Database(DatabaseMonitor),
+}
+
+impl ScheduledMonitor {
+
+    /**
+     * Get the scheduled job of the monitor.
+     *
+     * `schedule`: The schedule.
+     * `running_checks`: The running checks. The job marks its check as running.
+     *
+     * Returns: The job.
+     *
+     * throws: `ApplicationError`: If the job fails to be created.
+     */
+    pub fn get_job(&mut self, schedule: &MonitorSchedule, running_checks: &RunningChecks) -> Result<Job, ApplicationError> {
+        match self {
+            ScheduledMonitor::Tcp(monitor) => monitor.get_tcp_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Http(monitor) => monitor.get_http_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Command(monitor) => monitor.get_command_monitor_job(schedule, running_checks),
+            ScheduledMonitor::LoadAvg(monitor) => monitor.get_loadavg_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Mem(monitor) => monitor.get_meminfo_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Disk(monitor) => monitor.get_disk_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Cpu(monitor) => monitor.get_cpu_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Certificate(monitor) => monitor.get_certificate_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Dns(monitor) => monitor.get_dns_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Process(monitor) => monitor.get_process_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Systemctl(monitor) => monitor.get_systemctl_monitor_job(schedule, running_checks),
+            ScheduledMonitor::Database(monitor) => monitor.get_database_monitor_job(schedule, running_checks),
+        }
+    }
+
+    /**
+     * Check the monitor. Uses the same check and timeout as the scheduled job of the monitor. A check running longer
+     * than the timeout is cancelled and the monitor gets a timed out error.
+     *
+     * `timeout`: The maximum duration of the check. If None, the check runs until it finishes.
+     */
+    pub async fn check(&mut self, timeout: Option<Duration>) {
+        match self {
+            ScheduledMonitor::Tcp(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Http(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move {
+                    let _ = checked_monitor.check().await.map_err(|err| {
+                        error!("Error checking monitor: {:?}", err);
+                    });
+                }, timeout).await;
+            },
+            ScheduledMonitor::Command(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move {
+                    let _ = checked_monitor.check().await.map_err(|err| {
+                        error!("Error checking monitor: {:?}", err);
+                    });
+                }, timeout).await;
+            },
+            ScheduledMonitor::LoadAvg(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Mem(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Disk(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Cpu(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Certificate(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Dns(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Process(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Systemctl(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+            ScheduledMonitor::Database(monitor) => {
+                let mut checked_monitor = monitor.clone();
+                monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+    use crate::common::{configuration::DatabaseStoreLevel, MonitorStatus, Status};
+
+    use super::*;
+
+    /**
+     * Test that checking a scheduled monitor updates the status of the monitor.
+     */
+    #[tokio::test]
+    async fn test_check() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> = Arc::new(Mutex::new(HashMap::new()));
+        let monitor = TcpMonitor::new("localhost", 65000, None, "localhost", &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut scheduled_monitor = ScheduledMonitor::Tcp(monitor);
+        scheduled_monitor.check(None).await;
+        assert!(matches!(status.lock().unwrap().get("localhost").unwrap().status, Status::Error { .. }));
+    }
+
+    /**
+     * Test that a check running longer than the timeout is cancelled and counted as timed out.
+     */
+    #[tokio::test]
+    async fn test_check_timeout() {
+        let status: Arc<Mutex<HashMap<String, MonitorStatus>>> = Arc::new(Mutex::new(HashMap::new()));
+        let monitor = CommandMonitor::new("sleep", "sleep", Some(vec!["5".to_string()]), None, &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut scheduled_monitor = ScheduledMonitor::Command(monitor);
+        scheduled_monitor.check(Some(std::time::Duration::from_millis(200))).await;
+        let status_lock = status.lock().unwrap();
+        let monitor_status = status_lock.get("sleep").unwrap();
+        assert_eq!(monitor_status.status, Status::Error { message: "Check timed out after 200 ms".to_string() });
+        assert_eq!(monitor_status.timed_out_checks, 1);
+    }
+}
@@ -1,11 +1,12 @@
-use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}};
+use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}, time::Instant};
 
 use log::{debug, error, info};
 use tokio_cron_scheduler::Job;
 
-use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, services::DbService};
+use crate::{common::{configuration::DatabaseStoreLevel, ApplicationError, MonitorStatus, Status}, services::{DbService, NotificationService}};
 
 use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
 
 const SYSTEMD_ACTIVE_STATUS: &str = "active";
 
@@ -18,6 +19,8 @@ pub struct SystemctlMonitor {
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel,
     /// The services to monitor.
@@ -31,6 +34,7 @@ impl SystemctlMonitor {
      * `name`: The name of the monitor.
      * `status`: The status of the monitor.
      * `database_service`: The database service.
+     * `notification_service`: The notification service.
      * `database_store_level`: The database store level.
      * `active`: The services to monitor.
      *
@@ -39,6 +43,7 @@ impl SystemctlMonitor {
         name: &str,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel,
         active: Vec<String>,
     ) -> SystemctlMonitor {
@@ -56,6 +61,7 @@ impl SystemctlMonitor {
             name: name.to_string(),
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
             active,
         }
@@ -65,44 +71,44 @@ impl SystemctlMonitor {
      * Get systemctl job.
      *
      * `schedule`: The schedule for the job.
+     * `running_checks`: The running checks. The job marks its check as running.
      */
     pub fn get_systemctl_monitor_job(
         &mut self,
-        schedule: &str,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating Systemctl monitor: {}", &self.name);
         let systemctl_monitor = self.clone();
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {
-            let systemctl_monitor = systemctl_monitor.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut systemctl_monitor = systemctl_monitor.clone();
             Box::pin(async move {
-                systemctl_monitor.clone().check().await;
-            })              
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            )),
-        }
+                let mut checked_monitor = systemctl_monitor.clone();
+                systemctl_monitor.check_with_timeout(async move { checked_monitor.check().await; }, timeout).await;
+            })
+        })
     }
 
     /**
      * Check the monitor.
      */
-    async fn check(&mut self) {
+    pub async fn check(&mut self) {
         debug!("Checking monitor: {}", &self.name);
+        let start = Instant::now();
         let output = tokio::process::Command::new("systemctl")
             .arg("--all")
+            .kill_on_drop(true)
             .output()
             .await
             .expect("failed to execute process");
+        let duration = start.elapsed();
         let command_output = String::from_utf8_lossy(&output.stdout);        
         let non_active = self.get_nonactive_status(&command_output);
         let error_message = format!("Non-active services: {non_active:?}");
         if non_active.is_empty() {
-            self.set_status(&Status::Ok).await;            
+            self.set_status(&Status::Ok, duration).await;            
         } else {
-            self.set_status(&Status::Error { message: error_message }).await;
+            self.set_status(&Status::Error { message: error_message }, duration).await;
         }        
     }
 
@@ -158,6 +164,15 @@ impl super::Monitor for SystemctlMonitor {
         self.database_service.clone()
     }
 
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
     /**
      * Get the database store level.
      *
@@ -190,6 +205,7 @@ mod test {
             "test",
             &status,
             &database_service,
+            &Arc::new(None),
             &database_store_level,
             active,
         );
@@ -213,6 +229,7 @@ mod test {
             "test",
             &status,
             &database_service,
+            &Arc::new(None),
             &database_store_level,
             active,
         );
@@ -234,6 +251,7 @@ mod test {
             "test",
             &status,
             &database_service,
+            &Arc::new(None),
             &database_store_level,
             active,
         );
@@ -250,10 +268,11 @@ mod test {
             "test",
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None,
             vec![],
         );
-        let job = monitor.get_systemctl_monitor_job("0 0 * * * *");
+        let job = monitor.get_systemctl_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }  
 
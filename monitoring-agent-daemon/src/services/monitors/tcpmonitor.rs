@@ -4,12 +4,16 @@ use tokio_cron_scheduler::Job;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
 
 use crate::common::configuration::DatabaseStoreLevel;
 use crate::common::{ApplicationError, MonitorStatus, Status};
-use crate::services::DbService;
+use crate::services::{DbService, NotificationService};
 
+use super::common::check_response_time;
 use super::Monitor;
+use super::{MonitorSchedule, RunningChecks};
 
 
 /**
@@ -20,6 +24,7 @@ use super::Monitor;
  * name: The name of the monitor.
  * host: The host to monitor.
  * port: The port to monitor.
+ * max_response_time: The max response time in milliseconds.
  * status: The status of the monitor.
  *
  */
@@ -31,10 +36,14 @@ pub struct TcpMonitor {
     pub host: String,
     /// The port of the host monitor.
     pub port: u16,
+    /// The max response time in milliseconds.
+    pub max_response_time: Option<u64>,
     /// The status of the monitor.
     pub status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The database store level.
     database_store_level: DatabaseStoreLevel,
 }
@@ -45,16 +54,20 @@ impl TcpMonitor {
      *
      * host: The host to monitor.
      * port: The port to monitor.
+     * max_response_time: The max response time in milliseconds.
      * name: The name of the monitor.
      * status: The status of the monitor.
      *
      */
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         host: &str,
         port: u16,
+        max_response_time: Option<u64>,
         name: &str,
         status: &Arc<Mutex<HashMap<String, MonitorStatus>>>,
         database_service: &Arc<Option<DbService>>,
+        notification_service: &Arc<Option<NotificationService>>,
         database_store_level: &DatabaseStoreLevel,
     ) -> TcpMonitor {
         debug!("Creating TCP monitor: {}", &name);
@@ -72,8 +85,10 @@ impl TcpMonitor {
             name: name.to_string(),
             host: host.to_string(),
             port,
+            max_response_time,
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             database_store_level: database_store_level.clone(),
         }
     }
@@ -84,14 +99,15 @@ impl TcpMonitor {
      * `tcp_stream`: The TCP stream to close.
      *
      */
-    fn close_connection(tcp_stream: &std::net::TcpStream) {
-        let _ = tcp_stream.shutdown(std::net::Shutdown::Both).map_err(|err| error!("Error closing connection: {:?}", err));
+    async fn close_connection(tcp_stream: &mut tokio::net::TcpStream) {
+        let _ = tcp_stream.shutdown().await.map_err(|err| error!("Error closing connection: {:?}", err));
     }
 
         /**
      * Get a TCP monitor job.
      *
      * `schedule`: The schedule.
+     * `running_checks`: The running checks. The job marks its check as running.
      * `name`: The name of the monitor.
      * `host`: The host to monitor.
      * `port`: The port to monitor.
@@ -100,33 +116,33 @@ impl TcpMonitor {
      */
     pub fn get_tcp_monitor_job(
         &mut self,
-        schedule: &str,
+        schedule: &MonitorSchedule,
+        running_checks: &RunningChecks,
     ) -> Result<Job, ApplicationError> {
         info!("Creating Tcp monitor: {}", &self.name);
         let tcp_monitor = self.clone();
-        let job_result = Job::new_async(schedule, move |_uuid, _locked| {
-            let tcp_monitor = tcp_monitor.clone();
+        schedule.get_job(&self.name, &self.status, running_checks, move |timeout| {
+            let mut tcp_monitor = tcp_monitor.clone();
             Box::pin(async move {
-                TcpMonitor::run_scheduled(tcp_monitor.clone()).await;
-            })              
-        });        
-        match job_result {
-            Ok(job) => Ok(job),
-            Err(err) => Err(ApplicationError::new(
-                format!("Could not create job: {err}").as_str(),
-            )),
-        }
+                let checked_monitor = tcp_monitor.clone();
+                tcp_monitor.check_with_timeout(TcpMonitor::run_scheduled(checked_monitor), timeout).await;
+            })
+        })
     }
 
     /**
      * Check the monitor.
      */
-    async fn check(&mut self) {
+    pub async fn check(&mut self) {
         debug!("Checking monitor: {}", &self.name);
-        match std::net::TcpStream::connect(format!("{}:{}", &self.host, &self.port)) {
-            Ok(tcp_stream) => {
-                TcpMonitor::close_connection(&tcp_stream);
-                self.set_status(&Status::Ok).await;
+        let start = Instant::now();
+        let connect_result = tokio::net::TcpStream::connect(format!("{}:{}", &self.host, &self.port)).await;
+        let duration = start.elapsed();
+        match connect_result {
+            Ok(mut tcp_stream) => {
+                TcpMonitor::close_connection(&mut tcp_stream).await;
+                let status = check_response_time(Status::Ok, duration, self.max_response_time);
+                self.set_status(&status, duration).await;
             }
             Err(err) => {
                 info!("Monitor status error: {} - {}", &self.name, err);
@@ -135,7 +151,7 @@ impl TcpMonitor {
                         "Error connecting to {}:{} with error: {err}",
                         &self.host, &self.port,
                     ),
-                }).await;
+                }, duration).await;
             }
         }
     }    
@@ -182,6 +198,15 @@ impl super::Monitor for TcpMonitor {
         self.database_service.clone()
     }
 
+    /**
+     * Get the notification service.
+     *
+     * Returns: The notification service.
+     */
+    fn get_notification_service(&self) -> Arc<Option<NotificationService>> {
+        self.notification_service.clone()
+    }
+
     /**
      * Get the database store level.
      *
@@ -207,7 +232,7 @@ mod test {
     #[tokio::test]
     async fn test_check_port_139() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = TcpMonitor::new("localhost", 139, "localhost", &status, &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut monitor = TcpMonitor::new("localhost", 139, None, "localhost", &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
         monitor.check().await;
         assert_eq!(
             status.lock().unwrap().get("localhost").unwrap().status,
@@ -222,7 +247,7 @@ mod test {
     async fn test_check_port_65000() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = TcpMonitor::new("localhost", 65000, "localhost", &status, &Arc::new(None), &DatabaseStoreLevel::None);
+        let mut monitor = TcpMonitor::new("localhost", 65000, None, "localhost", &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
         monitor.check().await;
         assert_eq!(status.lock().unwrap().get("localhost").unwrap().status, Status::Error { message: "Error connecting to localhost:65000 with error: Connection refused (os error 111)".to_string() });
     }
@@ -234,8 +259,8 @@ mod test {
     async fn test_set_status() {
         let status: Arc<Mutex<HashMap<String, MonitorStatus>>> =
             Arc::new(Mutex::new(HashMap::new()));
-        let mut monitor = TcpMonitor::new("localhost", 65000, "localhost", &status, &Arc::new(None), &DatabaseStoreLevel::None);
-        monitor.set_status(&Status::Ok).await;
+        let mut monitor = TcpMonitor::new("localhost", 65000, None, "localhost", &status, &Arc::new(None), &Arc::new(None), &DatabaseStoreLevel::None);
+        monitor.set_status(&Status::Ok, std::time::Duration::ZERO).await;
         assert_eq!(
             status.lock().unwrap().get("localhost").unwrap().status,
             Status::Ok
@@ -249,12 +274,14 @@ mod test {
         let mut monitor = TcpMonitor::new(
             "localhost",
             65000,
+            None,
             "localhost",
             &status,
             &Arc::new(None),
+            &Arc::new(None),
             &DatabaseStoreLevel::None,
         );
-        let job = monitor.get_tcp_monitor_job("0 0 * * * *");
+        let job = monitor.get_tcp_monitor_job(&MonitorSchedule::new("0 0 * * * *", None, None).unwrap(), &RunningChecks::new());
         assert!(job.is_ok());
     }      
 }
@@ -0,0 +1,745 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+
+use crate::common::{ApplicationError, EmailConfig, Monitor, NotificationConfig, SmtpSecurity, Status, WebhookConfig};
+use crate::services::monitors::HttpMonitor;
+
+/**
+ * Notification Service.
+ *
+ * This struct represents the notification service. It notifies the configured receivers when a monitor changes status.
+ *
+ * `server_name`: The server name.
+ * `webhooks`: The webhooks to notify.
+ * `client`: The http client used for posting to the webhooks.
+ * `email`: The email configuration.
+ * `mailer`: The smtp transport used for sending emails.
+ * `email_recipients`: The email recipients for each monitor.
+ *
+ */
+#[derive(Debug, Clone)]
+pub struct NotificationService {
+    /// The server name.
+    server_name: String,
+    /// The webhooks to notify.
+    webhooks: Vec<WebhookConfig>,
+    /// The http client used for posting to the webhooks.
+    client: reqwest::Client,
+    /// The email configuration.
+    email: Option<EmailConfig>,
+    /// The smtp transport used for sending emails.
+    mailer: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    /// The email recipients for each monitor. Monitors not in the map use the default recipients.
+    email_recipients: HashMap<String, Vec<String>>,
+}
+
+/**
+ * Status transition message.
+ *
+ * This struct represents the notification sent when a monitor changes status.
+ */
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusTransitionMessage {
+    /// The server name.
+    server_name: String,
+    /// The name of the monitor.
+    monitor_name: String,
+    /// The previous status of the monitor.
+    old_status: String,
+    /// The new status of the monitor.
+    new_status: String,
+    /// The message of the new status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    /// The time of the status transition.
+    time: DateTime<Utc>,
+}
+
+impl StatusTransitionMessage {
+    /**
+     * Create a new status transition message.
+     *
+     * `server_name`: The server name.
+     * `monitor_name`: The name of the monitor.
+     * `old_status`: The previous status.
+     * `new_status`: The new status.
+     *
+     */
+    pub fn new(server_name: &str, monitor_name: &str, old_status: &Status, new_status: &Status) -> StatusTransitionMessage {
+        StatusTransitionMessage {
+            server_name: server_name.to_string(),
+            monitor_name: monitor_name.to_string(),
+            old_status: NotificationService::get_status_name(old_status).to_string(),
+            new_status: NotificationService::get_status_name(new_status).to_string(),
+            message: NotificationService::get_status_message(new_status),
+            time: Utc::now(),
+        }
+    }
+
+    /**
+     * Get the email subject. Returning to Ok is reported as a recovery.
+     *
+     * Returns: The email subject.
+     */
+    fn get_email_subject(&self) -> String {
+        if self.new_status == NotificationService::get_status_name(&Status::Ok) {
+            format!("[{}] {} recovered", self.server_name, self.monitor_name)
+        } else {
+            format!("[{}] {} changed status to {}", self.server_name, self.monitor_name, self.new_status)
+        }
+    }
+
+    /**
+     * Get the email body.
+     *
+     * Returns: The email body.
+     */
+    fn get_email_body(&self) -> String {
+        let mut body = format!(
+            "Server: {}\nMonitor: {}\nOld status: {}\nNew status: {}\n",
+            self.server_name, self.monitor_name, self.old_status, self.new_status
+        );
+        if let Some(message) = &self.message {
+            body.push_str(&format!("Message: {message}\n"));
+        }
+        body.push_str(&format!("Time: {}\n", self.time.to_rfc3339()));
+        body
+    }
+}
+
+impl NotificationService {
+    /**
+     * Create a new notification service.
+     *
+     * `notification_config`: The notification configuration.
+     * `server_name`: The server name.
+     * `monitors`: The monitors. Used for routing emails to monitor specific recipients.
+     *
+     * Returns: A new notification service.
+     *
+     * Errors:
+     * - If a webhook header or an email address is invalid.
+     * - If the http client could not be created.
+     * - If the smtp transport could not be created.
+     *
+     */
+    pub fn new(notification_config: &NotificationConfig, server_name: &str, monitors: &[Monitor]) -> Result<NotificationService, ApplicationError> {
+        NotificationService::validate(notification_config, monitors)?;
+        let client = reqwest::Client::builder()
+            .build()
+            .map_err(|err| ApplicationError::new(&format!("Error creating notification http client: {err}")))?;
+        let mailer = match &notification_config.email {
+            Some(email) => Some(NotificationService::get_mailer(email)?),
+            None => None,
+        };
+        let email_recipients = monitors
+            .iter()
+            .filter_map(|monitor| monitor.email_recipients.as_ref().map(|recipients| (monitor.name.clone(), recipients.clone())))
+            .collect();
+        Ok(NotificationService {
+            server_name: server_name.to_string(),
+            webhooks: notification_config.webhooks.clone(),
+            client,
+            email: notification_config.email.clone(),
+            mailer,
+            email_recipients,
+        })
+    }
+
+    /**
+     * Check that the webhook headers and the email addresses can be used, so an invalid configuration is found
+     * before the first notification is sent.
+     *
+     * `notification_config`: The notification configuration.
+     * `monitors`: The monitors with their email recipients.
+     *
+     * Returns: Ok if the configuration can be used.
+     *
+     * Errors:
+     * - Every invalid webhook header and email address found, one per line.
+     *
+     */
+    fn validate(notification_config: &NotificationConfig, monitors: &[Monitor]) -> Result<(), ApplicationError> {
+        let mut errors: Vec<String> = notification_config.webhooks.iter()
+            .filter_map(|webhook| HttpMonitor::get_headers(&webhook.headers).err())
+            .map(|err| format!("Webhook: {}", err.get_message()))
+            .collect();
+        if let Some(email) = &notification_config.email {
+            let recipients = monitors.iter().filter_map(|monitor| monitor.email_recipients.as_ref()).flatten();
+            for address in std::iter::once(&email.from).chain(&email.to).chain(recipients) {
+                if let Err(err) = NotificationService::get_mailbox(address) {
+                    errors.push(format!("Email: {}", err.get_message()));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApplicationError::new(&errors.join("\n")))
+        }
+    }
+
+    /**
+     * Create the smtp transport.
+     *
+     * `email`: The email configuration.
+     *
+     * Returns: The smtp transport.
+     *
+     * Errors:
+     * - If the tls parameters could not be created.
+     *
+     */
+    fn get_mailer(email: &EmailConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>, ApplicationError> {
+        let (tls, default_port) = match email.security {
+            SmtpSecurity::None => (Tls::None, 25),
+            SmtpSecurity::StartTls => (Tls::Required(NotificationService::get_tls_parameters(email)?), 587),
+            SmtpSecurity::Tls => (Tls::Wrapper(NotificationService::get_tls_parameters(email)?), 465),
+        };
+        let builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(email.host.as_str())
+            .port(email.port.unwrap_or(default_port))
+            .tls(tls)
+            .timeout(Some(Duration::from_secs(email.timeout)));
+        let builder = match &email.username {
+            Some(username) => builder.credentials(Credentials::new(username.clone(), email.password.clone().unwrap_or_default())),
+            None => builder,
+        };
+        Ok(builder.build())
+    }
+
+    /**
+     * Get the tls parameters for the smtp transport.
+     *
+     * `email`: The email configuration.
+     *
+     * Returns: The tls parameters.
+     *
+     * Errors:
+     * - If the tls parameters could not be created.
+     *
+     */
+    fn get_tls_parameters(email: &EmailConfig) -> Result<TlsParameters, ApplicationError> {
+        TlsParameters::builder(email.host.clone())
+            .dangerous_accept_invalid_certs(email.accept_invalid_certs)
+            .build()
+            .map_err(|err| ApplicationError::new(&format!("Error creating smtp tls parameters: {err}")))
+    }
+
+    /**
+     * Check if the change from the old status to the new status is a transition that should be notified.
+     * The first successful check after startup is not considered a transition.
+     *
+     * `old_status`: The previous status.
+     * `new_status`: The new status.
+     *
+     * Returns: True if the status has changed.
+     */
+    pub fn is_transition(old_status: &Status, new_status: &Status) -> bool {
+        if old_status == &Status::Unknown && new_status == &Status::Ok {
+            return false;
+        }
+        std::mem::discriminant(old_status) != std::mem::discriminant(new_status)
+    }
+
+    /**
+     * Notify all receivers of a status transition. The notifications are sent in the background.
+     *
+     * `monitor_name`: The name of the monitor.
+     * `old_status`: The previous status.
+     * `new_status`: The new status.
+     *
+     */
+    pub fn notify(&self, monitor_name: &str, old_status: &Status, new_status: &Status) {
+        info!("Monitor {} changed status from {:?} to {:?}", monitor_name, old_status, new_status);
+        let message = StatusTransitionMessage::new(&self.server_name, monitor_name, old_status, new_status);
+        for webhook in &self.webhooks {
+            let client = self.client.clone();
+            let webhook = webhook.clone();
+            let message = message.clone();
+            tokio::spawn(async move {
+                let _ = NotificationService::send_webhook(&client, &webhook, &message).await;
+            });
+        }
+        if let (Some(email), Some(mailer)) = (&self.email, &self.mailer) {
+            let recipients = self.get_email_recipients(monitor_name);
+            if recipients.is_empty() {
+                debug!("No email recipients for monitor: {}", monitor_name);
+                return;
+            }
+            let mailer = mailer.clone();
+            let from = email.from.clone();
+            tokio::spawn(async move {
+                let _ = NotificationService::send_email(&mailer, &from, &recipients, &message).await;
+            });
+        }
+    }
+
+    /**
+     * Get the email recipients of a monitor.
+     *
+     * `monitor_name`: The name of the monitor.
+     *
+     * Returns: The recipients of the monitor, the default recipients if the monitor has none, or no recipients if
+     * email is not configured.
+     *
+     */
+    pub fn get_email_recipients(&self, monitor_name: &str) -> Vec<String> {
+        match &self.email {
+            Some(email) => self.email_recipients.get(monitor_name).unwrap_or(&email.to).clone(),
+            None => Vec::new(),
+        }
+    }
+
+    /**
+     * Send the message as an email.
+     *
+     * `mailer`: The smtp transport.
+     * `from`: The sender address.
+     * `recipients`: The recipient addresses.
+     * `message`: The message to send.
+     *
+     * Returns: Ok if the email was accepted by the smtp server.
+     *
+     * Errors:
+     * - If an address could not be parsed.
+     * - If the email could not be built.
+     * - If the smtp server did not accept the email.
+     *
+     */
+    async fn send_email(mailer: &AsyncSmtpTransport<Tokio1Executor>, from: &str, recipients: &[String], message: &StatusTransitionMessage) -> Result<(), ApplicationError> {
+        let mut builder = Message::builder()
+            .from(NotificationService::get_mailbox(from)?)
+            .subject(message.get_email_subject())
+            .header(ContentType::TEXT_PLAIN);
+        for recipient in recipients {
+            builder = builder.to(NotificationService::get_mailbox(recipient)?);
+        }
+        let email = builder
+            .body(message.get_email_body())
+            .map_err(|err| ApplicationError::new(&format!("Error creating email: {err}")))?;
+        match mailer.send(email).await {
+            Ok(_) => {
+                debug!("Notification email sent to: {:?}", recipients);
+                Ok(())
+            }
+            Err(err) => {
+                error!("Error sending notification email: {err}");
+                Err(ApplicationError::new(&format!("Error sending notification email: {err}")))
+            }
+        }
+    }
+
+    /**
+     * Parse an email address.
+     *
+     * `address`: The address.
+     *
+     * Returns: The mailbox.
+     *
+     * Errors:
+     * - If the address could not be parsed.
+     */
+    fn get_mailbox(address: &str) -> Result<Mailbox, ApplicationError> {
+        address
+            .parse::<Mailbox>()
+            .map_err(|err| ApplicationError::new(&format!("Invalid email address {address}: {err}")))
+    }
+
+    /**
+     * Send the message to a webhook. Retries the request if it fails.
+     *
+     * `client`: The http client.
+     * `webhook`: The webhook configuration.
+     * `message`: The message to send.
+     *
+     * Returns: Ok if the webhook received the message.
+     *
+     * Errors:
+     * - If all attempts to send the message failed.
+     *
+     */
+    async fn send_webhook(client: &reqwest::Client, webhook: &WebhookConfig, message: &StatusTransitionMessage) -> Result<(), ApplicationError> {
+        let mut attempt: u32 = 0;
+        loop {
+            match NotificationService::post_webhook(client, webhook, message).await {
+                Ok(()) => {
+                    debug!("Notification sent to webhook: {}", &webhook.url);
+                    return Ok(());
+                }
+                Err(err) if attempt < webhook.retries => {
+                    attempt += 1;
+                    warn!("Error sending notification to webhook {}, retry {} of {}: {}", &webhook.url, attempt, webhook.retries, err.get_message());
+                    tokio::time::sleep(Duration::from_secs(webhook.retry_delay)).await;
+                }
+                Err(err) => {
+                    error!("Error sending notification to webhook {}: {}", &webhook.url, err.get_message());
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /**
+     * Post the message to a webhook.
+     *
+     * `client`: The http client.
+     * `webhook`: The webhook configuration.
+     * `message`: The message to send.
+     *
+     * Returns: Ok if the webhook responded with a success status code.
+     *
+     * Errors:
+     * - If the message could not be serialized.
+     * - If the request failed or timed out.
+     * - If the webhook responded with a non success status code.
+     *
+     */
+    async fn post_webhook(client: &reqwest::Client, webhook: &WebhookConfig, message: &StatusTransitionMessage) -> Result<(), ApplicationError> {
+        let body = serde_json::to_string(message)
+            .map_err(|err| ApplicationError::new(&format!("Error serializing notification: {err}")))?;
+        let response = client
+            .post(&webhook.url)
+            .headers(HttpMonitor::get_headers(&webhook.headers)?)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .timeout(Duration::from_secs(webhook.timeout))
+            .send()
+            .await
+            .map_err(|err| ApplicationError::new(&format!("Error posting notification: {err}")))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApplicationError::new(&format!("Unexpected response status: {}", response.status())))
+        }
+    }
+
+    /**
+     * Get the name of a status.
+     *
+     * `status`: The status.
+     *
+     * Returns: The name of the status.
+     */
+    fn get_status_name(status: &Status) -> &'static str {
+        match status {
+            Status::Ok => "Ok",
+            Status::Unknown => "Unknown",
+            Status::Warning { .. } => "Warning",
+            Status::Error { .. } => "Error",
+            Status::Flapping { .. } => "Flapping",
+            Status::Paused => "Paused",
+        }
+    }
+
+    /**
+     * Get the message of a status.
+     *
+     * `status`: The status.
+     *
+     * Returns: The message of the status if any.
+     */
+    fn get_status_message(status: &Status) -> Option<String> {
+        match status {
+            Status::Warning { message } | Status::Error { message } | Status::Flapping { message } => Some(message.clone()),
+            Status::Ok | Status::Unknown | Status::Paused => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+
+    /**
+     * Start a local webhook stand-in. Responds to each request with the next status code given.
+     *
+     * `status_codes`: The status codes to respond with.
+     *
+     * Returns: The url of the webhook and a receiver for the received requests.
+     */
+    async fn start_webhook_server(status_codes: Vec<u16>) -> (String, UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            for status_code in status_codes {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut stream).await;
+                sender.send(request).unwrap();
+                let response = format!("HTTP/1.1 {status_code} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    /**
+     * Read a full http request from the stream.
+     */
+    async fn read_request(stream: &mut TcpStream) -> String {
+        let mut data = Vec::new();
+        let mut buffer = [0u8; 1024];
+        loop {
+            let read = stream.read(&mut buffer).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&buffer[..read]);
+            let request = String::from_utf8_lossy(&data).to_string();
+            if let Some(header_end) = request.find("\r\n\r\n") {
+                let content_length = request[..header_end]
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if data.len() >= header_end + 4 + content_length {
+                    return request;
+                }
+            }
+        }
+        String::from_utf8_lossy(&data).to_string()
+    }
+
+    /**
+     * Start a local smtp stand-in. Accepts a single session and returns the lines received from the client.
+     *
+     * Returns: The port of the smtp server and a receiver for the session transcript.
+     */
+    async fn start_smtp_server() -> (u16, UnboundedReceiver<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+            writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            let mut transcript = Vec::new();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                transcript.push(line.clone());
+                if in_data {
+                    if line == "." {
+                        in_data = false;
+                        writer.write_all(b"250 Ok\r\n").await.unwrap();
+                    }
+                    continue;
+                }
+                let command = line.to_uppercase();
+                if command.starts_with("EHLO") {
+                    writer.write_all(b"250-localhost\r\n250 AUTH PLAIN LOGIN\r\n").await.unwrap();
+                } else if command.starts_with("AUTH") {
+                    writer.write_all(b"235 Authentication successful\r\n").await.unwrap();
+                } else if command.starts_with("DATA") {
+                    in_data = true;
+                    writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 Ok\r\n").await.unwrap();
+                }
+            }
+            sender.send(transcript).unwrap();
+        });
+        (port, receiver)
+    }
+
+    fn get_email_config(port: u16, username: Option<String>) -> EmailConfig {
+        EmailConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            security: SmtpSecurity::None,
+            username,
+            password: Some("password".to_string()),
+            from: "agent@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            timeout: 5,
+            accept_invalid_certs: false,
+        }
+    }
+
+    fn get_webhook(url: &str, retries: u32) -> WebhookConfig {
+        WebhookConfig {
+            url: url.to_string(),
+            headers: Some(HashMap::from([("Authorization".to_string(), "Bearer token".to_string())])),
+            timeout: 5,
+            retries,
+            retry_delay: 0,
+        }
+    }
+
+    #[test]
+    fn test_is_transition() {
+        let error = Status::Error { message: "error".to_string() };
+        let warning = Status::Warning { message: "warning".to_string() };
+        assert!(!NotificationService::is_transition(&Status::Unknown, &Status::Ok));
+        assert!(!NotificationService::is_transition(&Status::Ok, &Status::Ok));
+        assert!(!NotificationService::is_transition(&error, &Status::Error { message: "other error".to_string() }));
+        assert!(NotificationService::is_transition(&Status::Unknown, &error));
+        assert!(NotificationService::is_transition(&Status::Ok, &error));
+        assert!(NotificationService::is_transition(&error, &Status::Ok));
+        assert!(NotificationService::is_transition(&warning, &error));
+    }
+
+    #[test]
+    fn test_status_transition_message() {
+        let message = StatusTransitionMessage::new("server", "monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!("server", json["serverName"]);
+        assert_eq!("monitor", json["monitorName"]);
+        assert_eq!("Ok", json["oldStatus"]);
+        assert_eq!("Error", json["newStatus"]);
+        assert_eq!("error", json["message"]);
+        assert!(json["time"].is_string());
+        let message = StatusTransitionMessage::new("server", "monitor", &Status::Error { message: "error".to_string() }, &Status::Ok);
+        let json = serde_json::to_value(&message).unwrap();
+        assert!(json.get("message").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook() {
+        let (url, mut receiver) = start_webhook_server(vec![200]).await;
+        let message = StatusTransitionMessage::new("server", "test_monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        let result = NotificationService::send_webhook(&reqwest::Client::new(), &get_webhook(&url, 0), &message).await;
+        assert!(result.is_ok());
+        let request = receiver.recv().await.unwrap();
+        assert!(request.starts_with("POST /hook"));
+        assert!(request.to_lowercase().contains("authorization: bearer token"));
+        assert!(request.to_lowercase().contains("content-type: application/json"));
+        assert!(request.contains("\"monitorName\":\"test_monitor\""));
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_retry() {
+        let (url, mut receiver) = start_webhook_server(vec![500, 200]).await;
+        let message = StatusTransitionMessage::new("server", "test_monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        let result = NotificationService::send_webhook(&reqwest::Client::new(), &get_webhook(&url, 1), &message).await;
+        assert!(result.is_ok());
+        assert!(receiver.recv().await.is_some());
+        assert!(receiver.recv().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook_failed() {
+        let (url, _receiver) = start_webhook_server(vec![500, 500]).await;
+        let message = StatusTransitionMessage::new("server", "test_monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        let result = NotificationService::send_webhook(&reqwest::Client::new(), &get_webhook(&url, 1), &message).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let (url, mut receiver) = start_webhook_server(vec![200]).await;
+        let notification_config = NotificationConfig { webhooks: vec![get_webhook(&url, 0)], email: None };
+        let notification_service = NotificationService::new(&notification_config, "server", &[]).unwrap();
+        notification_service.notify("test_monitor", &Status::Error { message: "error".to_string() }, &Status::Ok);
+        let request = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await.unwrap().unwrap();
+        assert!(request.contains("\"oldStatus\":\"Error\""));
+        assert!(request.contains("\"newStatus\":\"Ok\""));
+    }
+
+    #[test]
+    fn test_email_subject_and_body() {
+        let message = StatusTransitionMessage::new("server", "monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        assert_eq!("[server] monitor changed status to Error", message.get_email_subject());
+        assert!(message.get_email_body().contains("Old status: Ok\nNew status: Error\nMessage: error\n"));
+        let message = StatusTransitionMessage::new("server", "monitor", &Status::Error { message: "error".to_string() }, &Status::Ok);
+        assert_eq!("[server] monitor recovered", message.get_email_subject());
+        assert!(!message.get_email_body().contains("Message:"));
+    }
+
+    #[test]
+    fn test_get_mailer() {
+        let mut email_config = get_email_config(25, None);
+        assert!(NotificationService::get_mailer(&email_config).is_ok());
+        email_config.security = SmtpSecurity::StartTls;
+        assert!(NotificationService::get_mailer(&email_config).is_ok());
+        email_config.security = SmtpSecurity::Tls;
+        assert!(NotificationService::get_mailer(&email_config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_email() {
+        let (port, mut receiver) = start_smtp_server().await;
+        let mailer = NotificationService::get_mailer(&get_email_config(port, None)).unwrap();
+        let message = StatusTransitionMessage::new("server", "test_monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        let result = NotificationService::send_email(&mailer, "agent@example.com", &["ops@example.com".to_string()], &message).await;
+        assert!(result.is_ok());
+        let transcript = receiver.recv().await.unwrap();
+        assert!(transcript.contains(&"MAIL FROM:<agent@example.com>".to_string()));
+        assert!(transcript.contains(&"RCPT TO:<ops@example.com>".to_string()));
+        assert!(transcript.contains(&"Subject: [server] test_monitor changed status to Error".to_string()));
+        assert!(!transcript.iter().any(|line| line.starts_with("AUTH")));
+    }
+
+    #[tokio::test]
+    async fn test_send_email_with_authentication() {
+        let (port, mut receiver) = start_smtp_server().await;
+        let mailer = NotificationService::get_mailer(&get_email_config(port, Some("user".to_string()))).unwrap();
+        let message = StatusTransitionMessage::new("server", "test_monitor", &Status::Error { message: "error".to_string() }, &Status::Ok);
+        let result = NotificationService::send_email(&mailer, "agent@example.com", &["ops@example.com".to_string()], &message).await;
+        assert!(result.is_ok());
+        let transcript = receiver.recv().await.unwrap();
+        assert!(transcript.iter().any(|line| line.starts_with("AUTH PLAIN")));
+        assert!(transcript.contains(&"Subject: [server] test_monitor recovered".to_string()));
+    }
+
+    #[test]
+    fn test_new_invalid_config() {
+        let mut webhook = get_webhook("http://127.0.0.1:9000/hook", 0);
+        webhook.headers = Some(HashMap::from([("Invalid Header".to_string(), "value".to_string())]));
+        let mut email_config = get_email_config(25, None);
+        email_config.from = "not an address".to_string();
+        let notification_config = NotificationConfig { webhooks: vec![webhook], email: Some(email_config) };
+        let err = NotificationService::new(&notification_config, "server", &[]).unwrap_err();
+        let message = err.get_message();
+        let errors: Vec<&str> = message.lines().collect();
+        assert_eq!(2, errors.len(), "{errors:?}");
+        assert!(errors[0].starts_with("Webhook: "));
+        assert!(errors[1].starts_with("Email: Invalid email address not an address"));
+    }
+
+    #[tokio::test]
+    async fn test_notify_email_routing() {
+        let (port, mut receiver) = start_smtp_server().await;
+        let notification_config = NotificationConfig { webhooks: vec![], email: Some(get_email_config(port, None)) };
+        let monitors = vec![Monitor {
+            name: "test_monitor".to_string(),
+            schedule: "* * * * * *".to_string(),
+            details: crate::common::MonitorType::Tcp { host: "127.0.0.1".to_string(), port: 80, max_response_time: None },
+            store: crate::common::configuration::DatabaseStoreLevel::None,
+            email_recipients: Some(vec!["db@example.com".to_string()]),
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
+        }];
+        let notification_service = NotificationService::new(&notification_config, "server", &monitors).unwrap();
+        notification_service.notify("test_monitor", &Status::Ok, &Status::Error { message: "error".to_string() });
+        let transcript = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await.unwrap().unwrap();
+        assert!(transcript.contains(&"RCPT TO:<db@example.com>".to_string()));
+        assert!(!transcript.contains(&"RCPT TO:<ops@example.com>".to_string()));
+    }
+}
@@ -1,11 +1,12 @@
 use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
 
-use log::info;
+use log::{error, info, warn};
 use tokio_cron_scheduler::{Job, JobScheduler};
+use uuid::Uuid;
 
-use crate::common::{configuration::MonitoringConfig, ApplicationError, MonitorStatus};
-use crate::services::DbService;
-use super::monitors::{CommandMonitor, HttpMonitor, LoadAvgMonitor, MeminfoMonitor, SystemctlMonitor, TcpMonitor, DatabaseMonitor};
+use crate::common::{configuration::MonitoringConfig, ApplicationError, ConfigFormat, MonitorStatus};
+use crate::services::{DbService, NotificationService, SdNotifyService};
+use super::monitors::{CertificateMonitor, CommandMonitor, CpuMonitor, DiskMonitor, DnsMonitor, ProcessMonitor, HttpMonitor, LoadAvgMonitor, MeminfoMonitor, SystemctlMonitor, TcpMonitor, DatabaseMonitor, ScheduledMonitor, RunningChecks, MonitorSchedule};
 
 /**
  * Scheduling Service.
@@ -16,7 +17,12 @@ use super::monitors::{CommandMonitor, HttpMonitor, LoadAvgMonitor, MeminfoMonito
  * `monitoring_config`: The monitoring configuration.
  * `status`: The status of the monitors.
  * `database_service`: The database service.
+ * `notification_service`: The notification service.
  * `server_name`: The server name.
+ * `monitors`: The monitors added to the scheduler.
+ * `jobs`: The ids of the scheduled jobs.
+ * `running_checks`: The running checks, scheduled or on demand.
+ * `sd_notify_service`: The systemd notify service, once systemd is notified that the agent is ready.
  * 
  */
 pub struct SchedulingService {
@@ -28,8 +34,18 @@ pub struct SchedulingService {
     status: Arc<Mutex<HashMap<String, MonitorStatus>>>,
     /// The database service.
     database_service: Arc<Option<DbService>>,
+    /// The notification service.
+    notification_service: Arc<Option<NotificationService>>,
     /// The server name.
     server_name: String,
+    /// The monitors added to the scheduler.
+    monitors: Arc<Mutex<HashMap<String, ScheduledMonitor>>>,
+    /// The ids of the scheduled jobs. Paused monitors have no scheduled job.
+    jobs: HashMap<String, Uuid>,
+    /// The running checks, scheduled or on demand. Used to let the checks finish on shutdown.
+    running_checks: RunningChecks,
+    /// The systemd notify service. Set when systemd is notified that the agent is ready, and used to update the status.
+    sd_notify_service: Option<SdNotifyService>,
 }
 
 impl SchedulingService {
@@ -39,13 +55,18 @@ impl SchedulingService {
      *
      * result: The result of creating the scheduling service.
      */
-    pub fn new(server_name: &str, monitoring_config: &MonitoringConfig, status: &Arc<Mutex<HashMap<String, MonitorStatus>>>, database_service: &Arc<Option<DbService>>) -> SchedulingService {
+    pub fn new(server_name: &str, monitoring_config: &MonitoringConfig, status: &Arc<Mutex<HashMap<String, MonitorStatus>>>, database_service: &Arc<Option<DbService>>, notification_service: &Arc<Option<NotificationService>>, monitors: &Arc<Mutex<HashMap<String, ScheduledMonitor>>>) -> SchedulingService {
         SchedulingService {
             scheduler: None,
             monitoring_config: monitoring_config.clone(),
             status: status.clone(),
             database_service: database_service.clone(),
+            notification_service: notification_service.clone(),
             server_name: server_name.to_string(),
+            monitors: monitors.clone(),
+            jobs: HashMap::new(),
+            running_checks: RunningChecks::new(),
+            sd_notify_service: None,
         }
     }
 
@@ -58,15 +79,11 @@ impl SchedulingService {
      */
     pub async fn start(&mut self, test: bool) -> Result<(), ApplicationError> {       
         /*
-         * Start the scheduling of the monitoring jobs.
-         */
-        let future_scheduling = self.add_jobs();
-        /*
-         * Block the main thread until the scheduling is done. If test is true, the scheduling will be ignored.
+         * Start the scheduling of the monitoring jobs. If test is true, the scheduling will be ignored.
          * This is useful for testing the configuration file and for testing the code.
          */
         if !test {
-            future_scheduling.await?;
+            self.add_jobs().await?;
         }
         Ok(())
     }
@@ -115,10 +132,7 @@ impl SchedulingService {
                 ));
             }
         }
-        loop {
-            info!("Scheduler is awake");
-            tokio::time::sleep(Duration::from_secs(300)).await;
-        }
+        Ok(())
     }
 
     /**
@@ -136,12 +150,36 @@ impl SchedulingService {
         monitor: &crate::common::Monitor,
         scheduler: &JobScheduler,        
     ) -> Result<(), ApplicationError> {
+        let notification_service = self.notification_service.clone();
+        let (scheduled_monitor, job, schedule) = self.create_job(monitor, &notification_service).await?;
+        self.add_monitor_job(monitor, scheduler, scheduled_monitor, job, &schedule).await
+    }
+
+    /**
+     * Create the monitor and its job, without adding the job to the scheduler. Creating the monitor resets its
+     * status.
+     *
+     * `monitor`: The monitor configuration.
+     * `notification_service`: The notification service used by the monitor.
+     *
+     * `result`: The monitor, the job and the schedule of the monitor.
+     *
+     * throws: `ApplicationError`: If the monitor or the job fails to be created.
+     */
+    async fn create_job(
+        &self,
+        monitor: &crate::common::Monitor,
+        notification_service: &Arc<Option<NotificationService>>,
+    ) -> Result<(ScheduledMonitor, Job, MonitorSchedule), ApplicationError> {
+        /*
+         * The schedule is either a cron expression or an interval, optionally with a random jitter.
+         */
+        let schedule = MonitorSchedule::new(&monitor.schedule, monitor.jitter.as_deref(), monitor.timeout.as_deref())?;
         let monitor_type = monitor.details.clone();
-        match monitor_type {
-            crate::common::MonitorType::Tcp { host, port } => {
-                let mut tcp_monitor = TcpMonitor::new(host.as_str(), port, &monitor.name, &self.status.clone(), &self.database_service.clone(), &monitor.store);
-                let job = tcp_monitor.get_tcp_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+        let mut scheduled_monitor = match monitor_type {
+            crate::common::MonitorType::Tcp { host, port, max_response_time } => {
+                let tcp_monitor = TcpMonitor::new(host.as_str(), port, max_response_time, &monitor.name, &self.status.clone(), &self.database_service.clone(), notification_service, &monitor.store);
+                ScheduledMonitor::Tcp(tcp_monitor)
             },
             crate::common::MonitorType::Http {
                 url,
@@ -154,12 +192,14 @@ impl SchedulingService {
                 root_certificate,
                 identity,
                 identity_password,
+                max_response_time,
             } => { 
                 let mut http_monitor = HttpMonitor::new(
                     url.as_str(),
                     method,
                     &body,
                     &headers,
+                    max_response_time,
                     &monitor.name,
                     use_builtin_root_certs,
                     accept_invalid_certs,
@@ -169,58 +209,592 @@ impl SchedulingService {
                     identity_password,
                     &self.status,
                     &self.database_service.clone(),
+                    notification_service,
                     &monitor.store,
                 )?;
-                let job = http_monitor.get_http_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+                http_monitor.set_request_timeout(schedule.get_timeout());
+                ScheduledMonitor::Http(http_monitor)
             },
             crate::common::MonitorType::Command {
                 command,
                 args,
                 expected,
             } => {
-                let mut command_monitor = CommandMonitor::new(&monitor.name, command.as_str(), args, expected, &self.status, &self.database_service.clone(), &monitor.store);
-                let job = command_monitor.get_command_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+                let command_monitor = CommandMonitor::new(&monitor.name, command.as_str(), args, expected, &self.status, &self.database_service.clone(), notification_service, &monitor.store);
+                ScheduledMonitor::Command(command_monitor)
             },
             crate::common::MonitorType::LoadAvg {  
                 threshold_1min,
                 threshold_5min,
                 threshold_10min,
+                warning_threshold_1min,
+                warning_threshold_5min,
+                warning_threshold_10min,
                 store_values,
             } => {               
-                let mut loadavg_monitor = LoadAvgMonitor::new(&monitor.name, threshold_1min, threshold_5min, threshold_10min, &self.status, &self.database_service.clone(), &monitor.store, store_values);
-                let job = loadavg_monitor.get_loadavg_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+                let loadavg_monitor = LoadAvgMonitor::new(&monitor.name, threshold_1min, threshold_5min, threshold_10min, warning_threshold_1min, warning_threshold_5min, warning_threshold_10min, &self.status, &self.database_service.clone(), notification_service, &monitor.store, store_values);
+                ScheduledMonitor::LoadAvg(loadavg_monitor)
+            },
+            crate::common::MonitorType::Mem {max_percentage_mem, max_percentage_swap, warning_percentage_mem, warning_percentage_swap, store_values
+            } => {
+                let meminfo_monitor = MeminfoMonitor::new(&monitor.name, max_percentage_mem, max_percentage_swap, warning_percentage_mem, warning_percentage_swap, &self.status, &self.database_service.clone(), notification_service, &monitor.store, store_values);
+                ScheduledMonitor::Mem(meminfo_monitor)
+            },
+            crate::common::MonitorType::Disk { mount_points, max_percentage_used, max_percentage_inodes_used, warning_percentage_used, warning_percentage_inodes_used, store_values
+            } => {
+                let disk_monitor = DiskMonitor::new(&monitor.name, mount_points, max_percentage_used, max_percentage_inodes_used, warning_percentage_used, warning_percentage_inodes_used, &self.status, &self.database_service.clone(), notification_service, &monitor.store, store_values);
+                ScheduledMonitor::Disk(disk_monitor)
+            },
+            crate::common::MonitorType::Cpu { max_percentage_used, warning_percentage_used, max_percentage_iowait, warning_percentage_iowait, max_percentage_steal, warning_percentage_steal, per_core, store_values
+            } => {
+                let cpu_monitor = CpuMonitor::new(&monitor.name, max_percentage_used, warning_percentage_used, max_percentage_iowait, warning_percentage_iowait, max_percentage_steal, warning_percentage_steal, per_core, &self.status, &self.database_service.clone(), notification_service, &monitor.store, store_values);
+                ScheduledMonitor::Cpu(cpu_monitor)
             },
-            crate::common::MonitorType::Mem {max_percentage_mem, max_percentage_swap, store_values
+            crate::common::MonitorType::Certificate { host, port, server_name, files, root_certificate, accept_invalid_certs, warning_days, error_days
             } => {
-                let mut meminfo_monitor = MeminfoMonitor::new(&monitor.name, max_percentage_mem, max_percentage_swap, &self.status, &self.database_service.clone(), &monitor.store, store_values);
-                let job = meminfo_monitor.get_meminfo_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+                let certificate_monitor = CertificateMonitor::new(&monitor.name, host, port, server_name, files, root_certificate, accept_invalid_certs, warning_days, error_days, &self.status, &self.database_service.clone(), notification_service, &monitor.store);
+                ScheduledMonitor::Certificate(certificate_monitor)
+            },
+            crate::common::MonitorType::Dns { name, record_type, nameserver, expected, max_response_time
+            } => {
+                let dns_monitor = DnsMonitor::new(&monitor.name, &name, record_type, nameserver, expected, max_response_time, &self.status, &self.database_service.clone(), notification_service, &monitor.store);
+                ScheduledMonitor::Dns(dns_monitor)
+            },
+            crate::common::MonitorType::Process { name, command_line, min_processes, max_processes, max_threads, forbidden_states
+            } => {
+                let process_monitor = ProcessMonitor::new(&monitor.name, name, command_line, min_processes, max_processes, max_threads, forbidden_states, &self.status, &self.database_service.clone(), notification_service, &monitor.store);
+                ScheduledMonitor::Process(process_monitor)
             },
             crate::common::MonitorType::Systemctl { active 
             } => {
-                let mut systemctl_monitor = SystemctlMonitor::new(&monitor.name, &self.status, &self.database_service.clone(), &monitor.store, active);
-                let job = systemctl_monitor.get_systemctl_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+                let systemctl_monitor = SystemctlMonitor::new(&monitor.name, &self.status, &self.database_service.clone(), notification_service, &monitor.store, active);
+                ScheduledMonitor::Systemctl(systemctl_monitor)
             },
             crate::common::MonitorType::Database {database_config, max_query_time,
             } => {
-                let mut database_monitor = DatabaseMonitor::new(
+                let database_monitor = DatabaseMonitor::new(
                     &monitor.name,
                     max_query_time,
                     &self.status,
                     &self.get_database_service(&self.database_service, &database_config).await?,
+                    notification_service,
                     &monitor.store,
                 );
-                let job = database_monitor.get_database_monitor_job(monitor.schedule.as_str())?;
-                self.add_job(scheduler, job).await
+                ScheduledMonitor::Database(database_monitor)
             },
-        }?;   
+        };
+        let job = scheduled_monitor.get_job(&schedule, &self.running_checks)?;
+        Ok((scheduled_monitor, job, schedule))
+    }
+
+    /**
+     * Add the job of a created monitor to the scheduler and register the monitor.
+     *
+     * `monitor`: The monitor configuration.
+     * `scheduler`: The job scheduler.
+     * `scheduled_monitor`: The created monitor.
+     * `job`: The job of the monitor.
+     * `schedule`: The schedule of the monitor.
+     *
+     * `result`: The result of adding the job to the scheduler.
+     *
+     * throws: `ApplicationError`: If the job fails to be added.
+     */
+    async fn add_monitor_job(
+        &mut self,
+        monitor: &crate::common::Monitor,
+        scheduler: &JobScheduler,
+        scheduled_monitor: ScheduledMonitor,
+        job: Job,
+        schedule: &MonitorSchedule,
+    ) -> Result<(), ApplicationError> {
+        let job_id = self.add_job(scheduler, job).await?;
+        self.jobs.insert(monitor.name.clone(), job_id);
+        self.register_monitor(&monitor.name, scheduled_monitor);
+        self.configure_monitor_status(monitor, schedule);
         Ok(()) 
     }
 
+    /**
+     * Add a job sending watchdog pings and the monitor status summary to systemd. The job runs in the scheduler,
+     * so systemd restarts the agent when the scheduler stops running jobs.
+     *
+     * `sd_notify_service`: The systemd notify service.
+     *
+     * `result`: The result of adding the watchdog job. Nothing is added if the watchdog is not enabled.
+     *
+     * throws: `ApplicationError`: If the scheduler is not started or the job fails to be added.
+     */
+    pub async fn add_watchdog_job(&mut self, sd_notify_service: &SdNotifyService) -> Result<(), ApplicationError> {
+        let Some(interval) = sd_notify_service.get_watchdog_interval() else {
+            return Ok(());
+        };
+        let scheduler = self.get_scheduler()?;
+        let sd_notify_service = sd_notify_service.clone();
+        let status = self.status.clone();
+        let job = Job::new_repeated_async(interval, move |_uuid, _locked| {
+            let sd_notify_service = sd_notify_service.clone();
+            let status = status.clone();
+            Box::pin(async move {
+                let summary = match status.lock() {
+                    Ok(status_lock) => SdNotifyService::get_status_summary(&status_lock),
+                    Err(err) => {
+                        error!("Error getting monitor status: {:?}", err);
+                        return;
+                    }
+                };
+                if let Err(err) = sd_notify_service.watchdog(&summary) {
+                    error!("Error sending watchdog ping: {:?}", err);
+                }
+            })
+        }).map_err(|err| ApplicationError::new(
+            format!("Could not create watchdog job: {err}").as_str(),
+        ))?;
+        self.add_job(&scheduler, job).await?;
+        info!("Watchdog pings every {} ms", interval.as_millis());
+        Ok(())
+    }
+
+    /**
+     * Notify systemd that the agent is ready. The scheduler must be started, and the watchdog job is added first,
+     * so systemd is only told the agent is ready when the checks and the watchdog pings are running. Later changes
+     * of the monitors are sent to systemd as status updates.
+     *
+     * `sd_notify_service`: The systemd notify service.
+     *
+     * `result`: The result of notifying systemd.
+     *
+     * throws: `ApplicationError`: If the scheduler is not started, the watchdog job fails to be added or the
+     * notification fails to be sent.
+     */
+    pub async fn notify_ready(&mut self, sd_notify_service: &SdNotifyService) -> Result<(), ApplicationError> {
+        self.get_scheduler()?;
+        self.add_watchdog_job(sd_notify_service).await?;
+        sd_notify_service.ready(&self.get_status_summary())?;
+        self.sd_notify_service = Some(sd_notify_service.clone());
+        Ok(())
+    }
+
+    /**
+     * Send the summary of the monitor statuses to systemd. Nothing is sent before systemd is notified that the
+     * agent is ready.
+     */
+    fn notify_status(&self) {
+        let Some(sd_notify_service) = &self.sd_notify_service else {
+            return;
+        };
+        if let Err(err) = sd_notify_service.status(&self.get_status_summary()) {
+            error!("Error notifying systemd: {:?}", err);
+        }
+    }
+
+    /**
+     * Get a summary of the monitor statuses.
+     *
+     * `result`: The summary, e.g. "3 monitors: 2 ok, 1 error".
+     */
+    pub fn get_status_summary(&self) -> String {
+        match self.status.lock() {
+            Ok(status_lock) => SdNotifyService::get_status_summary(&status_lock),
+            Err(err) => {
+                error!("Error getting monitor status: {:?}", err);
+                String::new()
+            }
+        }
+    }
+
+    /**
+     * Shut down the scheduling. The jobs are removed, the scheduler is stopped and the checks still waiting out their
+     * jitter are dropped, then the running checks are given until the deadline to finish. Finally the registered monitors and the database service are released, so the
+     * connection pools are closed when the last reference is dropped.
+     *
+     * `deadline`: The maximum time to wait for the running checks.
+     *
+     * `result`: True if all running checks finished before the deadline.
+     *
+     * throws: `ApplicationError`: If the scheduler fails to shut down.
+     */
+    pub async fn shutdown(&mut self, deadline: Duration) -> Result<bool, ApplicationError> {
+        if let Some(mut scheduler) = self.scheduler.take() {
+            info!("Stopping scheduler");
+            for (name, job_id) in self.jobs.drain() {
+                if let Err(err) = scheduler.remove(&job_id).await {
+                    error!("Could not remove job for monitor {}: {err}", name);
+                }
+            }
+            scheduler.shutdown().await.map_err(|err| ApplicationError::new(
+                format!("Could not shut down scheduler: {err}").as_str(),
+            ))?;
+        }
+        self.running_checks.stop();
+        let finished = self.running_checks.wait(deadline).await;
+        if !finished {
+            warn!("{} checks still running after {} seconds", self.running_checks.count(), deadline.as_secs());
+        }
+        match self.monitors.lock() {
+            Ok(mut monitors_lock) => {
+                monitors_lock.clear();
+            }
+            Err(err) => {
+                error!("Error removing monitors: {:?}", err);
+            }
+        }
+        self.database_service = Arc::new(None);
+        Ok(finished)
+    }
+
+    /**
+     * Reload the configuration from file.
+     *
+     * `config_path`: The path to the configuration file.
+     * `config_format`: The format of the configuration file. If not given, the format is detected from the file extension.
+     *
+     * `result`: The result of reloading the configuration.
+     *
+     * throws: `ApplicationError`: If the configuration is invalid or the jobs fail to be changed.
+     */
+    pub async fn reload_config(&mut self, config_path: &str, config_format: Option<ConfigFormat>) -> Result<(), ApplicationError> {
+        let monitoring_config = MonitoringConfig::new(config_path, config_format)?;
+        self.reload(&monitoring_config).await
+    }
+
+    /**
+     * Reload the monitors. Only the jobs of added, removed or changed monitors are changed in the scheduler.
+     * The status of unchanged monitors is kept. The notification service is created again from the new configuration,
+     * and all monitors are changed if the notification configuration changed. The added and changed monitors are
+     * created before any job is changed, so the running monitors are left unchanged if one fails to be created.
+     * Changes to the database configuration are rejected, as the monitors share the database service created on
+     * startup.
+     *
+     * `monitoring_config`: The new monitoring configuration.
+     *
+     * `result`: The result of reloading the monitors.
+     *
+     * throws: `ApplicationError`: If the configuration is invalid, the database configuration changed, a monitor
+     * fails to be created or the jobs fail to be changed.
+     */
+    pub async fn reload(&mut self, monitoring_config: &MonitoringConfig) -> Result<(), ApplicationError> {
+        monitoring_config.validate()?;
+        if monitoring_config.database != self.monitoring_config.database {
+            return Err(ApplicationError::new("The database configuration changed, restart the agent to apply it"));
+        }
+        let scheduler = self.get_scheduler()?;
+        /*
+         * Create the notification service before changing any monitor, so an invalid notification configuration
+         * leaves the running monitors unchanged. Monitors keep the notification service they were created with,
+         * so the added and changed monitors get the new one with the new email recipients.
+         */
+        let notification_service = Arc::new(match &monitoring_config.notifications {
+            Some(notification_config) => Some(NotificationService::new(notification_config, &self.server_name, &monitoring_config.monitors)?),
+            None => None,
+        });
+        let notifications_changed = monitoring_config.notifications != self.monitoring_config.notifications;
+        let old_monitors = self.monitoring_config.monitors.clone();
+        let is_unchanged = |monitor: &crate::common::Monitor, monitors: &[crate::common::Monitor]| !notifications_changed && monitors.contains(monitor);
+        /*
+         * Create the added and changed monitors. Creating a monitor resets its status, so the old statuses are
+         * restored if any monitor fails to be created.
+         */
+        let new_monitors: Vec<&crate::common::Monitor> = monitoring_config.monitors.iter()
+            .filter(|monitor| !is_unchanged(monitor, &old_monitors))
+            .collect();
+        let old_statuses = self.get_monitor_statuses(&new_monitors);
+        let mut created = Vec::new();
+        let mut errors = Vec::new();
+        for monitor in &new_monitors {
+            match self.create_job(monitor, &notification_service).await {
+                Ok(created_job) => created.push((*monitor, created_job)),
+                Err(err) => errors.push(format!("{}: {}", &monitor.name, err.get_message())),
+            }
+        }
+        if !errors.is_empty() {
+            self.restore_monitor_statuses(&new_monitors, old_statuses);
+            return Err(ApplicationError::new(
+                format!("Could not create monitors: {}", errors.join(", ")).as_str(),
+            ));
+        }
+        self.notification_service = notification_service;
+        /*
+         * Remove the removed monitors and the jobs of the changed monitors. The changed monitors keep the status
+         * of the created monitors.
+         */
+        for old_monitor in &old_monitors {
+            if is_unchanged(old_monitor, &monitoring_config.monitors) {
+                continue;
+            }
+            if monitoring_config.monitors.iter().any(|monitor| monitor.name == old_monitor.name) {
+                self.remove_job(&scheduler, &old_monitor.name).await?;
+            } else {
+                self.remove_monitor(&scheduler, &old_monitor.name).await?;
+            }
+        }
+        /*
+         * Add the jobs of the added and changed monitors. Continue with the other monitors if one fails.
+         */
+        for (monitor, (scheduled_monitor, job, schedule)) in created {
+            info!("Adding monitor: {}", &monitor.name);
+            if let Err(err) = self.add_monitor_job(monitor, &scheduler, scheduled_monitor, job, &schedule).await {
+                errors.push(format!("{}: {}", &monitor.name, err.get_message()));
+            }
+        }
+        self.monitoring_config = monitoring_config.clone();
+        self.notify_status();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApplicationError::new(
+                format!("Could not add monitors: {}", errors.join(", ")).as_str(),
+            ))
+        }
+    }
+
+    /**
+     * Get the current statuses of monitors.
+     *
+     * `monitors`: The monitor configurations.
+     *
+     * `result`: The statuses by monitor name. Monitors without a status are left out.
+     */
+    fn get_monitor_statuses(&self, monitors: &[&crate::common::Monitor]) -> HashMap<String, MonitorStatus> {
+        match self.status.lock() {
+            Ok(status_lock) => monitors.iter()
+                .filter_map(|monitor| status_lock.get(&monitor.name).map(|monitor_status| (monitor.name.clone(), monitor_status.clone())))
+                .collect(),
+            Err(err) => {
+                error!("Error getting monitor status: {:?}", err);
+                HashMap::new()
+            }
+        }
+    }
+
+    /**
+     * Restore the statuses of monitors. The statuses of monitors without an old status are removed.
+     *
+     * `monitors`: The monitor configurations.
+     * `old_statuses`: The old statuses by monitor name.
+     *
+     */
+    fn restore_monitor_statuses(&self, monitors: &[&crate::common::Monitor], mut old_statuses: HashMap<String, MonitorStatus>) {
+        match self.status.lock() {
+            Ok(mut status_lock) => {
+                for monitor in monitors {
+                    match old_statuses.remove(&monitor.name) {
+                        Some(monitor_status) => status_lock.insert(monitor.name.clone(), monitor_status),
+                        None => status_lock.remove(&monitor.name),
+                    };
+                }
+            }
+            Err(err) => {
+                error!("Error restoring monitor status: {:?}", err);
+            }
+        }
+    }
+
+    /**
+     * Remove a monitor. The job, the registered monitor and the status of the monitor are removed.
+     *
+     * `scheduler`: The job scheduler.
+     * `name`: The name of the monitor.
+     *
+     * `result`: The result of removing the monitor.
+     *
+     * throws: `ApplicationError`: If the job fails to be removed.
+     */
+    async fn remove_monitor(&mut self, scheduler: &JobScheduler, name: &str) -> Result<(), ApplicationError> {
+        info!("Removing monitor: {}", name);
+        self.remove_job(scheduler, name).await?;
+        match self.monitors.lock() {
+            Ok(mut monitors_lock) => {
+                monitors_lock.remove(name);
+            }
+            Err(err) => {
+                error!("Error removing monitor: {:?}", err);
+            }
+        }
+        match self.status.lock() {
+            Ok(mut status_lock) => {
+                status_lock.remove(name);
+            }
+            Err(err) => {
+                error!("Error removing monitor status: {:?}", err);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Remove the job of a monitor from the scheduler. Paused monitors have no job.
+     *
+     * `scheduler`: The job scheduler.
+     * `name`: The name of the monitor.
+     *
+     * `result`: The result of removing the job.
+     *
+     * throws: `ApplicationError`: If the job fails to be removed.
+     */
+    async fn remove_job(&mut self, scheduler: &JobScheduler, name: &str) -> Result<(), ApplicationError> {
+        if let Some(job_id) = self.jobs.remove(name) {
+            scheduler.remove(&job_id).await.map_err(|err| ApplicationError::new(
+                format!("Could not remove job: {err}").as_str(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Pause a monitor. The scheduled job of the monitor is removed and the status is set to paused.
+     *
+     * `name`: The name of the monitor.
+     *
+     * `result`: True if the monitor is paused, false if the monitor does not exist.
+     *
+     * throws: `ApplicationError`: If the job fails to be removed.
+     */
+    pub async fn pause_monitor(&mut self, name: &str) -> Result<bool, ApplicationError> {
+        let Some(job_id) = self.jobs.get(name).copied() else {
+            /*
+             * A registered monitor without a job is already paused.
+             */
+            return Ok(self.get_registered_monitor(name).is_some());
+        };
+        let scheduler = self.get_scheduler()?;
+        scheduler.remove(&job_id).await.map_err(|err| ApplicationError::new(
+            format!("Could not remove job: {err}").as_str(),
+        ))?;
+        self.jobs.remove(name);
+        self.change_monitor_status(name, MonitorStatus::pause);
+        self.notify_status();
+        info!("Monitor paused: {}", name);
+        Ok(true)
+    }
+
+    /**
+     * Resume a paused monitor. The job of the monitor is added to the scheduler again.
+     *
+     * `name`: The name of the monitor.
+     *
+     * `result`: True if the monitor is resumed, false if the monitor does not exist.
+     *
+     * throws: `ApplicationError`: If the job fails to be added.
+     */
+    pub async fn resume_monitor(&mut self, name: &str) -> Result<bool, ApplicationError> {
+        if self.jobs.contains_key(name) {
+            return Ok(true);
+        }
+        let Some(mut scheduled_monitor) = self.get_registered_monitor(name) else {
+            return Ok(false);
+        };
+        let Some(monitor) = self.monitoring_config.monitors.iter().find(|monitor| monitor.name == name) else {
+            return Ok(false);
+        };
+        let schedule = MonitorSchedule::new(&monitor.schedule, monitor.jitter.as_deref(), monitor.timeout.as_deref())?;
+        let job = scheduled_monitor.get_job(&schedule, &self.running_checks)?;
+        let scheduler = self.get_scheduler()?;
+        let job_id = self.add_job(&scheduler, job).await?;
+        self.jobs.insert(name.to_string(), job_id);
+        self.change_monitor_status(name, MonitorStatus::resume);
+        self.notify_status();
+        info!("Monitor resumed: {}", name);
+        Ok(true)
+    }
+
+    /**
+     * Get the running checks. Checks run outside of the schedule are marked as running, so shutdown waits for them.
+     *
+     * `result`: The running checks.
+     */
+    pub fn get_running_checks(&self) -> RunningChecks {
+        self.running_checks.clone()
+    }
+
+    /**
+     * Get the job scheduler.
+     *
+     * `result`: The job scheduler.
+     *
+     * throws: `ApplicationError`: If the scheduler is not started.
+     */
+    fn get_scheduler(&self) -> Result<JobScheduler, ApplicationError> {
+        self.scheduler.clone().ok_or_else(|| ApplicationError::new("Scheduler is not started"))
+    }
+
+    /**
+     * Get a registered monitor.
+     *
+     * `name`: The name of the monitor.
+     *
+     * `result`: The monitor, or None if the monitor is not registered.
+     */
+    fn get_registered_monitor(&self, name: &str) -> Option<ScheduledMonitor> {
+        match self.monitors.lock() {
+            Ok(monitors_lock) => monitors_lock.get(name).cloned(),
+            Err(err) => {
+                error!("Error getting monitor: {:?}", err);
+                None
+            }
+        }
+    }
+
+    /**
+     * Change the status of a monitor.
+     *
+     * `name`: The name of the monitor.
+     * `change`: The change to apply to the status.
+     *
+     */
+    fn change_monitor_status(&self, name: &str, change: fn(&mut MonitorStatus)) {
+        match self.status.lock() {
+            Ok(mut status_lock) => {
+                if let Some(monitor_status) = status_lock.get_mut(name) {
+                    change(monitor_status);
+                }
+            }
+            Err(err) => {
+                error!("Error changing monitor status: {:?}", err);
+            }
+        }
+    }
+
+    /**
+     * Register the monitor, making it possible to run the check outside of the schedule.
+     *
+     * `name`: The name of the monitor.
+     * `monitor`: The monitor.
+     *
+     */
+    fn register_monitor(&self, name: &str, monitor: ScheduledMonitor) {
+        match self.monitors.lock() {
+            Ok(mut monitors_lock) => {
+                monitors_lock.insert(name.to_string(), monitor);
+            }
+            Err(err) => {
+                error!("Error registering monitor: {:?}", err);
+            }
+        }
+    }
+
+    /**
+     * Set the thresholds, flap detection and check timeout on the status of the monitor.
+     *
+     * `monitor`: The monitor configuration.
+     * `schedule`: The schedule of the monitor.
+     *
+     */
+    fn configure_monitor_status(&self, monitor: &crate::common::Monitor, schedule: &MonitorSchedule) {
+        match self.status.lock() {
+            Ok(mut status_lock) => {
+                if let Some(monitor_status) = status_lock.get_mut(&monitor.name) {
+                    monitor_status.set_thresholds(monitor.failure_threshold, monitor.success_threshold);
+                    monitor_status.set_flap_detection(monitor.flap_detection.clone());
+                    monitor_status.timeout = schedule.get_timeout();
+                }
+            }
+            Err(err) => {
+                error!("Error configuring monitor status: {:?}", err);
+            }
+        }
+    }
+
     /**
      * Get the database service.
      *
@@ -247,13 +821,13 @@ impl SchedulingService {
      * `scheduler`: The job scheduler.
      * `job`: The job to add.
      *
-     * `result`: The result of adding the job to the scheduler.
+     * `result`: The id of the added job.
      *
      * throws: `ApplicationError`: If the job fails to be added.
      */
-    async fn add_job(&self, scheduler: &JobScheduler, job: Job) -> Result<(), ApplicationError> {
+    async fn add_job(&self, scheduler: &JobScheduler, job: Job) -> Result<Uuid, ApplicationError> {
         match scheduler.add(job).await {
-            Ok(_) => Ok(()),
+            Ok(job_id) => Ok(job_id),
             Err(err) => Err(ApplicationError::new(
                 format!("Could not add job: {err}").as_str(),
             )),
@@ -278,7 +852,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/test_full_configuration.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/test_full_configuration.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -289,7 +863,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_tcp() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_tcp.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -300,7 +874,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_http() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_http.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_http.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -311,7 +885,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_command() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_command.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_command.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -322,7 +896,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_loadavg() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_loadavg.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_loadavg.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -333,7 +907,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_meminfo() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_meminfo.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_meminfo.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -344,7 +918,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_systemctl() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_systemctl.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_systemctl.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -355,7 +929,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_db_mariadb() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_db_mariadb.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_db_mariadb.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }
@@ -366,7 +940,7 @@ mod test {
     #[tokio::test]
     async fn test_monitoring_service_db_postgres() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_db_postgres.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("./resources/test/configuration_import_test/test_simple_db_postgres.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.start(true).await;
         assert!(res.is_ok());
     }    
@@ -374,7 +948,7 @@ mod test {
     #[tokio::test]
     async fn test_add_jobs() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.add_jobs().await;
         print!("{:?}", res);
     }
@@ -382,27 +956,42 @@ mod test {
     #[tokio::test]
     async fn test_create_and_add_job_tcp_job() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json").unwrap(), &status, &Arc::new(None));
+        let monitors = Arc::new(Mutex::new(HashMap::new()));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &monitors);
         let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
             name: "test".to_string(),
             schedule: "* * * * * *".to_string(),
             store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
             details: crate::common::MonitorType::Tcp {
                 host: "localhost".to_string(),
                 port: 80,
+                max_response_time: None,
             },
         }, &JobScheduler::new().await.unwrap()).await;
-        assert!(res.is_ok())
+        assert!(res.is_ok());
+        assert!(matches!(monitors.lock().unwrap().get("test"), Some(ScheduledMonitor::Tcp(_))));
     }
 
     #[tokio::test]
     async fn test_create_and_add_job_http_job() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_http.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_http.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
             name: "test".to_string(),
             schedule: "* * * * * *".to_string(),
             store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
             details: crate::common::MonitorType::Http {
                 url: "http://localhost".to_string(),
                 method: crate::common::HttpMethod::Get,
@@ -414,6 +1003,7 @@ mod test {
                 root_certificate: None,
                 identity: None,
                 identity_password: None,
+                max_response_time: None,
             },
         }, &JobScheduler::new().await.unwrap()).await;
         assert!(res.is_ok())
@@ -423,11 +1013,17 @@ mod test {
     #[tokio::test]
     async fn test_create_and_add_job_systemctl_job() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_systemctl.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_systemctl.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
             name: "test".to_string(),
             schedule: "* * * * * *".to_string(),
             store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
             details: crate::common::MonitorType::Systemctl { 
                 active: vec!["ssh".to_string()],
             },
@@ -438,11 +1034,17 @@ mod test {
     #[tokio::test]
     async fn test_create_and_add_job_command_job() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_command.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_command.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
             name: "test".to_string(),
             schedule: "* * * * * *".to_string(),
             store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
             details: crate::common::MonitorType::Command {
                 command: "ls".to_string(),
                 args: Some(vec!["-l".to_string()]),
@@ -455,15 +1057,24 @@ mod test {
     #[tokio::test]
     async fn test_create_and_add_job_loadavg_job() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_loadavg.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_loadavg.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
             name: "test".to_string(),
             schedule: "* * * * * *".to_string(),
             store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
             details: crate::common::MonitorType::LoadAvg { 
                 threshold_1min: Some(0.0),
                 threshold_5min: Some(0.0),
                 threshold_10min: Some(0.0),
+                warning_threshold_1min: None,
+                warning_threshold_5min: None,
+                warning_threshold_10min: None,
                 store_values: false,
             },
         }, &JobScheduler::new().await.unwrap()).await;
@@ -473,19 +1084,378 @@ mod test {
     #[tokio::test]
     async fn test_create_and_add_job_meminfo_job() {
         let status = Arc::new(Mutex::new(HashMap::new()));
-        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_meminfo.json").unwrap(), &status, &Arc::new(None));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_meminfo.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
         let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
             name: "test".to_string(),
             schedule: "* * * * * *".to_string(),
             store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
             details: crate::common::MonitorType::Mem {
                 max_percentage_mem: Some(0.0),
                 max_percentage_swap: Some(0.0),
+                warning_percentage_mem: None,
+                warning_percentage_swap: None,
                 store_values: false,
             },
         }, &JobScheduler::new().await.unwrap()).await;
         assert!(res.is_ok())
-    }    
+    }
 
-}
+    #[tokio::test]
+    async fn test_create_and_add_job_sets_thresholds() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut scheduling_service = SchedulingService::new("", &MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap(), &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        let res = scheduling_service.create_and_add_job(&crate::common::Monitor {
+            name: "test".to_string(),
+            schedule: "* * * * * *".to_string(),
+            store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 3,
+            success_threshold: 2,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
+            details: crate::common::MonitorType::Tcp {
+                host: "localhost".to_string(),
+                port: 80,
+                max_response_time: None,
+            },
+        }, &JobScheduler::new().await.unwrap()).await;
+        assert!(res.is_ok());
+        let status_lock = status.lock().unwrap();
+        let monitor_status = status_lock.get("test").unwrap();
+        assert_eq!(3, monitor_status.failure_threshold);
+        assert_eq!(2, monitor_status.success_threshold);
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_monitor() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors[0].name = "test".to_string();
+        monitoring_config.monitors[0].schedule = "0 0 * * * *".to_string();
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        let scheduler = JobScheduler::new().await.unwrap();
+        scheduling_service.scheduler = Some(scheduler.clone());
+        scheduling_service.create_and_add_job(&monitoring_config.monitors[0], &scheduler).await.unwrap();
+
+        assert!(scheduling_service.pause_monitor("test").await.unwrap());
+        assert!(!scheduling_service.jobs.contains_key("test"));
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, crate::common::Status::Paused);
+        assert!(scheduling_service.pause_monitor("test").await.unwrap());
+
+        assert!(scheduling_service.resume_monitor("test").await.unwrap());
+        assert!(scheduling_service.jobs.contains_key("test"));
+        assert_eq!(status.lock().unwrap().get("test").unwrap().status, crate::common::Status::Unknown);
+
+        assert!(!scheduling_service.pause_monitor("missing").await.unwrap());
+        assert!(!scheduling_service.resume_monitor("missing").await.unwrap());
+    }
+
+    /**
+     * Get a tcp monitor configuration.
+     */
+    fn get_tcp_monitor(name: &str, port: u16) -> crate::common::Monitor {
+        crate::common::Monitor {
+            name: name.to_string(),
+            schedule: "0 0 * * * *".to_string(),
+            store: DatabaseStoreLevel::None,
+            email_recipients: None,
+            failure_threshold: 1,
+            success_threshold: 1,
+            flap_detection: None,
+            jitter: None,
+            timeout: None,
+            details: crate::common::MonitorType::Tcp {
+                host: "localhost".to_string(),
+                port,
+                max_response_time: None,
+            },
+        }
+    }
+
+    /**
+     * Test that monitors with interval schedules and jitter are scheduled, and an invalid jitter is rejected.
+     */
+    #[tokio::test]
+    async fn test_add_jobs_interval() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_interval.json", None).unwrap();
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        scheduling_service.add_jobs().await.unwrap();
+        assert!(scheduling_service.jobs.contains_key("interval"));
+        assert!(scheduling_service.jobs.contains_key("cron"));
+
+        monitoring_config.monitors[0].jitter = Some("often".to_string());
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        assert_eq!(scheduling_service.add_jobs().await.unwrap_err().get_message(), "Invalid jitter: often");
+    }
+
+    #[tokio::test]
+    async fn test_reload() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let monitors = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("unchanged", 80), get_tcp_monitor("changed", 81), get_tcp_monitor("removed", 82)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &monitors);
+        scheduling_service.add_jobs().await.unwrap();
+        status.lock().unwrap().get_mut("unchanged").unwrap().set_status(&crate::common::Status::Ok, std::time::Duration::ZERO);
+        status.lock().unwrap().get_mut("changed").unwrap().set_status(&crate::common::Status::Ok, std::time::Duration::ZERO);
+        let unchanged_job = *scheduling_service.jobs.get("unchanged").unwrap();
+        let changed_job = *scheduling_service.jobs.get("changed").unwrap();
+
+        let mut new_config = monitoring_config.clone();
+        new_config.monitors = vec![get_tcp_monitor("unchanged", 80), get_tcp_monitor("changed", 8081), get_tcp_monitor("added", 83)];
+        scheduling_service.reload(&new_config).await.unwrap();
+
+        assert_eq!(scheduling_service.jobs.len(), 3);
+        assert_eq!(scheduling_service.jobs.get("unchanged"), Some(&unchanged_job));
+        assert_ne!(scheduling_service.jobs.get("changed"), Some(&changed_job));
+        assert!(!scheduling_service.jobs.contains_key("removed"));
+        assert!(!monitors.lock().unwrap().contains_key("removed"));
+        let status_lock = status.lock().unwrap();
+        assert_eq!(status_lock.get("unchanged").unwrap().status, crate::common::Status::Ok);
+        assert_eq!(status_lock.get("changed").unwrap().status, crate::common::Status::Unknown);
+        assert_eq!(status_lock.get("added").unwrap().status, crate::common::Status::Unknown);
+        assert!(!status_lock.contains_key("removed"));
+    }
+
+    /**
+     * Test that reloading uses the new email recipients. The monitor with changed recipients is rescheduled with
+     * the new notification service, and a changed notification configuration reschedules all monitors.
+     */
+    #[tokio::test]
+    async fn test_reload_email_recipients() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        let email_config = crate::common::configuration::EmailConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(2525),
+            security: crate::common::configuration::SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "agent@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            timeout: 5,
+            accept_invalid_certs: false,
+        };
+        monitoring_config.notifications = Some(crate::common::configuration::NotificationConfig { webhooks: Vec::new(), email: Some(email_config) });
+        monitoring_config.monitors = vec![get_tcp_monitor("unchanged", 80), get_tcp_monitor("changed", 81)];
+        let notification_service = NotificationService::new(monitoring_config.notifications.as_ref().unwrap(), "", &monitoring_config.monitors).unwrap();
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(Some(notification_service)), &Arc::new(Mutex::new(HashMap::new())));
+        scheduling_service.add_jobs().await.unwrap();
+        let unchanged_job = *scheduling_service.jobs.get("unchanged").unwrap();
+        let changed_job = *scheduling_service.jobs.get("changed").unwrap();
+
+        let mut new_config = monitoring_config.clone();
+        new_config.monitors[1].email_recipients = Some(vec!["db@example.com".to_string()]);
+        scheduling_service.reload(&new_config).await.unwrap();
+        let notification_service = scheduling_service.notification_service.as_ref().clone().unwrap();
+        assert_eq!(notification_service.get_email_recipients("changed"), vec!["db@example.com".to_string()]);
+        assert_eq!(notification_service.get_email_recipients("unchanged"), vec!["ops@example.com".to_string()]);
+        assert_eq!(scheduling_service.jobs.get("unchanged"), Some(&unchanged_job));
+        assert_ne!(scheduling_service.jobs.get("changed"), Some(&changed_job));
+
+        let mut new_notification_config = new_config.clone();
+        new_notification_config.notifications.as_mut().unwrap().email.as_mut().unwrap().to = vec!["oncall@example.com".to_string()];
+        scheduling_service.reload(&new_notification_config).await.unwrap();
+        let notification_service = scheduling_service.notification_service.as_ref().clone().unwrap();
+        assert_eq!(notification_service.get_email_recipients("unchanged"), vec!["oncall@example.com".to_string()]);
+        assert_ne!(scheduling_service.jobs.get("unchanged"), Some(&unchanged_job));
 
+        new_notification_config.notifications = None;
+        scheduling_service.reload(&new_notification_config).await.unwrap();
+        assert!(scheduling_service.notification_service.is_none());
+        assert_eq!(scheduling_service.jobs.len(), 2);
+    }
+
+    /**
+     * Test that a monitor failing to be created leaves the running monitors unchanged.
+     *
+     * Test the following scenarios:
+     * - The job and the status of a changed monitor are kept.
+     * - A removed monitor keeps running.
+     * - An added monitor that was created is not added, and its status is removed.
+     * - The configuration is not changed.
+     */
+    #[tokio::test]
+    async fn test_reload_failed_monitor() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let monitors = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("changed", 80), get_tcp_monitor("removed", 81)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &monitors);
+        scheduling_service.add_jobs().await.unwrap();
+        status.lock().unwrap().get_mut("changed").unwrap().set_status(&crate::common::Status::Ok, std::time::Duration::ZERO);
+        let changed_job = *scheduling_service.jobs.get("changed").unwrap();
+
+        let mut failing_monitor = get_tcp_monitor("failing", 0);
+        failing_monitor.details = crate::common::MonitorType::Http {
+            url: "https://localhost".to_string(),
+            method: crate::common::HttpMethod::Get,
+            body: None,
+            headers: None,
+            use_builtin_root_certs: true,
+            accept_invalid_certs: false,
+            tls_info: false,
+            root_certificate: None,
+            identity: Some("resources/test/test_full_configuration.json".to_string()),
+            identity_password: None,
+            max_response_time: None,
+        };
+        let mut new_config = monitoring_config.clone();
+        new_config.monitors = vec![get_tcp_monitor("changed", 8080), get_tcp_monitor("added", 82), failing_monitor];
+        assert_eq!(
+            scheduling_service.reload(&new_config).await.unwrap_err().get_message(),
+            "Could not create monitors: failing: Identity password is required"
+        );
+
+        assert_eq!(scheduling_service.monitoring_config, monitoring_config);
+        assert_eq!(scheduling_service.jobs.len(), 2);
+        assert_eq!(scheduling_service.jobs.get("changed"), Some(&changed_job));
+        assert!(scheduling_service.jobs.contains_key("removed"));
+        assert!(monitors.lock().unwrap().contains_key("removed"));
+        let status_lock = status.lock().unwrap();
+        assert_eq!(status_lock.get("changed").unwrap().status, crate::common::Status::Ok);
+        assert!(status_lock.contains_key("removed"));
+        assert!(!status_lock.contains_key("added"));
+        assert!(!status_lock.contains_key("failing"));
+    }
+
+    /**
+     * Test that a changed database configuration is rejected and leaves the running monitors unchanged.
+     */
+    #[tokio::test]
+    async fn test_reload_database_changed() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("test", 80)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        scheduling_service.add_jobs().await.unwrap();
+        let job = *scheduling_service.jobs.get("test").unwrap();
+        let mut new_config = monitoring_config.clone();
+        new_config.database = Some(crate::common::DatabaseConfig {
+            dbtype: crate::common::configuration::DatabaseType::Postgres,
+            host: "127.0.0.1".to_string(),
+            db_name: "monitoring".to_string(),
+            user: "monitoring".to_string(),
+            password: "password".to_string(),
+            port: 5432,
+            min_connections: 1,
+            max_connections: 2,
+        });
+        new_config.monitors = vec![get_tcp_monitor("test", 81)];
+        assert_eq!(
+            scheduling_service.reload(&new_config).await.unwrap_err().get_message(),
+            "The database configuration changed, restart the agent to apply it"
+        );
+        assert_eq!(scheduling_service.monitoring_config, monitoring_config);
+        assert_eq!(scheduling_service.jobs.get("test"), Some(&job));
+    }
+
+    #[tokio::test]
+    async fn test_reload_duplicate_names() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("test", 80)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        scheduling_service.add_jobs().await.unwrap();
+        let mut new_config = monitoring_config.clone();
+        new_config.monitors = vec![get_tcp_monitor("test", 81), get_tcp_monitor("test", 82)];
+        assert!(scheduling_service.reload(&new_config).await.is_err());
+        assert_eq!(scheduling_service.monitoring_config, monitoring_config);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let monitors = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("test", 80)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &monitors);
+        scheduling_service.add_jobs().await.unwrap();
+        /*
+         * A running check delays the shutdown until it finishes or the deadline is reached.
+         */
+        let running_check = scheduling_service.running_checks.start();
+        assert!(!scheduling_service.shutdown(Duration::from_millis(100)).await.unwrap());
+        assert!(scheduling_service.scheduler.is_none());
+        assert!(scheduling_service.jobs.is_empty());
+        assert!(monitors.lock().unwrap().is_empty());
+        assert!(!scheduling_service.pause_monitor("test").await.unwrap());
+        drop(running_check);
+        assert!(scheduling_service.shutdown(Duration::from_millis(100)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_add_watchdog_job() {
+        let path = format!("/tmp/monitoring-agent-watchdog-{}.sock", std::process::id());
+        let _ = std::fs::remove_file(&path);
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("test", 80)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        /*
+         * Without a watchdog interval no job is added.
+         */
+        assert!(scheduling_service.add_watchdog_job(&SdNotifyService::new(&path, None)).await.is_ok());
+        assert!(scheduling_service.add_watchdog_job(&SdNotifyService::new(&path, Some(Duration::from_secs(1)))).await.is_err());
+        scheduling_service.add_jobs().await.unwrap();
+        scheduling_service.add_watchdog_job(&SdNotifyService::new(&path, Some(Duration::from_secs(1)))).await.unwrap();
+        let received = tokio::task::spawn_blocking(move || {
+            let mut buffer = [0; 1024];
+            let size = socket.recv(&mut buffer).unwrap();
+            String::from_utf8_lossy(&buffer[..size]).to_string()
+        }).await.unwrap();
+        assert_eq!(received, "WATCHDOG=1\nSTATUS=1 monitors: 1 unknown");
+        assert_eq!(scheduling_service.get_status_summary(), "1 monitors: 1 unknown");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /**
+     * Test notifying systemd that the agent is ready.
+     *
+     * Test the following scenarios:
+     * - Systemd is not notified before the scheduler is started.
+     * - Systemd is notified that the agent is ready after the scheduler is started.
+     * - Pausing and resuming a monitor sends the new status to systemd.
+     */
+    #[tokio::test]
+    async fn test_notify_ready() {
+        let path = format!("/tmp/monitoring-agent-ready-{}.sock", std::process::id());
+        let _ = std::fs::remove_file(&path);
+        let socket = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let status = Arc::new(Mutex::new(HashMap::new()));
+        let mut monitoring_config = MonitoringConfig::new("resources/test/configuration_import_test/test_simple_tcp.json", None).unwrap();
+        monitoring_config.monitors = vec![get_tcp_monitor("test", 80)];
+        let mut scheduling_service = SchedulingService::new("", &monitoring_config, &status, &Arc::new(None), &Arc::new(None), &Arc::new(Mutex::new(HashMap::new())));
+        let sd_notify_service = SdNotifyService::new(&path, None);
+        assert!(scheduling_service.notify_ready(&sd_notify_service).await.is_err());
+        assert!(scheduling_service.sd_notify_service.is_none());
+        scheduling_service.add_jobs().await.unwrap();
+        scheduling_service.notify_ready(&sd_notify_service).await.unwrap();
+        scheduling_service.pause_monitor("test").await.unwrap();
+        scheduling_service.resume_monitor("test").await.unwrap();
+        let received = tokio::task::spawn_blocking(move || {
+            let mut buffer = [0; 1024];
+            (0..3).map(|_| {
+                let size = socket.recv(&mut buffer).unwrap();
+                String::from_utf8_lossy(&buffer[..size]).to_string()
+            }).collect::<Vec<_>>()
+        }).await.unwrap();
+        assert_eq!(received, vec![
+            "READY=1\nSTATUS=1 monitors: 1 unknown",
+            "STATUS=1 monitors: 1 paused",
+            "STATUS=1 monitors: 1 unknown",
+        ]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+use log::debug;
+
+use crate::common::{ApplicationError, MonitorStatus, Status};
+
+/**
+ * Systemd notify service.
+ *
+ * This struct sends state changes to systemd using the `sd_notify` protocol. The state is sent as a datagram to the
+ * unix socket given by systemd in the `NOTIFY_SOCKET` environment variable.
+ *
+ * `socket_path`: The path of the notify socket. Paths starting with @ are in the abstract namespace.
+ * `watchdog_interval`: The interval for sending watchdog pings, if the watchdog is enabled.
+ *
+ */
+#[derive(Debug, Clone)]
+pub struct SdNotifyService {
+    /// The path of the notify socket.
+    socket_path: String,
+    /// The interval for sending watchdog pings. Half of the watchdog timeout given by systemd.
+    watchdog_interval: Option<Duration>,
+}
+
+impl SdNotifyService {
+
+    /**
+     * Create a new systemd notify service.
+     *
+     * `socket_path`: The path of the notify socket.
+     * `watchdog_interval`: The interval for sending watchdog pings.
+     *
+     * Returns: A new systemd notify service.
+     */
+    pub fn new(socket_path: &str, watchdog_interval: Option<Duration>) -> SdNotifyService {
+        SdNotifyService {
+            socket_path: socket_path.to_string(),
+            watchdog_interval,
+        }
+    }
+
+    /**
+     * Create the systemd notify service from the environment set by systemd.
+     *
+     * Returns: The systemd notify service, or None if the agent is not started by systemd with `Type=notify`.
+     */
+    pub fn from_env() -> Option<SdNotifyService> {
+        let socket_path = std::env::var("NOTIFY_SOCKET").ok()?;
+        let watchdog_usec = std::env::var("WATCHDOG_USEC").ok();
+        let watchdog_pid = std::env::var("WATCHDOG_PID").ok();
+        let watchdog_interval = SdNotifyService::parse_watchdog_interval(watchdog_usec.as_deref(), watchdog_pid.as_deref(), std::process::id());
+        Some(SdNotifyService::new(&socket_path, watchdog_interval))
+    }
+
+    /**
+     * Get the interval for sending watchdog pings. Pings are sent at half the watchdog timeout.
+     *
+     * `watchdog_usec`: The watchdog timeout in microseconds.
+     * `watchdog_pid`: The pid the watchdog is meant for. If not given, the watchdog is meant for this process.
+     * `pid`: The pid of this process.
+     *
+     * Returns: The interval, or None if the watchdog is not enabled for this process.
+     */
+    fn parse_watchdog_interval(watchdog_usec: Option<&str>, watchdog_pid: Option<&str>, pid: u32) -> Option<Duration> {
+        if let Some(watchdog_pid) = watchdog_pid {
+            if watchdog_pid.parse::<u32>().ok()? != pid {
+                return None;
+            }
+        }
+        let watchdog_usec = watchdog_usec?.parse::<u64>().ok()?;
+        if watchdog_usec == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(watchdog_usec / 2))
+    }
+
+    /**
+     * Get the interval for sending watchdog pings.
+     *
+     * Returns: The interval, or None if the watchdog is not enabled.
+     */
+    pub fn get_watchdog_interval(&self) -> Option<Duration> {
+        self.watchdog_interval
+    }
+
+    /**
+     * Notify systemd that the agent is ready.
+     *
+     * `status`: The status text to show in `systemctl status`.
+     *
+     * Returns: Ok if the notification was sent.
+     *
+     * throws: `ApplicationError`: If the notification fails to be sent.
+     */
+    pub fn ready(&self, status: &str) -> Result<(), ApplicationError> {
+        self.notify(&format!("READY=1\nSTATUS={status}"))
+    }
+
+    /**
+     * Send a watchdog ping to systemd together with the current status.
+     *
+     * `status`: The status text to show in `systemctl status`.
+     *
+     * Returns: Ok if the notification was sent.
+     *
+     * throws: `ApplicationError`: If the notification fails to be sent.
+     */
+    pub fn watchdog(&self, status: &str) -> Result<(), ApplicationError> {
+        self.notify(&format!("WATCHDOG=1\nSTATUS={status}"))
+    }
+
+    /**
+     * Send the current status to systemd.
+     *
+     * `status`: The status text to show in `systemctl status`.
+     *
+     * Returns: Ok if the notification was sent.
+     *
+     * throws: `ApplicationError`: If the notification fails to be sent.
+     */
+    pub fn status(&self, status: &str) -> Result<(), ApplicationError> {
+        self.notify(&format!("STATUS={status}"))
+    }
+
+    /**
+     * Notify systemd that the agent is stopping.
+     *
+     * Returns: Ok if the notification was sent.
+     *
+     * throws: `ApplicationError`: If the notification fails to be sent.
+     */
+    pub fn stopping(&self) -> Result<(), ApplicationError> {
+        self.notify("STOPPING=1\nSTATUS=Stopping")
+    }
+
+    /**
+     * Send a state to the notify socket.
+     *
+     * `state`: The newline separated state assignments.
+     *
+     * Returns: Ok if the state was sent.
+     *
+     * throws: `ApplicationError`: If the state fails to be sent.
+     */
+    fn notify(&self, state: &str) -> Result<(), ApplicationError> {
+        debug!("Notifying systemd: {}", state.replace('\n', " "));
+        let address = self.get_socket_address()?;
+        let socket = UnixDatagram::unbound().map_err(|err| ApplicationError::new(
+            format!("Could not create notify socket: {err}").as_str(),
+        ))?;
+        socket.send_to_addr(state.as_bytes(), &address).map_err(|err| ApplicationError::new(
+            format!("Could not notify systemd on {}: {err}", &self.socket_path).as_str(),
+        ))?;
+        Ok(())
+    }
+
+    /**
+     * Get the address of the notify socket.
+     *
+     * Returns: The socket address.
+     *
+     * throws: `ApplicationError`: If the socket path is invalid.
+     */
+    fn get_socket_address(&self) -> Result<SocketAddr, ApplicationError> {
+        let address = match self.socket_path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name.as_bytes()),
+            None => SocketAddr::from_pathname(&self.socket_path),
+        };
+        address.map_err(|err| ApplicationError::new(
+            format!("Invalid notify socket {}: {err}", &self.socket_path).as_str(),
+        ))
+    }
+
+    /**
+     * Get a summary of the monitor statuses, e.g. "3 monitors: 2 ok, 1 error".
+     *
+     * `statuses`: The status of the monitors.
+     *
+     * Returns: The summary.
+     */
+    pub fn get_status_summary(statuses: &HashMap<String, MonitorStatus>) -> String {
+        let labels = ["ok", "warning", "error", "flapping", "unknown", "paused"];
+        let mut counts = [0; 6];
+        for monitor_status in statuses.values() {
+            let index = match monitor_status.status {
+                Status::Ok => 0,
+                Status::Warning { .. } => 1,
+                Status::Error { .. } => 2,
+                Status::Flapping { .. } => 3,
+                Status::Unknown => 4,
+                Status::Paused => 5,
+            };
+            counts[index] += 1;
+        }
+        let details: Vec<String> = labels.iter().zip(counts.iter())
+            .filter(|(_, count)| **count > 0)
+            .map(|(label, count)| format!("{count} {label}"))
+            .collect();
+        if details.is_empty() {
+            return "0 monitors".to_string();
+        }
+        format!("{} monitors: {}", statuses.len(), details.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /**
+     * Get a notify socket bound to a temporary path, standing in for systemd.
+     */
+    fn get_socket(name: &str) -> (UnixDatagram, String) {
+        let path = format!("/tmp/monitoring-agent-notify-{name}-{}.sock", std::process::id());
+        let _ = std::fs::remove_file(&path);
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        (socket, path)
+    }
+
+    /**
+     * Receive a state from the notify socket.
+     */
+    fn receive(socket: &UnixDatagram) -> String {
+        let mut buffer = [0; 1024];
+        let size = socket.recv(&mut buffer).unwrap();
+        String::from_utf8_lossy(&buffer[..size]).to_string()
+    }
+
+    #[test]
+    fn test_notify() {
+        let (socket, path) = get_socket("notify");
+        let sd_notify_service = SdNotifyService::new(&path, None);
+        sd_notify_service.ready("1 monitors: 1 ok").unwrap();
+        assert_eq!(receive(&socket), "READY=1\nSTATUS=1 monitors: 1 ok");
+        sd_notify_service.watchdog("1 monitors: 1 error").unwrap();
+        assert_eq!(receive(&socket), "WATCHDOG=1\nSTATUS=1 monitors: 1 error");
+        sd_notify_service.status("1 monitors: 1 paused").unwrap();
+        assert_eq!(receive(&socket), "STATUS=1 monitors: 1 paused");
+        sd_notify_service.stopping().unwrap();
+        assert_eq!(receive(&socket), "STOPPING=1\nSTATUS=Stopping");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_notify_abstract_socket() {
+        let name = format!("monitoring-agent-notify-{}", std::process::id());
+        let socket = UnixDatagram::bind_addr(&SocketAddr::from_abstract_name(name.as_bytes()).unwrap()).unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        SdNotifyService::new(&format!("@{name}"), None).ready("0 monitors").unwrap();
+        assert_eq!(receive(&socket), "READY=1\nSTATUS=0 monitors");
+    }
+
+    #[test]
+    fn test_notify_missing_socket() {
+        let sd_notify_service = SdNotifyService::new("/tmp/monitoring-agent-notify-missing.sock", None);
+        assert!(sd_notify_service.ready("").is_err());
+    }
+
+    #[test]
+    fn test_get_watchdog_interval() {
+        assert_eq!(SdNotifyService::parse_watchdog_interval(Some("30000000"), None, 1), Some(Duration::from_secs(15)));
+        assert_eq!(SdNotifyService::parse_watchdog_interval(Some("30000000"), Some("1"), 1), Some(Duration::from_secs(15)));
+        assert_eq!(SdNotifyService::parse_watchdog_interval(Some("30000000"), Some("2"), 1), None);
+        assert_eq!(SdNotifyService::parse_watchdog_interval(Some("0"), None, 1), None);
+        assert_eq!(SdNotifyService::parse_watchdog_interval(Some("invalid"), None, 1), None);
+        assert_eq!(SdNotifyService::parse_watchdog_interval(None, None, 1), None);
+    }
+
+    #[test]
+    fn test_get_status_summary() {
+        let mut statuses = HashMap::new();
+        assert_eq!(SdNotifyService::get_status_summary(&statuses), "0 monitors");
+        statuses.insert("a".to_string(), MonitorStatus::new("a".to_string(), Status::Ok));
+        statuses.insert("b".to_string(), MonitorStatus::new("b".to_string(), Status::Ok));
+        statuses.insert("c".to_string(), MonitorStatus::new("c".to_string(), Status::Error { message: "error".to_string() }));
+        statuses.insert("d".to_string(), MonitorStatus::new("d".to_string(), Status::Paused));
+        assert_eq!(SdNotifyService::get_status_summary(&statuses), "4 monitors: 2 ok, 1 error, 1 paused");
+    }
+}
@@ -0,0 +1,313 @@
+use std::{fs::File, io::{BufRead, BufReader}};
+
+use log::error;
+use nix::sys::statvfs::statvfs;
+use serde::{Deserialize, Serialize};
+
+use crate::common::CommonLibError;
+
+/// Filesystem types without disk space of their own. They are skipped when all filesystems are read.
+const PSEUDO_FILESYSTEMS: [&str; 27] = [
+    "autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts", "devtmpfs", "efivarfs",
+    "fusectl", "hugetlbfs", "mqueue", "nsfs", "overlay", "proc", "pstore", "ramfs", "rpc_pipefs", "securityfs",
+    "selinuxfs", "squashfs", "sysfs", "tmpfs", "tracefs", "fuse.gvfsd-fuse", "fuse.portal",
+];
+
+/**
+ * Disk space and inode usage of a mounted filesystem, from /proc/mounts and statvfs.
+ */
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcsDisk {
+    /// The mounted device, e.g. /dev/sda1.
+    pub device: String,
+    /// The mount point, e.g. /.
+    pub mount_point: String,
+    /// The filesystem type, e.g. ext4.
+    pub fs_type: String,
+    /// The size of the filesystem in bytes.
+    pub total: Option<u64>,
+    /// The free space in bytes.
+    pub free: Option<u64>,
+    /// The space available to unprivileged users in bytes.
+    pub available: Option<u64>,
+    /// The number of inodes.
+    pub inodes_total: Option<u64>,
+    /// The number of free inodes.
+    pub inodes_free: Option<u64>,
+}
+
+impl ProcsDisk {
+
+    /**
+     * Create a new `ProcsDisk`.
+     *
+     * ```
+     * use monitoring_agent_lib::proc::disk::ProcsDisk;
+     * ProcsDisk::new("/dev/sda1", "/", "ext4", Some(100_000), Some(40_000), Some(30_000), Some(1000), Some(900));
+     * ```
+     *
+     * `device`: The mounted device.
+     * `mount_point`: The mount point.
+     * `fs_type`: The filesystem type.
+     * `total`: The size of the filesystem in bytes.
+     * `free`: The free space in bytes.
+     * `available`: The space available to unprivileged users in bytes.
+     * `inodes_total`: The number of inodes.
+     * `inodes_free`: The number of free inodes.
+     *
+     * Returns a new `ProcsDisk`.
+     */
+    #[allow(clippy::too_many_arguments)]
+    #[must_use] pub fn new(
+        device: &str,
+        mount_point: &str,
+        fs_type: &str,
+        total: Option<u64>,
+        free: Option<u64>,
+        available: Option<u64>,
+        inodes_total: Option<u64>,
+        inodes_free: Option<u64>,
+    ) -> ProcsDisk {
+        ProcsDisk {
+            device: device.to_string(),
+            mount_point: mount_point.to_string(),
+            fs_type: fs_type.to_string(),
+            total,
+            free,
+            available,
+            inodes_total,
+            inodes_free,
+        }
+    }
+
+    /**
+     * Get the usage of all real filesystems. Pseudo filesystems like proc, sysfs and tmpfs are skipped.
+     *
+     * ```
+     * use monitoring_agent_lib::proc::disk::ProcsDisk;
+     * ProcsDisk::get_disks();
+     * ```
+     *
+     * Returns the usage of the filesystems or an error. Filesystems whose usage can not be read are logged and
+     * skipped.
+     *
+     * # Errors
+     *  - If there is an error reading the mounts file.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub fn get_disks() -> Result<Vec<ProcsDisk>, CommonLibError> {
+        let mounts = ProcsDisk::read_mounts("/proc/mounts")?;
+        let mut disks = Vec::new();
+        for (device, mount_point, fs_type) in mounts {
+            if PSEUDO_FILESYSTEMS.contains(&fs_type.as_str()) {
+                continue;
+            }
+            /*
+             * A filesystem that can not be read, e.g. a fuse mount of another user, does not hide the others.
+             */
+            match ProcsDisk::read_usage(&device, &mount_point, &fs_type) {
+                Ok(disk) if disk.total.unwrap_or(0) > 0 => disks.push(disk),
+                Ok(_) => {}
+                Err(err) => error!("{}", err.message),
+            }
+        }
+        Ok(disks)
+    }
+
+    /**
+     * Get the usage of the filesystem mounted on a mount point.
+     *
+     * ```
+     * use monitoring_agent_lib::proc::disk::ProcsDisk;
+     * ProcsDisk::get_disk("/");
+     * ```
+     *
+     * `mount_point`: The mount point.
+     *
+     * Returns the usage of the filesystem or an error.
+     *
+     * # Errors
+     *  - If there is an error reading the mounts file.
+     *  - If nothing is mounted on the mount point.
+     *  - If there is an error reading the usage of the filesystem.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub fn get_disk(mount_point: &str) -> Result<ProcsDisk, CommonLibError> {
+        let mounts = ProcsDisk::read_mounts("/proc/mounts")?;
+        let Some((device, mount_point, fs_type)) = mounts.into_iter().find(|(_, mounted_on, _)| mounted_on == mount_point) else {
+            return Err(CommonLibError::new(format!("Nothing is mounted on {mount_point}").as_str()));
+        };
+        ProcsDisk::read_usage(&device, &mount_point, &fs_type)
+    }
+
+    /**
+     * Read the mounts file. A mount point mounted more than once is only returned for the last mount, which hides
+     * the earlier ones.
+     *
+     * `file`: The file to read.
+     *
+     * Returns the device, mount point and filesystem type of every mount or an error.
+     *
+     * # Errors
+     *  - If there is an error reading the mounts file.
+     *  - If there is an error reading a line from the mounts file.
+     */
+    fn read_mounts(file: &str) -> Result<Vec<(String, String, String)>, CommonLibError> {
+        let mounts_file = File::open(file).map_err(|err| {
+            error!("Error reading mounts: {err:?}");
+            CommonLibError::new(format!("Error reading mounts: {err:?}").as_str())
+        })?;
+        let mut mounts: Vec<(String, String, String)> = Vec::new();
+        for line in BufReader::new(mounts_file).lines() {
+            let line = line.map_err(|err| CommonLibError::new(format!("Error reading line: {err:?}").as_str()))?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 3 {
+                continue;
+            }
+            let mount_point = ProcsDisk::unescape(parts[1]);
+            mounts.retain(|(_, mounted_on, _)| *mounted_on != mount_point);
+            mounts.push((ProcsDisk::unescape(parts[0]), mount_point, parts[2].to_string()));
+        }
+        Ok(mounts)
+    }
+
+    /**
+     * Read the usage of a mounted filesystem.
+     *
+     * `device`: The mounted device.
+     * `mount_point`: The mount point.
+     * `fs_type`: The filesystem type.
+     *
+     * Returns the usage of the filesystem or an error.
+     *
+     * # Errors
+     *  - If statvfs fails on the mount point.
+     */
+    #[allow(clippy::useless_conversion)]
+    fn read_usage(device: &str, mount_point: &str, fs_type: &str) -> Result<ProcsDisk, CommonLibError> {
+        let stat = statvfs(mount_point).map_err(|err| {
+            CommonLibError::new(format!("Error reading disk usage of {mount_point}: {err}").as_str())
+        })?;
+        let fragment_size = u64::from(stat.fragment_size());
+        Ok(ProcsDisk::new(
+            device,
+            mount_point,
+            fs_type,
+            u64::from(stat.blocks()).checked_mul(fragment_size),
+            u64::from(stat.blocks_free()).checked_mul(fragment_size),
+            u64::from(stat.blocks_available()).checked_mul(fragment_size),
+            Some(u64::from(stat.files())),
+            Some(u64::from(stat.files_free())),
+        ))
+    }
+
+    /**
+     * Unescape a field of the mounts file. Spaces, tabs, newlines and backslashes are written as octal escapes,
+     * e.g. \040 for a space.
+     *
+     * `field`: The field.
+     *
+     * Returns the unescaped field.
+     */
+    fn unescape(field: &str) -> String {
+        let mut unescaped = String::new();
+        let mut rest = field;
+        while let Some(start) = rest.find('\\') {
+            unescaped.push_str(&rest[..start]);
+            let escape = rest.get(start + 1..start + 4).and_then(|octal| u8::from_str_radix(octal, 8).ok());
+            match escape {
+                Some(character) => {
+                    unescaped.push(char::from(character));
+                    rest = &rest[start + 4..];
+                }
+                None => {
+                    unescaped.push('\\');
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        unescaped.push_str(rest);
+        unescaped
+    }
+
+    /**
+     * Calculate the percentage of the disk space used, the same way as df. The space reserved for root counts as
+     * neither used nor available.
+     *
+     * Returns: Percentage or none
+     */
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn get_percent_used(&self) -> Option<f64> {
+        let used = self.total?.checked_sub(self.free?)?;
+        let size = used.checked_add(self.available?)?;
+        if size == 0 {
+            return None;
+        }
+        Some((used as f64 / size as f64) * 100f64)
+    }
+
+    /**
+     * Calculate the percentage of the inodes used.
+     *
+     * Returns: Percentage or none. None for filesystems without a fixed number of inodes, e.g. btrfs.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn get_percent_inodes_used(&self) -> Option<f64> {
+        let total = self.inodes_total?;
+        if total == 0 {
+            return None;
+        }
+        let used = total.checked_sub(self.inodes_free?)?;
+        Some((used as f64 / total as f64) * 100f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_current() {
+        let disks = ProcsDisk::get_disks();
+        assert!(disks.is_ok());
+        let disk = ProcsDisk::get_disk("/").unwrap();
+        assert_eq!(disk.mount_point, "/");
+        assert!(disk.total.is_some());
+        assert!(ProcsDisk::get_disk("/no/such/mount").is_err());
+    }
+
+    #[test]
+    fn test_read_predefined_mounts() {
+        let mounts = ProcsDisk::read_mounts("resources/test/test_mounts").unwrap();
+        assert_eq!(mounts, vec![
+            ("proc".to_string(), "/proc".to_string(), "proc".to_string()),
+            ("/dev/sda1".to_string(), "/".to_string(), "ext4".to_string()),
+            ("/dev/sdb1".to_string(), "/mnt/backup disk".to_string(), "xfs".to_string()),
+            ("tmpfs".to_string(), "/run".to_string(), "tmpfs".to_string()),
+            ("/dev/sda2".to_string(), "/home".to_string(), "btrfs".to_string()),
+        ]);
+        assert!(ProcsDisk::read_mounts("resources/test/missing_mounts").is_err());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(ProcsDisk::unescape("/mnt/backup\\040disk"), "/mnt/backup disk");
+        assert_eq!(ProcsDisk::unescape("/mnt/a\\134b"), "/mnt/a\\b");
+        assert_eq!(ProcsDisk::unescape("/mnt/a\\b"), "/mnt/a\\b");
+        assert_eq!(ProcsDisk::unescape("/"), "/");
+    }
+
+    #[test]
+    fn test_get_percent_used() {
+        let disk = ProcsDisk::new("/dev/sda1", "/", "ext4", Some(100_000), Some(40_000), Some(30_000), Some(1000), Some(900));
+        assert_eq!(disk.get_percent_used(), Some(60_000f64 / 90_000f64 * 100f64));
+        assert_eq!(disk.get_percent_inodes_used(), Some(10f64));
+        let disk = ProcsDisk::new("/dev/sda2", "/home", "btrfs", Some(100_000), None, Some(30_000), Some(0), Some(0));
+        assert_eq!(disk.get_percent_used(), None);
+        assert_eq!(disk.get_percent_inodes_used(), None);
+    }
+}
@@ -101,7 +101,7 @@ impl ProcsLoadavg {
             },
             Err(err) => {
                 error!("Error reading meminfo: {err:?}");
-                return Err(CommonLibError::new(format!("Error reading meminfo: {err:?}").as_str()));
+                Err(CommonLibError::new(format!("Error reading meminfo: {err:?}").as_str()))
             }
         }
     }
@@ -141,12 +141,13 @@ fn handle_loadavg_file(file: File) -> Result<ProcsLoadavg, CommonLibError> {
         },
         Err(err) => {
             error!("Error reading meminfo: {err:?}");
-            return Err(CommonLibError::new(format!("Error reading meminfo: {err:?}").as_str()));
+            Err(CommonLibError::new(format!("Error reading meminfo: {err:?}").as_str()))
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::clone_on_copy)]
 mod test {
     
         use super::*;
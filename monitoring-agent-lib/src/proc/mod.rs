@@ -6,8 +6,14 @@ pub mod meminfo;
 pub mod loadavg;
 /// Structure and methods to read and parse /proc/*/status 
 pub mod process;
+/// Structure and methods to read /proc/mounts and the disk usage of the mounted filesystems
+pub mod disk;
+/// Structure and methods to read /proc/stat and calculate the cpu usage
+pub mod stat;
 
 pub use crate::proc::cpuinfo::ProcsCpuinfo;
 pub use crate::proc::meminfo::ProcsMeminfo;
 pub use crate::proc::loadavg::ProcsLoadavg;
-pub use crate::proc::process::ProcsProcess;
\ No newline at end of file
+pub use crate::proc::process::ProcsProcess;
+pub use crate::proc::disk::ProcsDisk;
+pub use crate::proc::stat::{ProcsCpuStat, ProcsCpuUsage};
\ No newline at end of file
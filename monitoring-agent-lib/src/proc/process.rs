@@ -27,6 +27,8 @@ pub struct ProcsProcess {
     pub threads: Option<u32>,
     /// The groups the process belongs to.
     pub groups: Option<Vec<String>>,
+    /// The command line of the process, with the arguments separated by spaces. None for kernel threads.
+    pub command_line: Option<String>,
 }
 
 impl ProcsProcess {
@@ -36,12 +38,12 @@ impl ProcsProcess {
     *
     * ```
     * use monitoring_agent_lib::proc::process::ProcsProcess;
-    * ProcsProcess::new(None, None, None, None, None, None, None);
+    * ProcsProcess::new(None, None, None, None, None, None, None, None);
     * ```
     * ```
     * use monitoring_agent_lib::proc::process::ProcsProcess;
     * use monitoring_agent_lib::proc::process::ProcessState;
-    * ProcsProcess::new(Some(2914), Some(2656), Some("code".to_string()), Some("0002".to_string()), Some(ProcessState::InterruptableSleep), Some(1), Some(vec!["4".to_string(), "24".to_string(), "27".to_string(), "30".to_string(), "46".to_string(), "100".to_string(), "119".to_string(), "129".to_string(), "1000".to_string()]));
+    * ProcsProcess::new(Some(2914), Some(2656), Some("code".to_string()), Some("0002".to_string()), Some(ProcessState::InterruptableSleep), Some(1), Some(vec!["4".to_string(), "24".to_string(), "27".to_string(), "30".to_string(), "46".to_string(), "100".to_string(), "119".to_string(), "129".to_string(), "1000".to_string()]), Some("/usr/share/code/code".to_string()));
     * ```
     * 
     * `pid`: The process id.
@@ -51,10 +53,13 @@ impl ProcsProcess {
     * `state`: The state of the process.
     * `threads`: The number of threads in the process.
     * `groups`: The groups the process belongs to.
+    * `command_line`: The command line of the process.
     * 
     * Returns a new `ProcsProcess`.
     */
-    #[must_use] pub fn new(
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
         pid: Option<u32>,
         parent_pid: Option<u32>,
         name: Option<String>,
@@ -62,6 +67,7 @@ impl ProcsProcess {
         state: Option<ProcessState>,
         threads: Option<u32>,
         groups: Option<Vec<String>>,
+        command_line: Option<String>,
     ) -> ProcsProcess {
         ProcsProcess {
             pid,
@@ -71,6 +77,7 @@ impl ProcsProcess {
             state,
             threads,
             groups,
+            command_line,
         }
     }
 
@@ -105,7 +112,7 @@ impl ProcsProcess {
     }
 
     /**
-     * Read all processes.
+     * Read all processes. Processes that exit while they are read are left out.
      * 
      * `read_dir`: The directory to read.
      * 
@@ -126,8 +133,11 @@ impl ProcsProcess {
                     if ProcsProcess::is_process_directory(&starts_with_number_regexp, path) {                          
                         let path_buffer = path.path();
                         let use_dir = path_buffer.to_str().ok_or(CommonLibError::new("Error reading path"))?;
-                        let process = ProcsProcess::get_process_status_with_dir(use_dir)?;
-                        processes.push(process);
+                        match ProcsProcess::get_process_status_with_dir(use_dir) {
+                            Ok(process) => processes.push(process),
+                            Err(_) if !path_buffer.exists() => {},
+                            Err(err) => return Err(err),
+                        }
                     }           
                 },
                 Err(err) => {
@@ -228,8 +238,8 @@ impl ProcsProcess {
      * 
      */
     fn loop_child_paths(paths: ReadDir, processes: &mut Vec<ProcsProcess>) -> Result<(), CommonLibError> {
+        let starts_with_number_regexp = Regex::new(r"^[0-9]+$").map_err(|err|CommonLibError::new(format!("Error creating regexp: err: {err:?}").as_str()))?;
         for path in paths {
-            let starts_with_number_regexp = Regex::new(r"^[0-9]+$").map_err(|err|CommonLibError::new(format!("Error creating regexp: err: {err:?}").as_str()))?;
             match &path {
                 Ok(path) => {
                     ProcsProcess::add_child_process(&starts_with_number_regexp, path, processes)?;
@@ -283,7 +293,9 @@ impl ProcsProcess {
         let file = File::open(path);
         match file {
             Ok(file) => {
-                ProcsProcess::get_process_status_from_file(file)
+                let mut process = ProcsProcess::get_process_status_from_file(file)?;
+                process.command_line = ProcsProcess::get_command_line(proc_dir);
+                Ok(process)
             },
             Err(err) => {
                 error!("Error reading status: {err:?}");
@@ -323,9 +335,26 @@ impl ProcsProcess {
             ProcsProcess::get_state(parts.get("State")),
             parts.get("Threads").and_then(|f| u32::from_str(f).ok()),
             ProcsProcess::get_groups(parts.get("Groups")),
+            None,
         ))
     }
 
+    /**
+     * Get the command line of a process. The arguments in the cmdline file are separated by null characters.
+     * 
+     * `proc_dir`: The directory of the process.
+     * 
+     * Returns the command line, or None if it is empty or can not be read.
+     */
+    fn get_command_line(proc_dir: &str) -> Option<String> {
+        let command_line = fs::read(proc_dir.to_string() + "/cmdline").ok()?;
+        let arguments: Vec<String> = command_line.split(|byte| *byte == 0)
+            .filter(|argument| !argument.is_empty())
+            .map(|argument| String::from_utf8_lossy(argument).to_string())
+            .collect();
+        (!arguments.is_empty()).then(|| arguments.join(" "))
+    }
+
     /**
      * Get the groups.
      * 
@@ -461,6 +490,7 @@ pub enum ProcessState {
 }
 
 #[cfg(test)]
+#[allow(clippy::get_first)]
 mod test {
 
     use std::vec;
@@ -499,8 +529,16 @@ mod test {
         assert_eq!(&process.state, &Some(ProcessState::InterruptableSleep));
         assert_eq!(&process.threads, &Some(1));
         assert_eq!(&process.groups, &Some(vec!["4".to_string(), "24".to_string(), "27".to_string(), "30".to_string(), "46".to_string(), "100".to_string(), "119".to_string(), "129".to_string(), "1000".to_string()]));
+        assert_eq!(&process.command_line, &Some("nginx: worker process --config /etc/nginx/nginx.conf".to_string()));
     }    
 
+    #[test]
+    fn test_get_command_line() {
+        let process = ProcsProcess::get_process(std::process::id()).unwrap();
+        assert!(process.command_line.is_some());
+        assert_eq!(ProcsProcess::get_command_line("resources/test/processes/2914/task/54112"), None);
+    }
+
     #[test]
     fn test_read_children() {
         let processes = ProcsProcess::read_process_threads(2914, "resources/test/processes");
@@ -0,0 +1,292 @@
+use std::{fs::File, io::{BufRead, BufReader}};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::common::CommonLibError;
+
+/**
+ * The time a cpu has spent in each state since boot, from a cpu line of /proc/stat. The times are in clock ticks,
+ * usually hundredths of a second.
+ */
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcsCpuTimes {
+    /// The name of the cpu, cpu for all cpus or cpu0, cpu1... for a single core.
+    pub name: String,
+    /// Time spent in user mode.
+    pub user: u64,
+    /// Time spent in user mode with low priority.
+    pub nice: u64,
+    /// Time spent in system mode.
+    pub system: u64,
+    /// Time spent idle.
+    pub idle: u64,
+    /// Time spent idle waiting for I/O to complete.
+    pub iowait: u64,
+    /// Time spent servicing interrupts.
+    pub irq: u64,
+    /// Time spent servicing softirqs.
+    pub softirq: u64,
+    /// Time stolen by the hypervisor for other virtual machines.
+    pub steal: u64,
+}
+
+/**
+ * CPU times from /proc/stat, in total and for each core.
+ */
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcsCpuStat {
+    /// The times of all cpus together.
+    pub total: ProcsCpuTimes,
+    /// The times of each core.
+    pub cores: Vec<ProcsCpuTimes>,
+}
+
+/**
+ * CPU usage in percent of the time between two samples of /proc/stat.
+ */
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcsCpuUsage {
+    /// The usage of all cpus together.
+    pub total: CpuUsage,
+    /// The usage of each core.
+    pub cores: Vec<CpuUsage>,
+}
+
+/**
+ * CPU usage of a single cpu in percent.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuUsage {
+    /// The name of the cpu, cpu for all cpus or cpu0, cpu1... for a single core.
+    pub name: String,
+    /// Percentage in user mode, including low priority.
+    pub user: f64,
+    /// Percentage in system mode, including interrupts.
+    pub system: f64,
+    /// Percentage idle waiting for I/O.
+    pub iowait: f64,
+    /// Percentage stolen by the hypervisor.
+    pub steal: f64,
+    /// Percentage idle.
+    pub idle: f64,
+}
+
+impl ProcsCpuTimes {
+
+    /**
+     * Create new `ProcsCpuTimes`.
+     *
+     * ```
+     * use monitoring_agent_lib::proc::stat::ProcsCpuTimes;
+     * ProcsCpuTimes::new("cpu0", [263_556, 0, 26_690, 306_604, 1833, 0, 21, 6531]);
+     * ```
+     *
+     * `name`: The name of the cpu.
+     * `times`: The user, nice, system, idle, iowait, irq, softirq and steal times, in the order of /proc/stat.
+     *
+     * Returns new `ProcsCpuTimes`.
+     */
+    #[must_use]
+    pub fn new(name: &str, times: [u64; 8]) -> ProcsCpuTimes {
+        let [user, nice, system, idle, iowait, irq, softirq, steal] = times;
+        ProcsCpuTimes { name: name.to_string(), user, nice, system, idle, iowait, irq, softirq, steal }
+    }
+
+    /**
+     * Parse a cpu line of /proc/stat. Guest time is already counted as user time and is ignored.
+     *
+     * `line`: The line.
+     *
+     * Returns the times or none if the line is not a cpu line.
+     */
+    fn parse(line: &str) -> Option<ProcsCpuTimes> {
+        let mut columns = line.split_whitespace();
+        let name = columns.next().filter(|name| name.starts_with("cpu"))?;
+        let mut times = [0u64; 8];
+        for (index, column) in columns.take(8).enumerate() {
+            times[index] = column.parse().ok()?;
+        }
+        Some(ProcsCpuTimes::new(name, times))
+    }
+
+    /**
+     * Calculate the usage between an earlier sample and this one. Counters that went backwards, which happens for
+     * iowait on some kernels, count as zero.
+     *
+     * `previous`: The earlier sample.
+     *
+     * Returns the usage in percent. A cpu without any ticks between the samples is reported as idle.
+     */
+    #[allow(clippy::cast_precision_loss)]
+    fn get_usage(&self, previous: &ProcsCpuTimes) -> CpuUsage {
+        let user = self.user.saturating_sub(previous.user) + self.nice.saturating_sub(previous.nice);
+        let system = self.system.saturating_sub(previous.system)
+            + self.irq.saturating_sub(previous.irq)
+            + self.softirq.saturating_sub(previous.softirq);
+        let iowait = self.iowait.saturating_sub(previous.iowait);
+        let steal = self.steal.saturating_sub(previous.steal);
+        let idle = self.idle.saturating_sub(previous.idle);
+        let total = user + system + iowait + steal + idle;
+        if total == 0 {
+            return CpuUsage { name: self.name.clone(), user: 0.0, system: 0.0, iowait: 0.0, steal: 0.0, idle: 100.0 };
+        }
+        let percent = |ticks: u64| ticks as f64 / total as f64 * 100f64;
+        CpuUsage {
+            name: self.name.clone(),
+            user: percent(user),
+            system: percent(system),
+            iowait: percent(iowait),
+            steal: percent(steal),
+            idle: percent(idle),
+        }
+    }
+}
+
+impl ProcsCpuStat {
+
+    /**
+     * Get the current cpu times.
+     *
+     * ```
+     * use monitoring_agent_lib::proc::stat::ProcsCpuStat;
+     * ProcsCpuStat::get_cpu_stat();
+     * ```
+     *
+     * Returns the cpu times or an error.
+     *
+     * # Errors
+     *  - If there is an error reading the stat file.
+     *  - If there is an error reading a line from the stat file.
+     *  - If the stat file has no total cpu line.
+     */
+    #[tracing::instrument(level = "debug")]
+    pub fn get_cpu_stat() -> Result<ProcsCpuStat, CommonLibError> {
+        ProcsCpuStat::read_cpu_stat("/proc/stat")
+    }
+
+    /**
+     * Read the stat file.
+     *
+     * `file`: The file to read.
+     *
+     * Returns the cpu times or an error.
+     *
+     * # Errors
+     *  - If there is an error reading the stat file.
+     *  - If there is an error reading a line from the stat file.
+     *  - If the stat file has no total cpu line.
+     */
+    fn read_cpu_stat(file: &str) -> Result<ProcsCpuStat, CommonLibError> {
+        let stat_file = File::open(file).map_err(|err| {
+            error!("Error reading stat: {err:?}");
+            CommonLibError::new(format!("Error reading stat: {err:?}").as_str())
+        })?;
+        let mut total = None;
+        let mut cores = Vec::new();
+        for line in BufReader::new(stat_file).lines() {
+            let line = line.map_err(|err| CommonLibError::new(format!("Error reading line: {err:?}").as_str()))?;
+            match ProcsCpuTimes::parse(&line) {
+                Some(times) if times.name == "cpu" => total = Some(times),
+                Some(times) => cores.push(times),
+                None => {}
+            }
+        }
+        let total = total.ok_or_else(|| CommonLibError::new("Error parsing stat: no cpu line"))?;
+        Ok(ProcsCpuStat { total, cores })
+    }
+
+    /**
+     * Calculate the cpu usage between an earlier sample and this one. Cores are matched by name, so a core that was
+     * taken offline or brought online between the samples is left out.
+     *
+     * ```
+     * use monitoring_agent_lib::proc::stat::ProcsCpuStat;
+     * let previous = ProcsCpuStat::get_cpu_stat().unwrap();
+     * let current = ProcsCpuStat::get_cpu_stat().unwrap();
+     * current.get_usage(&previous);
+     * ```
+     *
+     * `previous`: The earlier sample.
+     *
+     * Returns the cpu usage in percent.
+     */
+    #[must_use]
+    pub fn get_usage(&self, previous: &ProcsCpuStat) -> ProcsCpuUsage {
+        let cores = self.cores.iter()
+            .filter_map(|core| {
+                previous.cores.iter()
+                    .find(|previous_core| previous_core.name == core.name)
+                    .map(|previous_core| core.get_usage(previous_core))
+            })
+            .collect();
+        ProcsCpuUsage { total: self.total.get_usage(&previous.total), cores }
+    }
+}
+
+impl CpuUsage {
+
+    /**
+     * Calculate the percentage of the cpu in use, i.e. neither idle nor waiting for I/O.
+     *
+     * Returns: Percentage
+     */
+    #[must_use]
+    pub fn get_percent_used(&self) -> f64 {
+        (100f64 - self.idle - self.iowait).max(0f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_current() {
+        let stat = ProcsCpuStat::get_cpu_stat().unwrap();
+        assert_eq!(stat.total.name, "cpu");
+        assert!(!stat.cores.is_empty());
+    }
+
+    #[test]
+    fn test_read_predefined_stat() {
+        let stat = ProcsCpuStat::read_cpu_stat("resources/test/test_stat").unwrap();
+        assert_eq!(stat.total, ProcsCpuTimes::new("cpu", [2000, 100, 600, 6800, 300, 50, 50, 100]));
+        assert_eq!(stat.cores.len(), 2);
+        assert_eq!(stat.cores[1], ProcsCpuTimes::new("cpu1", [1000, 50, 300, 3400, 150, 25, 25, 50]));
+        assert!(ProcsCpuStat::read_cpu_stat("resources/test/missing_stat").is_err());
+        assert!(ProcsCpuStat::read_cpu_stat("resources/test/test_loadavg").is_err());
+    }
+
+    #[test]
+    fn test_get_usage() {
+        let previous = ProcsCpuStat {
+            total: ProcsCpuTimes::new("cpu", [1000, 0, 500, 8000, 100, 0, 0, 0]),
+            cores: vec![
+                ProcsCpuTimes::new("cpu0", [500, 0, 250, 4000, 50, 0, 0, 0]),
+                ProcsCpuTimes::new("cpu1", [500, 0, 250, 4000, 50, 0, 0, 0]),
+            ],
+        };
+        let current = ProcsCpuStat {
+            total: ProcsCpuTimes::new("cpu", [1500, 100, 600, 8200, 150, 25, 25, 0]),
+            cores: vec![
+                ProcsCpuTimes::new("cpu0", [1100, 100, 375, 4000, 50, 25, 25, 0]),
+                ProcsCpuTimes::new("cpu2", [0, 0, 0, 100, 0, 0, 0, 0]),
+            ],
+        };
+        let usage = current.get_usage(&previous);
+        assert_eq!(usage.total, CpuUsage { name: "cpu".to_string(), user: 60.0, system: 15.0, iowait: 5.0, steal: 0.0, idle: 20.0 });
+        assert!((usage.total.get_percent_used() - 75.0).abs() < 1e-9);
+        assert_eq!(usage.cores.len(), 1);
+        assert_eq!(usage.cores[0].name, "cpu0");
+        assert!((usage.cores[0].get_percent_used() - 100.0).abs() < 1e-9);
+
+        let idle = previous.get_usage(&previous);
+        assert_eq!(idle.total.idle, 100.0);
+        assert_eq!(idle.total.get_percent_used(), 0.0);
+    }
+}